@@ -0,0 +1,343 @@
+use std::fs::File;
+use std::io::Write;
+use std::process::ExitCode;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use clap::{Parser, ValueEnum};
+
+use sqlprof_core::capture::{
+    self, CaptureFileOptions, CaptureFilter, CaptureOptions, CaptureSource, CaptureSourceKind, InFlightTracker,
+    PollPacer,
+};
+use sqlprof_core::db::{self, ConnectionConfig};
+use sqlprof_core::export::{EventWriter, ExportFormat, ExportRow};
+use sqlprof_core::fingerprint;
+
+/// Capture SQL Server queries without the desktop UI.
+///
+/// Events are written as they complete, one row per query, in the same
+/// columns as the app's exports. Statement and batch starts are not written
+/// separately; queries still running when the capture ends are written with
+/// status `unknown`.
+#[derive(Debug, Parser)]
+#[command(name = "sqlprof-cli", version)]
+struct Args {
+    /// Server as `host`, `host,port` or `host\instance`.
+    #[arg(short = 'S', long)]
+    server: String,
+
+    /// SQL login name.
+    #[arg(short = 'U', long, default_value = "sa")]
+    user: String,
+
+    /// SQL login password.
+    #[arg(short = 'P', long, env = "SQLPROF_PASSWORD", hide_env_values = true)]
+    password: String,
+
+    /// Database to connect to; the server default when omitted.
+    #[arg(short = 'd', long, default_value = "")]
+    database: String,
+
+    #[arg(long, value_enum, default_value_t = Encrypt::Mandatory)]
+    encrypt: Encrypt,
+
+    /// Accept the server certificate without validating it.
+    #[arg(long)]
+    trust_cert: bool,
+
+    #[arg(long, value_enum, default_value_t = Source::SqlTrace)]
+    source: Source,
+
+    /// Also capture each statement inside batches and RPCs.
+    #[arg(long)]
+    statement_level: bool,
+
+    /// Only capture this database (LIKE pattern).
+    #[arg(long, value_name = "PATTERN")]
+    filter_database: Option<String>,
+
+    /// Only capture this login (LIKE pattern).
+    #[arg(long, value_name = "PATTERN")]
+    filter_login: Option<String>,
+
+    /// Only capture this client host (LIKE pattern).
+    #[arg(long, value_name = "PATTERN")]
+    filter_host: Option<String>,
+
+    /// Only capture this application name (LIKE pattern).
+    #[arg(long, value_name = "PATTERN")]
+    filter_program: Option<String>,
+
+    /// Only capture queries that ran at least this long.
+    #[arg(long, value_name = "MS")]
+    min_duration_ms: Option<i64>,
+
+    /// Only capture queries with at least this many logical reads.
+    #[arg(long, value_name = "READS")]
+    min_logical_reads: Option<i64>,
+
+    /// Only capture queries whose text matches (LIKE pattern).
+    #[arg(long, value_name = "PATTERN")]
+    text_like: Option<String>,
+
+    /// Server-side directory for trace and event files; the error log
+    /// directory when omitted.
+    #[arg(long, value_name = "DIR")]
+    file_directory: Option<String>,
+
+    /// Maximum size of each trace or event file [default: 50].
+    #[arg(long, value_name = "MB")]
+    max_file_mb: Option<i64>,
+
+    /// Stop the trace when its file is full instead of starting a new one.
+    #[arg(long)]
+    no_rollover: bool,
+
+    /// How many rolled over files to keep [default: 20].
+    #[arg(long, value_name = "COUNT", conflicts_with = "no_rollover")]
+    max_files: Option<i32>,
+
+    /// Stop at this time (RFC 3339, e.g. 2024-05-01T18:00:00+02:00).
+    #[arg(long, value_name = "TIME")]
+    stop_at: Option<String>,
+
+    /// File to write to; standard output when omitted.
+    #[arg(short = 'o', long)]
+    output: Option<String>,
+
+    #[arg(short = 'f', long, value_enum, default_value_t = Format::Ndjson)]
+    format: Format,
+
+    /// Stop after this many seconds.
+    #[arg(long, value_name = "SECONDS")]
+    duration: Option<u64>,
+
+    /// Stop after writing this many events.
+    #[arg(long, value_name = "COUNT")]
+    max_events: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Encrypt {
+    Mandatory,
+    Optional,
+    Strict,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Format {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Source {
+    SqlTrace,
+    XeRingBuffer,
+    XeEventFile,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start runtime: {e}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(capture(args)) {
+        Ok(count) => {
+            eprintln!("Captured {count} events");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+async fn capture(args: Args) -> Result<u64, String> {
+    let config = ConnectionConfig {
+        server_name: args.server.clone(),
+        authentication: "sql".into(),
+        username: args.user.clone(),
+        password: args.password.clone(),
+        database: args.database.clone(),
+        encrypt: match args.encrypt {
+            Encrypt::Mandatory => "mandatory",
+            Encrypt::Optional => "optional",
+            Encrypt::Strict => "strict",
+        }
+        .into(),
+        trust_cert: args.trust_cert,
+    };
+    let kind = match args.source {
+        Source::SqlTrace => CaptureSourceKind::SqlTrace,
+        Source::XeRingBuffer => CaptureSourceKind::XeRingBuffer,
+        Source::XeEventFile => CaptureSourceKind::XeEventFile,
+    };
+    let options = CaptureOptions {
+        statement_level: args.statement_level,
+        filter: CaptureFilter {
+            database_name: args.filter_database.clone(),
+            login_name: args.filter_login.clone(),
+            host_name: args.filter_host.clone(),
+            program_name: args.filter_program.clone(),
+            min_duration_ms: args.min_duration_ms,
+            min_logical_reads: args.min_logical_reads,
+            text_like: args.text_like.clone(),
+        },
+        files: CaptureFileOptions {
+            directory: args.file_directory.clone(),
+            max_file_mb: args.max_file_mb,
+            rollover: !args.no_rollover,
+            max_files: args.max_files.or(CaptureFileOptions::default().max_files),
+        },
+        stop_at: args.stop_at.clone(),
+    };
+
+    let output: Box<dyn Write + Send> = match &args.output {
+        Some(path) => Box::new(File::create(path).map_err(|e| format!("Failed to create {path}: {e}"))?),
+        None => Box::new(std::io::stdout()),
+    };
+    let format = match args.format {
+        Format::Csv => ExportFormat::Csv,
+        Format::Ndjson => ExportFormat::Ndjson,
+        Format::Parquet => ExportFormat::Parquet,
+    };
+    let mut writer = EventWriter::new(output, format)?;
+
+    let mut source = capture::create_source(kind, &options, Some(&config))?;
+    if let Err(e) = source.start().await {
+        let _ = source.cleanup().await;
+        return Err(e);
+    }
+    eprintln!("Capturing from {}; press Ctrl+C to stop", args.server);
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                running.store(false, Ordering::Release);
+            }
+        });
+    }
+
+    let stop_at = options.stop_deadline()?;
+    let result = capture_loop(&mut *source, &mut writer, &args, stop_at, &running).await;
+
+    // Always drop the trace or XE session, even after an error.
+    let _ = source.stop().await;
+    let cleanup = source.cleanup().await;
+    let count = result?;
+    writer.finish()?;
+    cleanup?;
+    Ok(count)
+}
+
+async fn capture_loop<W: Write + Send>(
+    source: &mut dyn CaptureSource,
+    writer: &mut EventWriter<W>,
+    args: &Args,
+    stop_at: Option<chrono::DateTime<chrono::Utc>>,
+    running: &AtomicBool,
+) -> Result<u64, String> {
+    let deadline = args.duration.map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs));
+    let limit = args.max_events.unwrap_or(u64::MAX);
+    let mut pacer = PollPacer::default();
+    let mut delay = Duration::ZERO;
+    let mut behind = false;
+    let mut in_flight = InFlightTracker::default();
+    let mut count = 0;
+    let mut reconnected_after: Option<String> = None;
+
+    while count < limit
+        && running.load(Ordering::Acquire)
+        && deadline.is_none_or(|d| tokio::time::Instant::now() < d)
+        && stop_at.is_none_or(|t| chrono::Utc::now() < t)
+    {
+        capture::wait_while_running(running, delay).await;
+
+        let polled = tokio::time::timeout(capture::POLL_TIMEOUT, source.poll_batch())
+            .await
+            .unwrap_or_else(|_| Err(format!("Polling failed: {}", db::CONNECTION_TIMED_OUT)));
+        let events = match polled {
+            Ok(Some(events)) => {
+                reconnected_after = None;
+                events
+            }
+            Ok(None) => break,
+            // Failing the same way straight after reconnecting means the
+            // connection was not the problem.
+            Err(e) if db::is_connection_error(&e) && reconnected_after.as_deref() != Some(e.as_str()) => {
+                let reconnected = capture::reconnect(source, running, e.clone(), |attempt, error| {
+                    eprintln!("{error}; reconnecting (attempt {attempt})");
+                })
+                .await;
+                match reconnected {
+                    Ok(()) => {
+                        eprintln!("Reconnected; resuming the capture");
+                        reconnected_after = Some(e);
+                        continue;
+                    }
+                    Err(Some(e)) => return Err(e),
+                    Err(None) => break,
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        delay = pacer.next_delay(events.len(), source.behind());
+        if source.behind() != behind {
+            behind = source.behind();
+            if behind {
+                eprintln!("Falling behind: the server produces events faster than they are read");
+            } else {
+                eprintln!("Caught up");
+            }
+        }
+        let now = chrono::Utc::now().to_rfc3339();
+        for mut event in events {
+            if count >= limit {
+                break;
+            }
+            match event.event_name.as_str() {
+                // The report XML is in `sql_text`; written as is.
+                "deadlock_graph" | "blocked_process_report" => {
+                    event.captured_at = now.clone();
+                    writer.write(ExportRow::from(event))?;
+                    count += 1;
+                    continue;
+                }
+                _ => fingerprint::apply(&mut event),
+            }
+            for mut event in in_flight.track(event) {
+                // Only finished requests; the completion carries the same id.
+                if event.event_status == "running" || count >= limit {
+                    continue;
+                }
+                event.captured_at = now.clone();
+                writer.write(ExportRow::from(event))?;
+                count += 1;
+            }
+        }
+        writer.flush()?;
+    }
+
+    if count < limit {
+        let now = chrono::Utc::now().to_rfc3339();
+        for mut event in in_flight.abandon_all().into_iter().take((limit - count) as usize) {
+            event.captured_at = now.clone();
+            writer.write(ExportRow::from(event))?;
+            count += 1;
+        }
+    }
+    Ok(count)
+}
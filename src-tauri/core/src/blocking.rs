@@ -0,0 +1,189 @@
+use serde::{Deserialize, Serialize};
+
+use crate::db::SqlClient;
+use crate::deadlock::{attr, child, text};
+
+const BLOCKED_PROCESS_THRESHOLD_GET: &str = "
+SELECT CAST(value_in_use AS int) AS threshold
+FROM sys.configurations
+WHERE name = N'blocked process threshold (s)';
+";
+
+const BLOCKED_PROCESS_THRESHOLD_SET: &str = "
+DECLARE @show_advanced int =
+    (SELECT CAST(value_in_use AS int) FROM sys.configurations WHERE name = N'show advanced options');
+
+IF @show_advanced = 0
+BEGIN
+    EXEC sp_configure N'show advanced options', 1;
+    RECONFIGURE;
+END
+
+EXEC sp_configure N'blocked process threshold (s)', @P1;
+RECONFIGURE;
+
+IF @show_advanced = 0
+BEGIN
+    EXEC sp_configure N'show advanced options', 0;
+    RECONFIGURE;
+END
+";
+
+/// One `Blocked process report`: a single blocker/blocked pair as seen when
+/// the server's blocked process monitor fired.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockedProcessReport {
+    pub id: String,
+    pub event_time: String,
+    pub captured_at: String,
+    pub blocked: BlockingProcess,
+    pub blocker: BlockingProcess,
+    pub xml: String,
+    /// Why the XML could not be parsed; both processes are empty then.
+    #[serde(default)]
+    pub parse_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlockingProcess {
+    pub session_id: i32,
+    pub status: String,
+    pub database_name: String,
+    pub login_name: String,
+    pub host_name: String,
+    pub program_name: String,
+    pub isolation_level: String,
+    pub transaction_count: i32,
+    /// Only set on the blocked side.
+    pub lock_mode: String,
+    pub wait_resource: String,
+    pub wait_time_ms: i64,
+    pub input_buffer: String,
+}
+
+pub fn parse_blocked_process_report(xml: &str) -> Result<BlockedProcessReport, String> {
+    let document =
+        roxmltree::Document::parse(xml).map_err(|e| format!("Invalid blocked process report XML: {e}"))?;
+    let Some(report) = document.descendants().find(|n| n.has_tag_name("blocked-process-report")) else {
+        return Err("Blocked process report XML has no <blocked-process-report> element".into());
+    };
+
+    let process = |side: &str| {
+        child(report, side)
+            .and_then(|n| child(n, "process"))
+            .map(parse_process)
+            .unwrap_or_default()
+    };
+
+    Ok(BlockedProcessReport {
+        blocked: process("blocked-process"),
+        blocker: process("blocking-process"),
+        xml: xml.to_string(),
+        ..BlockedProcessReport::default()
+    })
+}
+
+fn parse_process(node: roxmltree::Node) -> BlockingProcess {
+    BlockingProcess {
+        session_id: attr(node, "spid").parse().unwrap_or(0),
+        status: attr(node, "status"),
+        database_name: attr(node, "currentdbname"),
+        login_name: attr(node, "loginname"),
+        host_name: attr(node, "hostname"),
+        program_name: attr(node, "clientapp"),
+        isolation_level: attr(node, "isolationlevel"),
+        transaction_count: attr(node, "trancount").parse().unwrap_or(0),
+        lock_mode: attr(node, "lockMode"),
+        wait_resource: attr(node, "waitresource"),
+        wait_time_ms: attr(node, "waittime").parse().unwrap_or(0),
+        input_buffer: child(node, "inputbuf").map(text).unwrap_or_default(),
+    }
+}
+
+/// Reads `blocked process threshold (s)`; 0 means the server never produces
+/// blocked process reports.
+pub async fn get_blocked_process_threshold(client: &mut SqlClient) -> Result<i32, String> {
+    let rows = client
+        .simple_query(BLOCKED_PROCESS_THRESHOLD_GET)
+        .await
+        .map_err(|e| format!("Failed to read blocked process threshold: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to read blocked process threshold: {e}"))?;
+
+    rows.iter()
+        .flatten()
+        .find_map(|row| row.get::<i32, _>("threshold"))
+        .ok_or_else(|| "Blocked process threshold is not available on this server".into())
+}
+
+pub async fn set_blocked_process_threshold(client: &mut SqlClient, seconds: i32) -> Result<(), String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(BLOCKED_PROCESS_THRESHOLD_SET);
+    query.bind(seconds);
+
+    query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to set blocked process threshold: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to confirm blocked process threshold: {e}"))?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &str = r#"<blocked-process-report monitorLoop="4096">
+  <blocked-process>
+    <process id="process3c" waitresource="OBJECT: 5:1237579447:0" waittime="20374" spid="58" status="suspended" trancount="1" lockMode="IS" isolationlevel="read committed (2)" clientapp="report" hostname="bi01" loginname="reader" currentdbname="Shop">
+      <executionStack><frame line="1" /></executionStack>
+      <inputbuf>SELECT COUNT(*) FROM Orders</inputbuf>
+    </process>
+  </blocked-process>
+  <blocking-process>
+    <process status="sleeping" spid="61" trancount="2" currentdbname="Shop" loginname="app_user">
+      <inputbuf>BEGIN TRAN; UPDATE Orders SET Status = 2</inputbuf>
+    </process>
+  </blocking-process>
+</blocked-process-report>"#;
+
+    #[test]
+    fn both_sides_are_read() {
+        let report = parse_blocked_process_report(REPORT).unwrap();
+        assert_eq!(report.blocked.session_id, 58);
+        assert_eq!(report.blocked.status, "suspended");
+        assert_eq!(report.blocked.lock_mode, "IS");
+        assert_eq!(report.blocked.wait_time_ms, 20374);
+        assert_eq!(report.blocked.wait_resource, "OBJECT: 5:1237579447:0");
+        assert_eq!(report.blocked.input_buffer, "SELECT COUNT(*) FROM Orders");
+        assert_eq!(report.blocker.session_id, 61);
+        assert_eq!(report.blocker.transaction_count, 2);
+        assert_eq!(report.blocker.wait_time_ms, 0);
+        assert_eq!(report.xml, REPORT);
+        assert!(report.parse_error.is_none());
+    }
+
+    #[test]
+    fn report_inside_an_event_is_found() {
+        let xml = format!(r#"<event name="blocked_process_report"><data name="blocked_process"><value>{REPORT}</value></data></event>"#);
+        assert_eq!(parse_blocked_process_report(&xml).unwrap().blocked.session_id, 58);
+    }
+
+    #[test]
+    fn malformed_reports_fail() {
+        assert!(
+            parse_blocked_process_report("<blocked-process-report>")
+                .unwrap_err()
+                .starts_with("Invalid blocked process report XML")
+        );
+        assert_eq!(
+            parse_blocked_process_report("<event />").unwrap_err(),
+            "Blocked process report XML has no <blocked-process-report> element"
+        );
+    }
+}
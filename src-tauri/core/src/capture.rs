@@ -0,0 +1,787 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use crate::db::{self, ConnectionConfig};
+use crate::profiler::QueryEvent;
+use crate::trace::SqlTraceSource;
+use crate::trace_file::{ServerTraceFileSource, TraceFileSource};
+use crate::xel_file::{ServerXelFileSource, XelFileSource};
+use crate::xevents::{XeSource, XeTarget};
+
+pub(crate) const MIN_TIMESTAMP: &str = "1900-01-01T00:00:00.000";
+
+/// A poll that takes longer than this is taken as a lost connection; a
+/// dropped VPN tends to leave the socket silent rather than closed.
+pub const POLL_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Rows a live source reads per poll. A poll that returns this many has
+/// more waiting, see `CaptureSource::behind`.
+pub(crate) const POLL_ROW_LIMIT: i32 = 5000;
+
+const POLL_INTERVAL_INITIAL: Duration = Duration::from_millis(300);
+const POLL_INTERVAL_MIN: Duration = Duration::from_millis(100);
+const POLL_INTERVAL_MAX: Duration = Duration::from_secs(2);
+
+pub(crate) const RECONNECT_BACKOFF_INITIAL: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+const RECONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// A mechanism that produces `QueryEvent`s for the profiler loop.
+///
+/// The loop calls `start` once, then `poll_batch` on every tick until the
+/// capture is stopped or the source reports that it is exhausted, and finally
+/// `stop` followed by `cleanup`. After a lost connection it calls `reconnect`
+/// until that succeeds, then carries on polling. Sources own whatever
+/// connections they need.
+#[async_trait]
+pub trait CaptureSource: Send {
+    async fn start(&mut self) -> Result<(), String>;
+
+    /// Returns the events captured since the previous call, or `None` once the
+    /// source has nothing more to deliver.
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String>;
+
+    /// Stops producing new events without releasing server-side resources.
+    async fn stop(&mut self) -> Result<(), String>;
+
+    /// Releases server-side resources and connections. Must be safe to call
+    /// after a failed `start` or `stop`.
+    async fn cleanup(&mut self) -> Result<(), String>;
+
+    /// Replaces the connection after `poll_batch` failed because it was lost.
+    /// The server-side trace or session keeps collecting meanwhile, so polling
+    /// resumes from the source's watermark without gaps or duplicates. Fails
+    /// if the trace or session is gone, e.g. after a server restart.
+    async fn reconnect(&mut self) -> Result<(), String> {
+        Err("This capture source can't reconnect".into())
+    }
+
+    /// Whether the last `poll_batch` stopped at its row limit with more
+    /// events waiting, i.e. the capture is falling behind the server.
+    fn behind(&self) -> bool {
+        false
+    }
+}
+
+/// Paces polling: straight back after a poll that fell behind, faster while
+/// events keep coming, and slower, up to `POLL_INTERVAL_MAX`, while idle.
+#[derive(Debug)]
+pub struct PollPacer {
+    interval: Duration,
+}
+
+impl Default for PollPacer {
+    fn default() -> Self {
+        Self {
+            interval: POLL_INTERVAL_INITIAL,
+        }
+    }
+}
+
+impl PollPacer {
+    /// The wait before the next poll, given what the last one returned.
+    pub fn next_delay(&mut self, event_count: usize, behind: bool) -> Duration {
+        if behind {
+            self.interval = POLL_INTERVAL_MIN;
+            return Duration::ZERO;
+        }
+        self.interval = if event_count > 0 {
+            (self.interval / 2).max(POLL_INTERVAL_MIN)
+        } else {
+            (self.interval * 3 / 2).min(POLL_INTERVAL_MAX)
+        };
+        self.interval
+    }
+}
+
+/// Sleeps for `delay`, returning early once `run_flag` is cleared so a stop
+/// isn't held up by a long idle interval.
+pub async fn wait_while_running(run_flag: &AtomicBool, delay: Duration) {
+    let deadline = tokio::time::Instant::now() + delay;
+    while run_flag.load(Ordering::Acquire) {
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            break;
+        }
+        tokio::time::sleep((deadline - now).min(Duration::from_millis(100))).await;
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum CaptureSourceKind {
+    #[default]
+    SqlTrace,
+    XeRingBuffer,
+    XeEventFile,
+    /// Replays a `.trc` file (and its rollover files), parsed here unless
+    /// `on_server` asks the connected server to read it.
+    TraceFile {
+        path: String,
+        #[serde(default)]
+        on_server: bool,
+    },
+    /// Replays an XE `event_file` target (`.xel`), decoded here unless
+    /// `on_server` asks the connected server to read it.
+    XelFile {
+        path: String,
+        #[serde(default)]
+        on_server: bool,
+    },
+}
+
+impl CaptureSourceKind {
+    /// File replays, as opposed to captures from a live server.
+    pub fn is_offline(&self) -> bool {
+        self.file_path().is_some()
+    }
+
+    /// The file a replay reads.
+    pub fn file_path(&self) -> Option<&str> {
+        match self {
+            Self::TraceFile { path, .. } | Self::XelFile { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Per-capture settings chosen in the toolbar. Offline sources replay
+/// whatever the file contains and ignore them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureOptions {
+    /// Also capture each statement inside a batch or RPC. Expensive on busy
+    /// servers, so it is off unless asked for.
+    pub statement_level: bool,
+    pub filter: CaptureFilter,
+    pub files: CaptureFileOptions,
+    /// When the capture stops by itself, as an RFC 3339 timestamp.
+    pub stop_at: Option<String>,
+}
+
+impl CaptureOptions {
+    /// Checks the settings a live capture can't start with.
+    pub fn validate(&self) -> Result<(), String> {
+        self.files.validate()?;
+        if let Some(stop_at) = self.stop_deadline()? {
+            if stop_at <= chrono::Utc::now() {
+                return Err("The stop time has already passed".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// `stop_at` parsed, or `None` when the capture runs until stopped.
+    pub fn stop_deadline(&self) -> Result<Option<chrono::DateTime<chrono::Utc>>, String> {
+        let Some(stop_at) = self.stop_at.as_deref().map(str::trim).filter(|v| !v.is_empty()) else {
+            return Ok(None);
+        };
+        chrono::DateTime::parse_from_rfc3339(stop_at)
+            .map(|t| Some(t.with_timezone(&chrono::Utc)))
+            .map_err(|e| format!("Invalid stop time '{stop_at}': {e}"))
+    }
+
+    /// The stop time as the server-side scripts take it: UTC, in the
+    /// `CONVERT` style 126 format.
+    pub(crate) fn stop_at_utc(&self) -> Option<String> {
+        self.stop_deadline()
+            .ok()
+            .flatten()
+            .map(|t| t.format("%Y-%m-%dT%H:%M:%S").to_string())
+    }
+}
+
+/// Where SQL Trace and the XE `event_file` target write on the server, and
+/// how large the files get. By default, 50 MB files next to the error log,
+/// rolling over and keeping the last 20 (1 GB in all). Live SQL Trace polls
+/// re-read the file being written, so smaller files keep them cheaper.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureFileOptions {
+    /// A directory on the server; the error log directory when unset.
+    pub directory: Option<String>,
+    /// `DEFAULT_MAX_FILE_MB` when unset.
+    pub max_file_mb: Option<i64>,
+    /// Start a new file when one is full. Without it, SQL Trace stops
+    /// collecting once its file is full.
+    pub rollover: bool,
+    /// With rollover, how many files to keep; the oldest is deleted when
+    /// another is started. All of them when unset.
+    pub max_files: Option<i32>,
+}
+
+impl Default for CaptureFileOptions {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            max_file_mb: None,
+            rollover: true,
+            max_files: Some(20),
+        }
+    }
+}
+
+impl CaptureFileOptions {
+    pub const DEFAULT_MAX_FILE_MB: i64 = 50;
+
+    fn validate(&self) -> Result<(), String> {
+        let files = self.normalized();
+        if files.max_file_mb.is_some_and(|mb| mb < 2) {
+            return Err("The maximum file size must be at least 2 MB".into());
+        }
+        if files.max_files.is_some_and(|count| count < 2) {
+            return Err("Rollover needs to keep at least 2 files".into());
+        }
+        Ok(())
+    }
+
+    /// Binds the options as four consecutive parameters: directory, maximum
+    /// file size in MB, rollover and file count, with NULL for anything not
+    /// set.
+    pub(crate) fn bind_to(&self, query: &mut tiberius::Query<'_>) {
+        let files = self.normalized();
+        query.bind(files.directory);
+        query.bind(files.max_file_mb.unwrap_or(Self::DEFAULT_MAX_FILE_MB));
+        query.bind(files.rollover);
+        query.bind(files.max_files);
+    }
+
+    /// The options with a blank directory removed, and the file count only
+    /// when rolling over.
+    pub(crate) fn normalized(&self) -> CaptureFileOptions {
+        CaptureFileOptions {
+            directory: self
+                .directory
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string),
+            max_file_mb: self.max_file_mb,
+            rollover: self.rollover,
+            max_files: self.max_files.filter(|_| self.rollover),
+        }
+    }
+}
+
+/// Filters applied on the server when the trace or XE session is created, so
+/// events that don't match are never collected. Duration, reads and text
+/// only filter requests; SQL Trace applies those when polling, see
+/// `TRACE_POLL_EVENTS`. Text values are LIKE patterns; empty values are
+/// ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptureFilter {
+    pub database_name: Option<String>,
+    pub login_name: Option<String>,
+    pub host_name: Option<String>,
+    pub program_name: Option<String>,
+    pub min_duration_ms: Option<i64>,
+    pub min_logical_reads: Option<i64>,
+    pub text_like: Option<String>,
+}
+
+impl CaptureFilter {
+    /// Binds the filter as seven consecutive parameters, in field order, with
+    /// NULL for anything not set. Duration is bound in microseconds.
+    pub(crate) fn bind_to(&self, query: &mut tiberius::Query<'_>) {
+        let filter = self.normalized();
+        query.bind(filter.database_name);
+        query.bind(filter.login_name);
+        query.bind(filter.host_name);
+        query.bind(filter.program_name);
+        query.bind(filter.min_duration_ms.map(|ms| ms * 1000));
+        query.bind(filter.min_logical_reads);
+        query.bind(filter.text_like);
+    }
+
+    /// The filter with blank patterns and non-positive thresholds removed.
+    pub(crate) fn normalized(&self) -> CaptureFilter {
+        let pattern = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        CaptureFilter {
+            database_name: pattern(&self.database_name),
+            login_name: pattern(&self.login_name),
+            host_name: pattern(&self.host_name),
+            program_name: pattern(&self.program_name),
+            min_duration_ms: self.min_duration_ms.filter(|ms| *ms > 0),
+            min_logical_reads: self.min_logical_reads.filter(|reads| *reads > 0),
+            text_like: pattern(&self.text_like),
+        }
+    }
+}
+
+pub fn create_source(
+    kind: CaptureSourceKind,
+    options: &CaptureOptions,
+    config: Option<&ConnectionConfig>,
+) -> Result<Box<dyn CaptureSource>, String> {
+    match kind {
+        CaptureSourceKind::TraceFile { path, on_server: false } => return Ok(Box::new(TraceFileSource::new(path))),
+        CaptureSourceKind::XelFile { path, on_server: false } => return Ok(Box::new(XelFileSource::new(path))),
+        _ => {}
+    }
+
+    let Some(config) = config else {
+        return Err(match kind {
+            CaptureSourceKind::TraceFile { .. } | CaptureSourceKind::XelFile { .. } => {
+                "Connect to a server to have it read the file".into()
+            }
+            _ => "Not connected".into(),
+        });
+    };
+    if !kind.is_offline() {
+        options.validate()?;
+    }
+
+    Ok(match kind {
+        CaptureSourceKind::SqlTrace => Box::new(SqlTraceSource::new(config.clone(), options.clone())),
+        CaptureSourceKind::XeRingBuffer => {
+            Box::new(XeSource::new(config.clone(), XeTarget::RingBuffer, options.clone()))
+        }
+        CaptureSourceKind::XeEventFile => {
+            Box::new(XeSource::new(config.clone(), XeTarget::EventFile, options.clone()))
+        }
+        CaptureSourceKind::XelFile { path, .. } => Box::new(ServerXelFileSource::new(&path, config.clone())),
+        CaptureSourceKind::TraceFile { path, .. } => Box::new(ServerTraceFileSource::new(path, config.clone())),
+    })
+}
+
+/// Calls `source.reconnect()` with exponential backoff until it succeeds.
+/// `on_attempt` is told about each attempt before it is made, along with the
+/// error that prompted it. Gives up with the error when the failure is not a
+/// connection problem (the trace is gone, the login was disabled), or with
+/// `None` once `run_flag` is cleared.
+pub async fn reconnect(
+    source: &mut dyn CaptureSource,
+    run_flag: &AtomicBool,
+    mut error: String,
+    mut on_attempt: impl FnMut(u32, &str),
+) -> Result<(), Option<String>> {
+    let mut backoff = RECONNECT_BACKOFF_INITIAL;
+    for attempt in 1.. {
+        on_attempt(attempt, &error);
+
+        let deadline = tokio::time::Instant::now() + backoff;
+        while tokio::time::Instant::now() < deadline {
+            if !run_flag.load(Ordering::Acquire) {
+                return Err(None);
+            }
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+        backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+
+        let result = tokio::time::timeout(RECONNECT_TIMEOUT, source.reconnect())
+            .await
+            .unwrap_or_else(|_| Err(format!("Reconnect failed: {}", db::CONNECTION_TIMED_OUT)));
+        match result {
+            Ok(()) => return Ok(()),
+            Err(e) if db::is_connection_error(&e) => error = e,
+            Err(e) => return Err(Some(e)),
+        }
+    }
+    unreachable!("attempts are unbounded")
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct PolledEvent {
+    pub event: QueryEvent,
+    /// Position used to resume polling; the end time (start time for
+    /// starting events) for SQL Trace rows and the event timestamp for
+    /// Extended Events.
+    pub watermark_time: String,
+}
+
+/// Tracks the last `(timestamp, event_sequence)` delivered by a polling source
+/// so overlapping poll results are not emitted twice.
+#[derive(Debug)]
+pub(crate) struct Watermark {
+    pub last_timestamp: String,
+    pub last_event_sequence: i64,
+    seen_without_sequence_at_timestamp: HashSet<String>,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self {
+            last_timestamp: String::from(MIN_TIMESTAMP),
+            last_event_sequence: -1,
+            seen_without_sequence_at_timestamp: HashSet::new(),
+        }
+    }
+}
+
+impl Watermark {
+    pub fn admit(&mut self, polled: &PolledEvent) -> bool {
+        let ts = &polled.watermark_time;
+        let seq = polled.event.event_sequence;
+        if *ts < self.last_timestamp {
+            return false;
+        }
+
+        if *ts > self.last_timestamp {
+            self.last_timestamp = ts.clone();
+            self.last_event_sequence = -1;
+            self.seen_without_sequence_at_timestamp.clear();
+        }
+
+        if seq > 0 {
+            if seq <= self.last_event_sequence {
+                return false;
+            }
+            self.last_event_sequence = seq;
+        } else {
+            let fallback_key = format!(
+                "{}|{}|{}|{}|{}|{:?}|{}|{}|{}|{}",
+                polled.event.event_name,
+                polled.event.session_id,
+                polled.event.elapsed_time,
+                polled.event.cpu_time,
+                polled.event.logical_reads,
+                polled.event.physical_reads,
+                polled.event.writes,
+                polled.event.row_count,
+                polled.event.database_name,
+                polled.event.sql_text
+            );
+            if !self.seen_without_sequence_at_timestamp.insert(fallback_key) {
+                return false;
+            }
+            if self.last_event_sequence < 0 {
+                // Prevent replaying sequence=0 rows for same timestamp forever.
+                self.last_event_sequence = 0;
+            }
+        }
+
+        true
+    }
+
+    pub fn admit_all(&mut self, polled: Vec<PolledEvent>) -> Vec<QueryEvent> {
+        polled
+            .into_iter()
+            .filter(|p| self.admit(p))
+            .map(|p| p.event)
+            .collect()
+    }
+}
+
+/// Maps the result of a completed batch or RPC (the trace `Error` column or
+/// the XE `result` field) to the status the source reports for it.
+pub(crate) fn completion_status(result: i64) -> String {
+    match result {
+        1 => "failed".into(),
+        2 => "cancelled".into(),
+        _ => String::new(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RequestKind {
+    Rpc,
+    Batch,
+}
+
+/// Splits an event name into the request it belongs to and whether it is the
+/// starting half of the pair.
+fn request_phase(event_name: &str) -> Option<(RequestKind, bool)> {
+    match event_name {
+        "rpc_starting" => Some((RequestKind::Rpc, true)),
+        "rpc_completed" => Some((RequestKind::Rpc, false)),
+        "sql_batch_starting" => Some((RequestKind::Batch, true)),
+        "sql_batch_completed" => Some((RequestKind::Batch, false)),
+        _ => None,
+    }
+}
+
+/// Pairs starting events with their completion by SPID and event sequence so
+/// an in-flight request keeps one id from the moment it starts until it
+/// finishes, and can be updated in place by the frontend. Other events seen
+/// on a session while a request is in flight are nested under it, and errors
+/// and attentions mark it failed or cancelled.
+#[derive(Debug, Default)]
+pub struct InFlightTracker {
+    running: HashMap<(i32, RequestKind), InFlight>,
+    /// SQL Trace reports one error as `Exception` (number and severity)
+    /// followed by `User Error Message` (text); keyed by session.
+    last_exception: HashMap<i32, QueryEvent>,
+}
+
+#[derive(Debug)]
+struct InFlight {
+    event: QueryEvent,
+    cancelled: bool,
+}
+
+impl InFlightTracker {
+    /// Assigns the id and status of `event` and returns what should be
+    /// emitted for it, including any request it shows was abandoned.
+    pub fn track(&mut self, mut event: QueryEvent) -> Vec<QueryEvent> {
+        let Some((kind, starting)) = request_phase(&event.event_name) else {
+            return vec![self.track_nested(event)];
+        };
+        let key = (event.session_id, kind);
+
+        if starting {
+            event.id = uuid::Uuid::new_v4().to_string();
+            event.event_status = "running".into();
+            // A session runs one request of a kind at a time, so an older
+            // entry means its completion was lost.
+            let mut emitted = Vec::with_capacity(2);
+            let in_flight = InFlight {
+                event: event.clone(),
+                cancelled: false,
+            };
+            if let Some(previous) = self.running.insert(key, in_flight) {
+                let mut previous = previous.event;
+                previous.event_status = "unknown".into();
+                emitted.push(previous);
+            }
+            emitted.push(event);
+            return emitted;
+        }
+
+        let paired = self.running.get(&key).is_some_and(|started| {
+            started.event.event_sequence <= 0
+                || event.event_sequence <= 0
+                || started.event.event_sequence < event.event_sequence
+        });
+        let started = if paired { self.running.remove(&key) } else { None };
+
+        // Sources report `failed`/`cancelled` when the completion says so.
+        let mut status = std::mem::take(&mut event.event_status);
+        match started {
+            Some(started) => {
+                event.id = started.event.id;
+                if event.error.is_none() {
+                    event.error = started.event.error;
+                }
+                if started.cancelled && status.is_empty() {
+                    status = "cancelled".into();
+                }
+            }
+            None => event.id = uuid::Uuid::new_v4().to_string(),
+        }
+        event.event_status = match status.is_empty() {
+            false => status,
+            true if event.error.is_some() => "failed".into(),
+            true => "completed".into(),
+        };
+        vec![event]
+    }
+
+    fn track_nested(&mut self, mut event: QueryEvent) -> QueryEvent {
+        event.event_status = "completed".into();
+
+        // Statements, errors and attentions belong to whichever request
+        // their session is running.
+        let parent_key = self
+            .running
+            .iter()
+            .filter(|((session_id, _), started)| {
+                *session_id == event.session_id
+                    && (event.event_sequence <= 0 || started.event.event_sequence < event.event_sequence)
+            })
+            .max_by_key(|(_, started)| started.event.event_sequence)
+            .map(|(key, _)| *key);
+        if let Some(parent) = parent_key.and_then(|key| self.running.get_mut(&key)) {
+            event.parent_id = Some(parent.event.id.clone());
+            if event.event_name == "attention" {
+                parent.cancelled = true;
+            }
+            if let Some(error) = event.error.as_ref() {
+                let replace = match parent.event.error.as_ref() {
+                    None => true,
+                    Some(existing) => existing.number == error.number && existing.message.is_empty(),
+                };
+                if replace {
+                    parent.event.error = Some(error.clone());
+                }
+            }
+        }
+
+        event.id = match event.event_name.as_str() {
+            "exception" => {
+                let id = uuid::Uuid::new_v4().to_string();
+                event.id = id.clone();
+                self.last_exception.insert(event.session_id, event.clone());
+                id
+            }
+            "user_error_message" => match self.last_exception.remove(&event.session_id) {
+                // Same error: update the exception row in place.
+                Some(exception)
+                    if exception.error.as_ref().map(|e| e.number)
+                        == event.error.as_ref().map(|e| e.number) =>
+                {
+                    exception.id
+                }
+                _ => uuid::Uuid::new_v4().to_string(),
+            },
+            _ => uuid::Uuid::new_v4().to_string(),
+        };
+        event
+    }
+
+    /// Marks every request still in flight as `unknown`, for when the capture
+    /// ends before their completion is seen.
+    pub fn abandon_all(&mut self) -> Vec<QueryEvent> {
+        self.last_exception.clear();
+        self.running
+            .drain()
+            .map(|(_, in_flight)| {
+                let mut event = in_flight.event;
+                event.event_status = "unknown".into();
+                event
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiler::QueryError;
+
+    fn polled(time: &str, sequence: i64, sql: &str) -> PolledEvent {
+        let mut event = QueryEvent::for_test("sql_batch_completed", 52, sequence);
+        event.sql_text = sql.into();
+        PolledEvent {
+            event,
+            watermark_time: time.into(),
+        }
+    }
+
+    #[test]
+    fn watermark_skips_what_was_delivered() {
+        let mut watermark = Watermark::default();
+        assert!(watermark.admit(&polled("2024-01-01T10:00:00.000", 5, "a")));
+        assert!(!watermark.admit(&polled("2024-01-01T10:00:00.000", 5, "a")));
+        assert!(!watermark.admit(&polled("2024-01-01T10:00:00.000", 4, "b")));
+        assert!(watermark.admit(&polled("2024-01-01T10:00:00.000", 6, "c")));
+        assert!(!watermark.admit(&polled("2024-01-01T09:59:59.999", 7, "d")));
+        assert!(watermark.admit(&polled("2024-01-01T10:00:00.001", 1, "e")));
+        assert_eq!(watermark.last_timestamp, "2024-01-01T10:00:00.001");
+        assert_eq!(watermark.last_event_sequence, 1);
+    }
+
+    #[test]
+    fn watermark_dedupes_events_without_sequence() {
+        let mut watermark = Watermark::default();
+        let polls = vec![
+            polled("2024-01-01T10:00:00.000", 0, "a"),
+            polled("2024-01-01T10:00:00.000", 0, "b"),
+            polled("2024-01-01T10:00:00.000", 0, "a"),
+        ];
+        assert_eq!(watermark.admit_all(polls).len(), 2);
+        // The next poll overlaps at the same timestamp.
+        assert!(!watermark.admit(&polled("2024-01-01T10:00:00.000", 0, "b")));
+        assert!(watermark.admit(&polled("2024-01-01T10:00:01.000", 0, "a")));
+    }
+
+    fn event(event_name: &str, session_id: i32, sequence: i64) -> QueryEvent {
+        QueryEvent::for_test(event_name, session_id, sequence)
+    }
+
+    #[test]
+    fn completion_takes_the_id_of_its_start() {
+        let mut tracker = InFlightTracker::default();
+        let started = tracker.track(event("rpc_starting", 52, 1));
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].event_status, "running");
+
+        let statement = tracker.track(event("sp_statement_completed", 52, 2));
+        assert_eq!(statement[0].parent_id.as_deref(), Some(started[0].id.as_str()));
+
+        let completed = tracker.track(event("rpc_completed", 52, 3));
+        assert_eq!(completed.len(), 1);
+        assert_eq!(completed[0].id, started[0].id);
+        assert_eq!(completed[0].event_status, "completed");
+        assert!(tracker.abandon_all().is_empty());
+    }
+
+    #[test]
+    fn requests_pair_per_session_and_kind() {
+        let mut tracker = InFlightTracker::default();
+        let rpc = tracker.track(event("rpc_starting", 52, 1)).remove(0);
+        let batch = tracker.track(event("sql_batch_starting", 53, 2)).remove(0);
+
+        let other = tracker.track(event("rpc_completed", 53, 3)).remove(0);
+        assert_ne!(other.id, rpc.id);
+        assert_ne!(other.id, batch.id);
+        assert_eq!(tracker.track(event("sql_batch_completed", 53, 4))[0].id, batch.id);
+        assert_eq!(tracker.track(event("rpc_completed", 52, 5))[0].id, rpc.id);
+    }
+
+    #[test]
+    fn completion_older_than_the_start_is_not_paired() {
+        let mut tracker = InFlightTracker::default();
+        let started = tracker.track(event("sql_batch_starting", 52, 10)).remove(0);
+        let stale = tracker.track(event("sql_batch_completed", 52, 9)).remove(0);
+        assert_ne!(stale.id, started.id);
+        assert_eq!(tracker.abandon_all()[0].id, started.id);
+    }
+
+    #[test]
+    fn lost_completion_marks_the_start_unknown() {
+        let mut tracker = InFlightTracker::default();
+        let first = tracker.track(event("sql_batch_starting", 52, 1)).remove(0);
+        let emitted = tracker.track(event("sql_batch_starting", 52, 2));
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[0].id, first.id);
+        assert_eq!(emitted[0].event_status, "unknown");
+        assert_eq!(emitted[1].event_status, "running");
+
+        let abandoned = tracker.abandon_all();
+        assert_eq!(abandoned.len(), 1);
+        assert_eq!(abandoned[0].id, emitted[1].id);
+        assert_eq!(abandoned[0].event_status, "unknown");
+    }
+
+    #[test]
+    fn errors_and_attentions_mark_the_request() {
+        let mut tracker = InFlightTracker::default();
+        tracker.track(event("sql_batch_starting", 52, 1));
+        let mut error = event("error_reported", 52, 2);
+        error.error = Some(QueryError {
+            number: 1205,
+            severity: 13,
+            message: "deadlock victim".into(),
+        });
+        tracker.track(error);
+        let failed = tracker.track(event("sql_batch_completed", 52, 3)).remove(0);
+        assert_eq!(failed.event_status, "failed");
+        assert_eq!(failed.error.map(|e| e.number), Some(1205));
+
+        tracker.track(event("rpc_starting", 52, 4));
+        tracker.track(event("attention", 52, 5));
+        let cancelled = tracker.track(event("rpc_completed", 52, 6)).remove(0);
+        assert_eq!(cancelled.event_status, "cancelled");
+    }
+
+    #[test]
+    fn pacer_stays_within_bounds() {
+        let mut pacer = PollPacer::default();
+        assert_eq!(pacer.next_delay(10, true), Duration::ZERO);
+
+        let mut delay = Duration::ZERO;
+        for _ in 0..50 {
+            delay = pacer.next_delay(0, false);
+            assert!(delay <= POLL_INTERVAL_MAX);
+        }
+        assert_eq!(delay, POLL_INTERVAL_MAX);
+
+        for _ in 0..50 {
+            delay = pacer.next_delay(100, false);
+            assert!(delay >= POLL_INTERVAL_MIN);
+        }
+        assert_eq!(delay, POLL_INTERVAL_MIN);
+    }
+}
@@ -72,6 +72,26 @@ pub async fn connect(config: &ConnectionConfig) -> Result<SqlClient, String> {
     Ok(client)
 }
 
+/// Whether an error from `connect` or a query means the connection itself
+/// failed (network, TLS, a dropped session) rather than the server rejecting
+/// the statement. Errors are strings by the time they reach the callers, so
+/// this goes by the wording of tiberius and of `connect`.
+pub fn is_connection_error(message: &str) -> bool {
+    const MARKERS: &[&str] = &[
+        "performing I/O",
+        "Error forming TLS connection",
+        "Protocol error",
+        "TCP connection to",
+        "Named instance resolution failed",
+        CONNECTION_TIMED_OUT,
+    ];
+    MARKERS.iter().any(|marker| message.contains(marker))
+}
+
+/// Reported for a query that got no answer in time. A connection dropped
+/// by a VPN or firewall often just goes silent rather than failing.
+pub const CONNECTION_TIMED_OUT: &str = "the server stopped responding";
+
 fn parse_server_name(server_name: &str) -> Result<(String, u16, Option<String>), String> {
     let (addr, explicit_port) = if let Some(comma_idx) = server_name.rfind(',') {
         let port_str = server_name[comma_idx + 1..].trim();
@@ -0,0 +1,258 @@
+use serde::{Deserialize, Serialize};
+
+/// A deadlock report (`Lock:Deadlock Graph` or `xml_deadlock_report`) parsed
+/// into the parts the UI shows. The original XML is kept for copying into
+/// SSMS or other tools.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadlockGraph {
+    pub id: String,
+    pub event_time: String,
+    pub captured_at: String,
+    pub victim_ids: Vec<String>,
+    pub processes: Vec<DeadlockProcess>,
+    pub resources: Vec<DeadlockResource>,
+    pub xml: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadlockProcess {
+    pub id: String,
+    pub is_victim: bool,
+    pub session_id: i32,
+    pub database_name: String,
+    pub login_name: String,
+    pub host_name: String,
+    pub program_name: String,
+    pub isolation_level: String,
+    pub lock_mode: String,
+    pub wait_resource: String,
+    pub wait_time_ms: i64,
+    pub transaction_name: String,
+    pub input_buffer: String,
+    pub frames: Vec<DeadlockFrame>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadlockFrame {
+    pub procedure: String,
+    pub line: i32,
+    pub statement: String,
+}
+
+/// A locked resource with the processes holding it (owners) and the ones
+/// blocked on it (waiters); together these are the edges of the graph.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadlockResource {
+    pub id: String,
+    /// The resource element name, e.g. `keylock`, `pagelock`, `objectlock`.
+    pub kind: String,
+    pub object_name: String,
+    pub index_name: String,
+    pub mode: String,
+    pub owners: Vec<DeadlockLockHolder>,
+    pub waiters: Vec<DeadlockLockHolder>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeadlockLockHolder {
+    pub process_id: String,
+    pub mode: String,
+    pub request_type: String,
+}
+
+/// Parses either the SQL Trace form (`<deadlock-list><deadlock victim=...>`)
+/// or the Extended Events form (`<deadlock><victim-list>...`).
+pub fn parse_deadlock_graph(xml: &str) -> Result<DeadlockGraph, String> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| format!("Invalid deadlock XML: {e}"))?;
+    let Some(deadlock) = document.descendants().find(|n| n.has_tag_name("deadlock")) else {
+        return Err("Deadlock XML has no <deadlock> element".into());
+    };
+
+    let mut victim_ids: Vec<String> = deadlock
+        .attribute("victim")
+        .map(|id| vec![id.to_string()])
+        .unwrap_or_default();
+    if let Some(victim_list) = child(deadlock, "victim-list") {
+        for victim in victim_list.children().filter(|n| n.has_tag_name("victimProcess")) {
+            if let Some(id) = victim.attribute("id") {
+                if !victim_ids.iter().any(|v| v == id) {
+                    victim_ids.push(id.to_string());
+                }
+            }
+        }
+    }
+
+    let processes = child(deadlock, "process-list")
+        .map(|list| {
+            list.children()
+                .filter(|n| n.has_tag_name("process"))
+                .map(|n| parse_process(n, &victim_ids))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let resources = child(deadlock, "resource-list")
+        .map(|list| list.children().filter(|n| n.is_element()).map(parse_resource).collect())
+        .unwrap_or_default();
+
+    Ok(DeadlockGraph {
+        victim_ids,
+        processes,
+        resources,
+        xml: xml.to_string(),
+        ..DeadlockGraph::default()
+    })
+}
+
+fn parse_process(node: roxmltree::Node, victim_ids: &[String]) -> DeadlockProcess {
+    let id = attr(node, "id");
+    let frames = child(node, "executionStack")
+        .map(|stack| {
+            stack
+                .children()
+                .filter(|n| n.has_tag_name("frame"))
+                .map(|frame| DeadlockFrame {
+                    procedure: attr(frame, "procname"),
+                    line: attr(frame, "line").parse().unwrap_or(0),
+                    statement: text(frame),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    DeadlockProcess {
+        is_victim: victim_ids.contains(&id),
+        session_id: attr(node, "spid").parse().unwrap_or(0),
+        database_name: attr(node, "currentdbname"),
+        login_name: attr(node, "loginname"),
+        host_name: attr(node, "hostname"),
+        program_name: attr(node, "clientapp"),
+        isolation_level: attr(node, "isolationlevel"),
+        lock_mode: attr(node, "lockMode"),
+        wait_resource: attr(node, "waitresource"),
+        wait_time_ms: attr(node, "waittime").parse().unwrap_or(0),
+        transaction_name: attr(node, "transactionname"),
+        input_buffer: child(node, "inputbuf").map(text).unwrap_or_default(),
+        frames,
+        id,
+    }
+}
+
+fn parse_resource(node: roxmltree::Node) -> DeadlockResource {
+    let holders = |list: &str, item: &str| -> Vec<DeadlockLockHolder> {
+        child(node, list)
+            .map(|list| {
+                list.children()
+                    .filter(|n| n.has_tag_name(item))
+                    .map(|n| DeadlockLockHolder {
+                        process_id: attr(n, "id"),
+                        mode: attr(n, "mode"),
+                        request_type: attr(n, "requestType"),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    DeadlockResource {
+        id: attr(node, "id"),
+        kind: node.tag_name().name().to_string(),
+        object_name: attr(node, "objectname"),
+        index_name: attr(node, "indexname"),
+        mode: attr(node, "mode"),
+        owners: holders("owner-list", "owner"),
+        waiters: holders("waiter-list", "waiter"),
+    }
+}
+
+pub(crate) fn child<'a, 'input>(node: roxmltree::Node<'a, 'input>, name: &str) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+pub(crate) fn attr(node: roxmltree::Node, name: &str) -> String {
+    node.attribute(name).unwrap_or_default().to_string()
+}
+
+pub(crate) fn text(node: roxmltree::Node) -> String {
+    node.descendants()
+        .filter(|n| n.is_text())
+        .filter_map(|n| n.text())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const XE_DEADLOCK: &str = r#"<event name="xml_deadlock_report"><data name="xml_report"><value>
+<deadlock>
+  <victim-list><victimProcess id="process1a" /></victim-list>
+  <process-list>
+    <process id="process1a" spid="54" waitresource="KEY: 5:72057594043170816 (8194443284a0)" waittime="3042" lockMode="U" transactionname="user_transaction" isolationlevel="read committed (2)" clientapp="app" hostname="web01" loginname="app_user" currentdbname="Shop">
+      <executionStack>
+        <frame procname="Shop.dbo.UpdateStock" line="12">UPDATE Stock SET Qty = Qty - 1 WHERE Id = @id</frame>
+      </executionStack>
+      <inputbuf>EXEC dbo.UpdateStock @id = 7</inputbuf>
+    </process>
+    <process id="process2b" spid="61" waittime="2980" lockMode="X" currentdbname="Shop">
+      <inputbuf>UPDATE Orders SET Status = 2</inputbuf>
+    </process>
+  </process-list>
+  <resource-list>
+    <keylock objectname="Shop.dbo.Stock" indexname="PK_Stock" mode="X">
+      <owner-list><owner id="process2b" mode="X" /></owner-list>
+      <waiter-list><waiter id="process1a" mode="U" requestType="wait" /></waiter-list>
+    </keylock>
+  </resource-list>
+</deadlock>
+</value></data></event>"#;
+
+    #[test]
+    fn extended_events_report() {
+        let graph = parse_deadlock_graph(XE_DEADLOCK).unwrap();
+        assert_eq!(graph.victim_ids, ["process1a"]);
+        assert_eq!(graph.xml, XE_DEADLOCK);
+
+        let victim = &graph.processes[0];
+        assert!(victim.is_victim);
+        assert_eq!(victim.session_id, 54);
+        assert_eq!(victim.wait_time_ms, 3042);
+        assert_eq!(victim.database_name, "Shop");
+        assert_eq!(victim.input_buffer, "EXEC dbo.UpdateStock @id = 7");
+        assert_eq!(victim.frames.len(), 1);
+        assert_eq!(victim.frames[0].procedure, "Shop.dbo.UpdateStock");
+        assert_eq!(victim.frames[0].line, 12);
+        assert!(!graph.processes[1].is_victim);
+
+        let resource = &graph.resources[0];
+        assert_eq!(resource.kind, "keylock");
+        assert_eq!(resource.index_name, "PK_Stock");
+        assert_eq!(resource.owners[0].process_id, "process2b");
+        assert_eq!(resource.waiters[0].request_type, "wait");
+    }
+
+    #[test]
+    fn trace_report_names_the_victim_on_the_deadlock() {
+        let xml = r#"<deadlock-list><deadlock victim="p2">
+            <process-list><process id="p1" spid="51" /><process id="p2" spid="52" /></process-list>
+            <resource-list><pagelock objectname="db.dbo.t" mode="IX" /></resource-list>
+        </deadlock></deadlock-list>"#;
+        let graph = parse_deadlock_graph(xml).unwrap();
+        assert_eq!(graph.victim_ids, ["p2"]);
+        let victims: Vec<bool> = graph.processes.iter().map(|p| p.is_victim).collect();
+        assert_eq!(victims, [false, true]);
+        assert_eq!(graph.resources[0].kind, "pagelock");
+        assert!(graph.resources[0].owners.is_empty());
+    }
+
+    #[test]
+    fn malformed_reports_fail() {
+        assert!(parse_deadlock_graph("<deadlock>").unwrap_err().starts_with("Invalid deadlock XML"));
+        assert_eq!(
+            parse_deadlock_graph("<event />").unwrap_err(),
+            "Deadlock XML has no <deadlock> element"
+        );
+    }
+}
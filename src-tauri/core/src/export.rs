@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Int32Array, Int64Array, RecordBatch, StringArray};
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::CaptureFilter;
+use crate::profiler::QueryEvent;
+use crate::sessions::SessionStore;
+
+/// Rows per Parquet row group; also the most rows held in memory at once.
+const PARQUET_BATCH_ROWS: usize = 65_536;
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+    Parquet,
+}
+
+/// The export schema: one flat row per event, the same columns in the same
+/// order for every format. Columns are only ever appended, so scripts that
+/// read exports keep working. Durations are microseconds; timestamps are
+/// kept exactly as the source reported them.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportRow {
+    pub id: String,
+    pub event_name: String,
+    pub event_status: String,
+    pub event_sequence: i64,
+    pub parent_id: Option<String>,
+    pub session_id: i32,
+    pub start_time: String,
+    pub end_time: String,
+    pub captured_at: String,
+    pub database_name: String,
+    pub login_name: String,
+    pub host_name: String,
+    pub program_name: String,
+    pub nt_user_name: String,
+    pub client_process_id: i32,
+    pub object_name: String,
+    pub transaction_id: i64,
+    pub cpu_time: i64,
+    pub elapsed_time: i64,
+    pub physical_reads: Option<i64>,
+    pub logical_reads: i64,
+    pub writes: i64,
+    pub row_count: i64,
+    pub query_hash: String,
+    pub fingerprint: String,
+    pub sql_text: String,
+    pub current_statement: String,
+    pub error_number: Option<i32>,
+    pub error_severity: Option<i32>,
+    pub error_message: Option<String>,
+    /// Unknown columns carried over from an imported file, as a JSON object.
+    pub extra: Option<String>,
+}
+
+impl From<QueryEvent> for ExportRow {
+    fn from(event: QueryEvent) -> Self {
+        let (error_number, error_severity, error_message) = match event.error {
+            Some(error) => (Some(error.number), Some(error.severity), Some(error.message)),
+            None => (None, None, None),
+        };
+        Self {
+            id: event.id,
+            event_name: event.event_name,
+            event_status: event.event_status,
+            event_sequence: event.event_sequence,
+            parent_id: event.parent_id,
+            session_id: event.session_id,
+            start_time: event.start_time,
+            end_time: event.end_time,
+            captured_at: event.captured_at,
+            database_name: event.database_name,
+            login_name: event.login_name,
+            host_name: event.host_name,
+            program_name: event.program_name,
+            nt_user_name: event.nt_user_name,
+            client_process_id: event.client_process_id,
+            object_name: event.object_name,
+            transaction_id: event.transaction_id,
+            cpu_time: event.cpu_time,
+            elapsed_time: event.elapsed_time,
+            physical_reads: event.physical_reads,
+            logical_reads: event.logical_reads,
+            writes: event.writes,
+            row_count: event.row_count,
+            query_hash: event.query_hash,
+            fingerprint: event.fingerprint,
+            sql_text: event.sql_text,
+            current_statement: event.current_statement,
+            error_number,
+            error_severity,
+            error_message,
+            extra: (!event.extra.is_empty()).then(|| serde_json::Value::Object(event.extra).to_string()),
+        }
+    }
+}
+
+/// Writes a saved session's query events to `path`, streaming them from the
+/// store. `event_ids`, when given, limits the export to those events (the
+/// rows the UI is showing). Returns the number of events written.
+pub fn export_session(
+    store: &SessionStore,
+    id: &str,
+    path: &Path,
+    format: ExportFormat,
+    filter: &CaptureFilter,
+    event_ids: Option<&[String]>,
+) -> Result<u64, String> {
+    let wanted: Option<HashSet<&str>> = event_ids.map(|ids| ids.iter().map(String::as_str).collect());
+    let file = File::create(path).map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    let mut writer = EventWriter::new(file, format)?;
+    let mut count = 0;
+
+    store.for_each_event(id, filter, |event| {
+        if wanted.as_ref().is_some_and(|ids| !ids.contains(event.id.as_str())) {
+            return Ok(());
+        }
+        writer.write(ExportRow::from(event))?;
+        count += 1;
+        Ok(())
+    })?;
+
+    writer.finish()?;
+    Ok(count)
+}
+
+/// Writes `ExportRow`s in one of the export formats. Parquet rows are
+/// buffered into row groups; the text formats are written as they come.
+pub enum EventWriter<W: Write + Send> {
+    Csv(csv::Writer<BufWriter<W>>),
+    Ndjson(BufWriter<W>),
+    Parquet {
+        writer: ArrowWriter<W>,
+        schema: SchemaRef,
+        rows: Vec<ExportRow>,
+    },
+}
+
+impl<W: Write + Send> EventWriter<W> {
+    pub fn new(output: W, format: ExportFormat) -> Result<Self, String> {
+        Ok(match format {
+            ExportFormat::Csv => Self::Csv(csv::Writer::from_writer(BufWriter::new(output))),
+            ExportFormat::Ndjson => Self::Ndjson(BufWriter::new(output)),
+            ExportFormat::Parquet => {
+                let schema = parquet_schema();
+                let props = WriterProperties::builder()
+                    .set_compression(Compression::SNAPPY)
+                    .set_max_row_group_size(PARQUET_BATCH_ROWS)
+                    .build();
+                let writer = ArrowWriter::try_new(output, schema.clone(), Some(props))
+                    .map_err(|e| format!("Failed to start Parquet file: {e}"))?;
+                Self::Parquet {
+                    writer,
+                    schema,
+                    rows: Vec::with_capacity(PARQUET_BATCH_ROWS),
+                }
+            }
+        })
+    }
+
+    pub fn write(&mut self, row: ExportRow) -> Result<(), String> {
+        match self {
+            Self::Csv(writer) => writer.serialize(&row).map_err(|e| format!("Failed to write CSV: {e}")),
+            Self::Ndjson(writer) => {
+                serde_json::to_writer(&mut *writer, &row).map_err(|e| format!("Failed to write NDJSON: {e}"))?;
+                writer.write_all(b"\n").map_err(|e| format!("Failed to write NDJSON: {e}"))
+            }
+            Self::Parquet { writer, schema, rows } => {
+                rows.push(row);
+                if rows.len() >= PARQUET_BATCH_ROWS {
+                    write_parquet_batch(writer, schema, rows)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Pushes buffered text rows to the output, for readers following a
+    /// live stream. Parquet can only be read once finished.
+    pub fn flush(&mut self) -> Result<(), String> {
+        match self {
+            Self::Csv(writer) => writer.flush().map_err(|e| format!("Failed to write CSV: {e}")),
+            Self::Ndjson(writer) => writer.flush().map_err(|e| format!("Failed to write NDJSON: {e}")),
+            Self::Parquet { .. } => Ok(()),
+        }
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        match self {
+            Self::Csv(mut writer) => writer.flush().map_err(|e| format!("Failed to write CSV: {e}")),
+            Self::Ndjson(mut writer) => writer.flush().map_err(|e| format!("Failed to write NDJSON: {e}")),
+            Self::Parquet {
+                mut writer,
+                schema,
+                mut rows,
+            } => {
+                if !rows.is_empty() {
+                    write_parquet_batch(&mut writer, &schema, &mut rows)?;
+                }
+                writer
+                    .close()
+                    .map(|_| ())
+                    .map_err(|e| format!("Failed to finish Parquet file: {e}"))
+            }
+        }
+    }
+}
+
+/// Must list the `ExportRow` columns in declaration order.
+fn parquet_schema() -> SchemaRef {
+    let text = |name: &str| Field::new(name, DataType::Utf8, false);
+    let optional_text = |name: &str| Field::new(name, DataType::Utf8, true);
+    let int = |name: &str| Field::new(name, DataType::Int32, false);
+    let optional_int = |name: &str| Field::new(name, DataType::Int32, true);
+    let bigint = |name: &str| Field::new(name, DataType::Int64, false);
+    let optional_bigint = |name: &str| Field::new(name, DataType::Int64, true);
+
+    Arc::new(Schema::new(vec![
+        text("id"),
+        text("event_name"),
+        text("event_status"),
+        bigint("event_sequence"),
+        optional_text("parent_id"),
+        int("session_id"),
+        text("start_time"),
+        text("end_time"),
+        text("captured_at"),
+        text("database_name"),
+        text("login_name"),
+        text("host_name"),
+        text("program_name"),
+        text("nt_user_name"),
+        int("client_process_id"),
+        text("object_name"),
+        bigint("transaction_id"),
+        bigint("cpu_time"),
+        bigint("elapsed_time"),
+        optional_bigint("physical_reads"),
+        bigint("logical_reads"),
+        bigint("writes"),
+        bigint("row_count"),
+        text("query_hash"),
+        text("fingerprint"),
+        text("sql_text"),
+        text("current_statement"),
+        optional_int("error_number"),
+        optional_int("error_severity"),
+        optional_text("error_message"),
+        optional_text("extra"),
+    ]))
+}
+
+fn write_parquet_batch<W: Write + Send>(
+    writer: &mut ArrowWriter<W>,
+    schema: &SchemaRef,
+    rows: &mut Vec<ExportRow>,
+) -> Result<(), String> {
+    let text = |f: fn(&ExportRow) -> &str| -> ArrayRef { Arc::new(StringArray::from_iter_values(rows.iter().map(f))) };
+    let optional_text = |f: fn(&ExportRow) -> Option<&str>| -> ArrayRef { Arc::new(rows.iter().map(f).collect::<StringArray>()) };
+    let int = |f: fn(&ExportRow) -> i32| -> ArrayRef { Arc::new(Int32Array::from_iter_values(rows.iter().map(f))) };
+    let optional_int = |f: fn(&ExportRow) -> Option<i32>| -> ArrayRef { Arc::new(rows.iter().map(f).collect::<Int32Array>()) };
+    let bigint = |f: fn(&ExportRow) -> i64| -> ArrayRef { Arc::new(Int64Array::from_iter_values(rows.iter().map(f))) };
+    let optional_bigint = |f: fn(&ExportRow) -> Option<i64>| -> ArrayRef { Arc::new(rows.iter().map(f).collect::<Int64Array>()) };
+
+    let columns = vec![
+        text(|r| &r.id),
+        text(|r| &r.event_name),
+        text(|r| &r.event_status),
+        bigint(|r| r.event_sequence),
+        optional_text(|r| r.parent_id.as_deref()),
+        int(|r| r.session_id),
+        text(|r| &r.start_time),
+        text(|r| &r.end_time),
+        text(|r| &r.captured_at),
+        text(|r| &r.database_name),
+        text(|r| &r.login_name),
+        text(|r| &r.host_name),
+        text(|r| &r.program_name),
+        text(|r| &r.nt_user_name),
+        int(|r| r.client_process_id),
+        text(|r| &r.object_name),
+        bigint(|r| r.transaction_id),
+        bigint(|r| r.cpu_time),
+        bigint(|r| r.elapsed_time),
+        optional_bigint(|r| r.physical_reads),
+        bigint(|r| r.logical_reads),
+        bigint(|r| r.writes),
+        bigint(|r| r.row_count),
+        text(|r| &r.query_hash),
+        text(|r| &r.fingerprint),
+        text(|r| &r.sql_text),
+        text(|r| &r.current_statement),
+        optional_int(|r| r.error_number),
+        optional_int(|r| r.error_severity),
+        optional_text(|r| r.error_message.as_deref()),
+        optional_text(|r| r.extra.as_deref()),
+    ];
+
+    let batch = RecordBatch::try_new(schema.clone(), columns)
+        .map_err(|e| format!("Failed to build Parquet batch: {e}"))?;
+    writer.write(&batch).map_err(|e| format!("Failed to write Parquet: {e}"))?;
+    rows.clear();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiler::QueryError;
+
+    fn row() -> ExportRow {
+        let mut event = QueryEvent::for_test("rpc_completed", 52, 7);
+        event.id = "7".into();
+        event.physical_reads = Some(3);
+        event.error = Some(QueryError {
+            number: 1205,
+            severity: 13,
+            message: "deadlock victim".into(),
+        });
+        ExportRow::from(event)
+    }
+
+    fn write(format: ExportFormat) -> Vec<u8> {
+        let mut output = Vec::new();
+        let mut writer = EventWriter::new(&mut output, format).unwrap();
+        writer.write(row()).unwrap();
+        writer.finish().unwrap();
+        output
+    }
+
+    #[test]
+    fn every_format_has_the_same_columns() {
+        let parquet_path = std::env::temp_dir().join(format!("sqlprof-export-{}.parquet", uuid::Uuid::new_v4()));
+        std::fs::write(&parquet_path, write(ExportFormat::Parquet)).unwrap();
+        let reader = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(
+            File::open(&parquet_path).unwrap(),
+        )
+        .unwrap();
+        let parquet_columns: Vec<String> = reader.schema().fields().iter().map(|f| f.name().clone()).collect();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 1);
+        drop(reader);
+        std::fs::remove_file(&parquet_path).unwrap();
+
+        let csv = write(ExportFormat::Csv);
+        let mut csv = csv::Reader::from_reader(csv.as_slice());
+        let csv_columns: Vec<String> = csv.headers().unwrap().iter().map(str::to_string).collect();
+        assert_eq!(csv.records().count(), 1);
+
+        let ndjson = String::from_utf8(write(ExportFormat::Ndjson)).unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let object: serde_json::Map<String, serde_json::Value> = serde_json::from_str(lines[0]).unwrap();
+        let mut ndjson_columns: Vec<String> = object.keys().cloned().collect();
+
+        assert_eq!(csv_columns, parquet_columns);
+        let mut expected = parquet_columns.clone();
+        expected.sort();
+        ndjson_columns.sort();
+        assert_eq!(ndjson_columns, expected);
+        assert_eq!(parquet_columns.first().map(String::as_str), Some("id"));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::profiler::QueryEvent;
+
+/// Keywords after which a `-` or `+` is a sign rather than an operator.
+const SIGN_KEYWORDS: &[&str] = &[
+    "select", "where", "and", "or", "not", "when", "then", "else", "return", "values", "by", "is", "between",
+    "like", "top", "case", "in", "exec", "execute", "print",
+];
+
+/// Wrappers whose statement argument is what actually ran, with the
+/// (zero-based) position of that argument.
+const WRAPPERS: &[(&str, usize)] = &[
+    ("sp_executesql", 0),
+    ("sp_prepexec", 2),
+    ("sp_prepare", 2),
+    ("sp_cursorprepexec", 3),
+    ("sp_cursorprepare", 3),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Keyword or identifier, lowercased; bracketed and quoted names keep
+    /// their delimiters.
+    Word(String),
+    /// `@name` or `@@name`, lowercased.
+    Variable(String),
+    /// String literal with quotes removed and `''` unescaped.
+    Str(String),
+    Number,
+    Symbol(String),
+}
+
+/// Sets `fingerprint` and `query_hash` on batches, RPCs and statements.
+/// Other events (errors, attentions) keep them empty.
+pub fn apply(event: &mut QueryEvent) {
+    let is_request = event.event_name.starts_with("rpc_")
+        || event.event_name.starts_with("sql_batch_")
+        || event.event_name.ends_with("_statement_completed");
+    if !is_request {
+        return;
+    }
+
+    let text = if event.current_statement.is_empty() {
+        &event.sql_text
+    } else {
+        &event.current_statement
+    };
+    event.fingerprint = fingerprint(text);
+    event.query_hash = if event.fingerprint.is_empty() {
+        String::new()
+    } else {
+        query_hash(&event.fingerprint)
+    };
+}
+
+/// Normalizes SQL so that executions differing only in literal values,
+/// IN-list length, whitespace, comments or casing produce the same text.
+/// `sp_executesql` and prepared-statement wrappers are unwrapped first.
+pub fn fingerprint(sql: &str) -> String {
+    let mut tokens = tokenize(sql);
+    // A wrapped statement can itself be a wrapper (dynamic SQL built in a
+    // batch that was sent through sp_executesql); bound the unwrapping.
+    for _ in 0..4 {
+        match unwrap_statement(&tokens) {
+            Some(inner) => tokens = tokenize(&inner),
+            None => break,
+        }
+    }
+    render(&collapse_lists(tokens))
+}
+
+/// 64-bit FNV-1a of the fingerprint, as hex. Stable across runs and
+/// versions, unlike `std`'s hasher.
+pub fn query_hash(fingerprint: &str) -> String {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in fingerprint.as_bytes() {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    format!("{hash:016x}")
+}
+
+fn tokenize(sql: &str) -> Vec<Token> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '-' && next == Some('-') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c == '/' && next == Some('*') {
+            // Block comments nest in T-SQL.
+            let mut depth = 0;
+            while i < chars.len() {
+                if chars[i] == '/' && chars.get(i + 1) == Some(&'*') {
+                    depth += 1;
+                    i += 2;
+                } else if chars[i] == '*' && chars.get(i + 1) == Some(&'/') {
+                    depth -= 1;
+                    i += 2;
+                    if depth == 0 {
+                        break;
+                    }
+                } else {
+                    i += 1;
+                }
+            }
+        } else if c == '\'' || ((c == 'N' || c == 'n') && next == Some('\'')) {
+            i += if c == '\'' { 1 } else { 2 };
+            let (text, end) = read_delimited(&chars, i, '\'');
+            tokens.push(Token::Str(text));
+            i = end;
+        } else if c == '[' || c == '"' {
+            let close = if c == '[' { ']' } else { '"' };
+            let (name, end) = read_delimited(&chars, i + 1, close);
+            tokens.push(Token::Word(format!("{c}{}{close}", name.to_lowercase())));
+            i = end;
+        } else if c == '0' && matches!(next, Some('x' | 'X')) {
+            i += 2;
+            while i < chars.len() && chars[i].is_ascii_hexdigit() {
+                i += 1;
+            }
+            push_number(&mut tokens);
+        } else if c.is_ascii_digit() || (c == '.' && next.is_some_and(|n| n.is_ascii_digit())) {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                i += 1;
+                if i < chars.len() && matches!(chars[i], '+' | '-') {
+                    i += 1;
+                }
+                while i < chars.len() && chars[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            push_number(&mut tokens);
+        } else if c == '@' {
+            let start = i;
+            i += 1;
+            while i < chars.len() && (is_word_char(chars[i]) || chars[i] == '@') {
+                i += 1;
+            }
+            tokens.push(Token::Variable(chars[start..i].iter().collect::<String>().to_lowercase()));
+        } else if is_word_char(c) {
+            let start = i;
+            while i < chars.len() && (is_word_char(chars[i]) || chars[i] == '$') {
+                i += 1;
+            }
+            tokens.push(Token::Word(chars[start..i].iter().collect::<String>().to_lowercase()));
+        } else {
+            let pair: String = [c, next.unwrap_or(' ')].iter().collect();
+            if matches!(pair.as_str(), "<=" | ">=" | "<>" | "!=" | "!<" | "!>" | "+=" | "-=" | "*=" | "/=" | "::") {
+                tokens.push(Token::Symbol(pair));
+                i += 2;
+            } else {
+                tokens.push(Token::Symbol(c.to_string()));
+                i += 1;
+            }
+        }
+    }
+
+    // Trailing semicolons don't change the statement.
+    while tokens.last() == Some(&Token::Symbol(";".into())) {
+        tokens.pop();
+    }
+    tokens
+}
+
+/// Pushes a number, folding a sign in front of it into the literal so that
+/// `x = -5` and `x = 5` fingerprint the same. A `-` after an operand, as in
+/// `a - 5`, stays an operator.
+fn push_number(tokens: &mut Vec<Token>) {
+    let signed = matches!(tokens.last(), Some(Token::Symbol(s)) if s == "-" || s == "+");
+    if signed {
+        let operand = match tokens.len().checked_sub(2).map(|i| &tokens[i]) {
+            None => false,
+            Some(Token::Word(w)) => !SIGN_KEYWORDS.contains(&w.as_str()),
+            Some(Token::Symbol(s)) => s == ")",
+            Some(Token::Variable(_) | Token::Str(_) | Token::Number) => true,
+        };
+        if !operand {
+            tokens.pop();
+        }
+    }
+    tokens.push(Token::Number);
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '#'
+}
+
+/// Reads up to the closing delimiter, where a doubled delimiter is an
+/// escaped one. Returns the content and the index after the closing
+/// delimiter.
+fn read_delimited(chars: &[char], mut i: usize, close: char) -> (String, usize) {
+    let mut text = String::new();
+    while i < chars.len() {
+        if chars[i] == close {
+            if chars.get(i + 1) == Some(&close) {
+                text.push(close);
+                i += 2;
+                continue;
+            }
+            return (text, i + 1);
+        }
+        text.push(chars[i]);
+        i += 1;
+    }
+    (text, i)
+}
+
+/// Finds a wrapper call such as `exec sp_executesql N'...'` (possibly after
+/// the `declare`/`set` lines drivers add around prepared calls) and returns
+/// its statement argument.
+fn unwrap_statement(tokens: &[Token]) -> Option<String> {
+    tokens.iter().enumerate().find_map(|(i, token)| {
+        let Token::Word(name) = token else {
+            return None;
+        };
+        let name = name.trim_matches(|c| c == '[' || c == ']');
+        let (_, position) = WRAPPERS.iter().find(|(wrapper, _)| *wrapper == name)?;
+        match argument(&tokens[i + 1..], *position)? {
+            Token::Str(statement) => Some(statement.clone()),
+            _ => None,
+        }
+    })
+}
+
+/// The first token of the `position`-th top-level argument; `@p = value`
+/// arguments yield the value.
+fn argument(tokens: &[Token], position: usize) -> Option<&Token> {
+    let mut depth = 0;
+    let mut index = 0;
+    let mut start = true;
+    let mut iter = tokens.iter().peekable();
+
+    while let Some(token) = iter.next() {
+        match token {
+            Token::Symbol(s) if s == "(" => depth += 1,
+            Token::Symbol(s) if s == ")" => depth -= 1,
+            Token::Symbol(s) if s == "," && depth == 0 => {
+                index += 1;
+                start = true;
+                continue;
+            }
+            Token::Symbol(s) if s == ";" && depth == 0 => return None,
+            _ => {}
+        }
+        if start && index == position {
+            if matches!(token, Token::Variable(_)) && iter.peek() == Some(&&Token::Symbol("=".into())) {
+                iter.next();
+                return iter.next();
+            }
+            return Some(token);
+        }
+        start = false;
+    }
+    None
+}
+
+/// Replaces literals with `?` and collapses `IN (...)` lists made only of
+/// literals and variables to `in (?)`.
+fn collapse_lists(tokens: Vec<Token>) -> Vec<Token> {
+    let is_value = |t: &Token| matches!(t, Token::Str(_) | Token::Number | Token::Variable(_));
+    let mut out = Vec::with_capacity(tokens.len());
+    let mut i = 0;
+
+    while i < tokens.len() {
+        if tokens[i] == Token::Word("in".into()) && tokens.get(i + 1) == Some(&Token::Symbol("(".into())) {
+            let mut j = i + 2;
+            let mut values = 0;
+            while j < tokens.len() && is_value(&tokens[j]) {
+                values += 1;
+                j += 1;
+                if tokens.get(j) == Some(&Token::Symbol(",".into())) {
+                    j += 1;
+                } else {
+                    break;
+                }
+            }
+            if values > 0 && tokens.get(j) == Some(&Token::Symbol(")".into())) {
+                out.push(Token::Word("in".into()));
+                out.push(Token::Symbol("(".into()));
+                out.push(Token::Number);
+                out.push(Token::Symbol(")".into()));
+                i = j + 1;
+                continue;
+            }
+        }
+        out.push(tokens[i].clone());
+        i += 1;
+    }
+    out
+}
+
+fn render(tokens: &[Token]) -> String {
+    let mut out = String::new();
+    for token in tokens {
+        let text = match token {
+            Token::Word(w) | Token::Variable(w) | Token::Symbol(w) => w.as_str(),
+            Token::Str(_) | Token::Number => "?",
+        };
+        let tight = matches!(text, "," | ")" | "." | ";") || out.ends_with('(') || out.ends_with('.');
+        if !out.is_empty() && !tight {
+            out.push(' ');
+        }
+        out.push_str(text);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whitespace_casing_and_comments_are_ignored() {
+        let expected = "select a, b from dbo.t where c = ?";
+        assert_eq!(fingerprint("SELECT a,b FROM dbo.t WHERE c = 1"), expected);
+        assert_eq!(
+            fingerprint("select a, -- the key\n  b /* outer /* nested */ still comment */ from dbo.t\nwhere c=2;"),
+            expected
+        );
+    }
+
+    #[test]
+    fn literals_become_placeholders() {
+        let expected = "select * from t where a = ? and b = ? and c = ? and d = ?";
+        assert_eq!(fingerprint("select * from t where a = 'x' and b = N'it''s' and c = 0x1F and d = 1.5e3"), expected);
+        assert_eq!(fingerprint("select * from t where a = N'' and b = '' and c = 0xFF and d = .5"), expected);
+    }
+
+    #[test]
+    fn unary_minus_is_part_of_the_literal() {
+        assert_eq!(fingerprint("select * from t where x = -5"), fingerprint("select * from t where x = 5"));
+        assert_eq!(fingerprint("select -1"), "select ?");
+        assert_eq!(fingerprint("select * from t where x in (-1, 2)"), "select * from t where x in (?)");
+        assert_eq!(fingerprint("select a - 5 from t"), "select a - ? from t");
+        assert_eq!(fingerprint("select (a) - 5, @v - 1 from t"), "select (a) - ?, @v - ? from t");
+    }
+
+    #[test]
+    fn in_lists_collapse() {
+        let expected = "select * from t where id in (?)";
+        assert_eq!(fingerprint("select * from t where id in (1, 2, 3)"), expected);
+        assert_eq!(fingerprint("select * from t where id in ('a')"), expected);
+        assert_eq!(fingerprint("select * from t where id in (@p0, @p1)"), expected);
+        // Subqueries are left alone.
+        assert_eq!(
+            fingerprint("select * from t where id in (select id from u)"),
+            "select * from t where id in (select id from u)"
+        );
+    }
+
+    #[test]
+    fn sp_executesql_is_unwrapped() {
+        let expected = "select * from t where id = @id";
+        assert_eq!(
+            fingerprint("exec sp_executesql N'SELECT * FROM t WHERE id = @id', N'@id int', @id = 42"),
+            expected
+        );
+        assert_eq!(
+            fingerprint("EXEC [sp_executesql] @stmt = N'select * from t where id = @id', @params = N'@id int', @id = 7"),
+            expected
+        );
+        // Dynamic SQL inside dynamic SQL.
+        assert_eq!(
+            fingerprint("exec sp_executesql N'exec sp_executesql N''select * from t where id = @id'', N''@id int'', @id = 1'"),
+            expected
+        );
+    }
+
+    #[test]
+    fn prepared_statements_are_unwrapped() {
+        let sql = "declare @p1 int\nset @p1=NULL\nexec sp_prepexec @p1 output,N'@P0 int',N'select a from t where b = @P0',5\nselect @p1";
+        assert_eq!(fingerprint(sql), "select a from t where b = @p0");
+        assert_eq!(
+            fingerprint("exec sp_cursorprepexec @p1 output, @p2 output, N'@P0 int', N'select a from t where b = @P0', 1, 2, 3, 4"),
+            "select a from t where b = @p0"
+        );
+    }
+
+    #[test]
+    fn identifiers_keep_their_delimiters() {
+        assert_eq!(fingerprint("select [Order] from \"T\""), "select [order] from \"t\"");
+    }
+
+    #[test]
+    fn trailing_semicolons_are_dropped() {
+        assert_eq!(fingerprint("select 1;;"), fingerprint("select 2"));
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        // FNV-1a offset basis and a fixed value; changing either would split
+        // stored query stats across versions.
+        assert_eq!(query_hash(""), "cbf29ce484222325");
+        assert_eq!(query_hash("select ?"), query_hash(&fingerprint("SELECT 42")));
+        assert_eq!(query_hash("select ?"), "02fb7a5a1a5a9a58");
+    }
+}
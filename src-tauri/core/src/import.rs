@@ -0,0 +1,292 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::export::ExportFormat;
+use crate::fingerprint;
+use crate::profiler::{QueryError, QueryEvent, micros_to_ms};
+use crate::sessions::{SessionInfo, SessionStore};
+
+/// Columns a row can't be rebuilt without. Everything else in the export
+/// schema defaults to empty or zero when missing.
+const REQUIRED_COLUMNS: &[&str] = &["id", "event_name", "start_time"];
+
+/// Fields of the in-app event that exports derive or flatten; they are
+/// recomputed rather than kept as unknown columns.
+const DERIVED_COLUMNS: &[&str] = &["cpu_time_ms", "elapsed_time_ms"];
+
+/// Reads a CSV or NDJSON file written by the export and stores it as a
+/// read-only session. Columns outside the export schema are kept on each
+/// event's `extra`.
+pub fn import_session(store: &mut SessionStore, path: &Path) -> Result<SessionInfo, String> {
+    let format = detect_format(path)?;
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {e}", path.display()))?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Imported session".into());
+    let source = serde_json::json!({ "kind": "import", "path": path.to_string_lossy() });
+
+    match format {
+        ExportFormat::Csv => store.import(&name, &source, csv_records(file)?.map(|r| r.and_then(Record::into_event))),
+        ExportFormat::Ndjson => store.import(&name, &source, ndjson_records(file).map(|r| r.and_then(Record::into_event))),
+        ExportFormat::Parquet => unreachable!("rejected by detect_format"),
+    }
+}
+
+fn detect_format(path: &Path) -> Result<ExportFormat, String> {
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "csv" => Ok(ExportFormat::Csv),
+        "ndjson" | "jsonl" | "json" => Ok(ExportFormat::Ndjson),
+        "parquet" => Err("Parquet files can't be imported; export the session as CSV or JSON Lines instead".into()),
+        _ => Err("Unrecognized file type; expected a .csv, .ndjson or .jsonl export".into()),
+    }
+}
+
+/// One input row as column name to value, with the line it came from.
+struct Record {
+    line: u64,
+    fields: Map<String, Value>,
+}
+
+fn csv_records(file: File) -> Result<impl Iterator<Item = Result<Record, String>>, String> {
+    let mut reader = csv::Reader::from_reader(BufReader::new(file));
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("Failed to read CSV header: {e}"))?
+        .clone();
+
+    let mut seen = HashSet::new();
+    if let Some(duplicate) = headers.iter().find(|h| !seen.insert(*h)) {
+        return Err(format!("Column {duplicate} appears more than once"));
+    }
+    let missing: Vec<&str> = REQUIRED_COLUMNS.iter().copied().filter(|c| !seen.contains(c)).collect();
+    if !missing.is_empty() {
+        return Err(format!(
+            "Not a profiler export: missing column(s) {}",
+            missing.join(", ")
+        ));
+    }
+
+    Ok(reader.into_records().map(move |row| {
+        let row = row.map_err(|e| format!("Failed to read CSV: {e}"))?;
+        let line = row.position().map_or(0, |p| p.line());
+        let fields = headers
+            .iter()
+            .zip(row.iter())
+            .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+            .collect();
+        Ok(Record { line, fields })
+    }))
+}
+
+fn ndjson_records(file: File) -> impl Iterator<Item = Result<Record, String>> {
+    BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(text) if text.trim().is_empty()))
+        .map(|(index, line)| {
+            let line_number = index as u64 + 1;
+            let text = line.map_err(|e| format!("Line {line_number}: {e}"))?;
+            match serde_json::from_str(&text) {
+                Ok(Value::Object(fields)) => Ok(Record {
+                    line: line_number,
+                    fields,
+                }),
+                Ok(_) => Err(format!("Line {line_number}: expected a JSON object")),
+                Err(e) => Err(format!("Line {line_number}: invalid JSON: {e}")),
+            }
+        })
+}
+
+impl Record {
+    fn into_event(mut self) -> Result<QueryEvent, String> {
+        let id = self.required_text("id")?;
+        let event_name = self.required_text("event_name")?;
+        let start_time = self.required_text("start_time")?;
+
+        let cpu_time = self.number("cpu_time")?.unwrap_or(0);
+        let elapsed_time = self.number("elapsed_time")?.unwrap_or(0);
+        let error = match (self.number("error_number")?, self.take("error")) {
+            (Some(number), _) => Some(QueryError {
+                number,
+                severity: self.number("error_severity")?.unwrap_or(0),
+                message: self.text("error_message")?,
+            }),
+            // Events saved by the app itself nest the error.
+            (None, Some(Value::Object(error))) => serde_json::from_value(Value::Object(error)).ok(),
+            (None, _) => None,
+        };
+
+        let mut event = QueryEvent {
+            id,
+            session_id: self.number("session_id")?.unwrap_or(0),
+            start_time,
+            end_time: self.text("end_time")?,
+            event_name,
+            database_name: self.text("database_name")?,
+            cpu_time,
+            elapsed_time,
+            cpu_time_ms: micros_to_ms(cpu_time),
+            elapsed_time_ms: micros_to_ms(elapsed_time),
+            physical_reads: self.number("physical_reads")?,
+            writes: self.number("writes")?.unwrap_or(0),
+            logical_reads: self.number("logical_reads")?.unwrap_or(0),
+            row_count: self.number("row_count")?.unwrap_or(0),
+            sql_text: self.text("sql_text")?,
+            current_statement: self.text("current_statement")?,
+            fingerprint: self.text("fingerprint")?,
+            query_hash: self.text("query_hash")?,
+            login_name: self.text("login_name")?,
+            host_name: self.text("host_name")?,
+            program_name: self.text("program_name")?,
+            object_name: self.text("object_name")?,
+            client_process_id: self.number("client_process_id")?.unwrap_or(0),
+            nt_user_name: self.text("nt_user_name")?,
+            transaction_id: self.number("transaction_id")?.unwrap_or(0),
+            captured_at: self.text("captured_at")?,
+            event_status: self.optional_text("event_status")?.unwrap_or_else(|| "unknown".into()),
+            event_sequence: self.number("event_sequence")?.unwrap_or(0),
+            parent_id: self.optional_text("parent_id")?,
+            error,
+            extra: Map::new(),
+        };
+
+        if event.query_hash.is_empty() {
+            fingerprint::apply(&mut event);
+        }
+
+        // A previous import's unknown columns, then this file's.
+        match self.fields.remove("extra") {
+            Some(Value::Object(extra)) => event.extra.extend(extra),
+            Some(Value::String(text)) if !text.is_empty() => match serde_json::from_str(&text) {
+                Ok(Value::Object(extra)) => event.extra.extend(extra),
+                _ => {
+                    event.extra.insert("extra".into(), Value::String(text));
+                }
+            },
+            _ => {}
+        }
+        for column in DERIVED_COLUMNS {
+            self.fields.remove(*column);
+        }
+        event.extra.extend(self.fields);
+
+        Ok(event)
+    }
+
+    /// Removes a column, treating null and (CSV's) empty string as absent.
+    fn take(&mut self, name: &str) -> Option<Value> {
+        self.fields
+            .remove(name)
+            .filter(|v| !v.is_null() && v.as_str() != Some(""))
+    }
+
+    fn optional_text(&mut self, name: &str) -> Result<Option<String>, String> {
+        match self.take(name) {
+            None => Ok(None),
+            Some(Value::String(text)) => Ok(Some(text)),
+            Some(value @ (Value::Number(_) | Value::Bool(_))) => Ok(Some(value.to_string())),
+            Some(_) => Err(format!("Line {}: {name} must be text", self.line)),
+        }
+    }
+
+    fn text(&mut self, name: &str) -> Result<String, String> {
+        Ok(self.optional_text(name)?.unwrap_or_default())
+    }
+
+    fn required_text(&mut self, name: &str) -> Result<String, String> {
+        self.optional_text(name)?
+            .ok_or_else(|| format!("Line {}: {name} is missing", self.line))
+    }
+
+    fn number<T: TryFrom<i64>>(&mut self, name: &str) -> Result<Option<T>, String> {
+        let Some(value) = self.take(name) else {
+            return Ok(None);
+        };
+        let parsed = match &value {
+            Value::Number(n) => n.as_i64().or_else(|| n.as_f64().filter(|f| f.fract() == 0.0).map(|f| f as i64)),
+            Value::String(text) => text.trim().parse::<i64>().ok(),
+            _ => None,
+        };
+        parsed
+            .and_then(|n| T::try_from(n).ok())
+            .map(Some)
+            .ok_or_else(|| format!("Line {}: {name} must be a whole number, got {value}", self.line))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes `contents` to a new file in the temp dir and opens it.
+    fn file_with(contents: &str) -> File {
+        let path = std::env::temp_dir().join(format!("sqlprof-import-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        let file = File::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        file
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert!(matches!(detect_format(Path::new("a.CSV")), Ok(ExportFormat::Csv)));
+        assert!(matches!(detect_format(Path::new("a.jsonl")), Ok(ExportFormat::Ndjson)));
+        assert!(detect_format(Path::new("a.parquet")).unwrap_err().contains("CSV or JSON Lines"));
+        assert!(detect_format(Path::new("a.txt")).is_err());
+    }
+
+    #[test]
+    fn csv_without_required_columns_is_rejected() {
+        let error = csv_records(file_with("id,sql_text\n1,select 1\n")).err();
+        assert_eq!(
+            error.as_deref(),
+            Some("Not a profiler export: missing column(s) event_name, start_time")
+        );
+    }
+
+    #[test]
+    fn csv_with_a_repeated_column_is_rejected() {
+        let error = csv_records(file_with("id,event_name,start_time,id\n")).err();
+        assert_eq!(error.as_deref(), Some("Column id appears more than once"));
+    }
+
+    #[test]
+    fn csv_keeps_unknown_columns_as_extra() {
+        let csv = "id,event_name,start_time,sql_text,cpu_time,cpu_time_ms,wait_type\n\
+                   a,sql_batch_completed,2024-01-01T00:00:00,select 1,1500,1.5,PAGEIOLATCH_SH\n";
+        let events: Vec<QueryEvent> = csv_records(file_with(csv))
+            .unwrap()
+            .map(|r| r.and_then(Record::into_event))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].cpu_time_ms, 1.5);
+        assert_eq!(events[0].event_status, "unknown");
+        assert!(!events[0].query_hash.is_empty());
+        assert_eq!(events[0].extra.get("wait_type"), Some(&Value::String("PAGEIOLATCH_SH".into())));
+        assert!(!events[0].extra.contains_key("cpu_time_ms"));
+    }
+
+    #[test]
+    fn ndjson_reports_the_line_of_a_bad_record() {
+        let ndjson = "{\"id\":\"a\",\"event_name\":\"rpc_completed\",\"start_time\":\"t\"}\n\n\
+                      {\"id\":\"b\",\"start_time\":\"t\"}\n\
+                      [1]\n";
+        let results: Vec<Result<QueryEvent, String>> = ndjson_records(file_with(ndjson))
+            .map(|r| r.and_then(Record::into_event))
+            .collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(results[1].as_ref().err().map(String::as_str), Some("Line 3: event_name is missing"));
+        assert_eq!(results[2].as_ref().err().map(String::as_str), Some("Line 4: expected a JSON object"));
+    }
+}
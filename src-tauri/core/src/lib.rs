@@ -0,0 +1,20 @@
+//! The capture engine behind the profiler: connecting to SQL Server, running
+//! SQL Trace and Extended Events captures, the session store and exports.
+//! It knows nothing about the UI; captured events go to an `EventSink`.
+
+pub mod blocking;
+pub mod capture;
+pub mod db;
+pub mod deadlock;
+pub mod export;
+pub mod fingerprint;
+pub mod import;
+pub mod live;
+pub mod orphans;
+pub mod profiler;
+pub mod sessions;
+pub mod stats;
+mod trace;
+mod trace_file;
+mod xel_file;
+mod xevents;
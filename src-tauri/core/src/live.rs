@@ -0,0 +1,203 @@
+//! The live feed: the most recent query events of a capture. It is bounded
+//! so a long capture doesn't grow the UI without limit; events that leave it
+//! stay in the session store, where `SessionStore::events_window` pages
+//! through them.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::Serialize;
+
+use crate::profiler::QueryEvent;
+
+pub const DEFAULT_LIVE_EVENT_LIMIT: usize = 5000;
+const MIN_LIVE_EVENT_LIMIT: usize = 100;
+
+/// What the sink is sent after each poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveBatch {
+    /// New events, and updates to ones already in the feed, in capture
+    /// order. A completion carries the id of the start it completes.
+    pub events: Vec<QueryEvent>,
+    /// Events that have left the feed since the capture started. They are
+    /// the first `dropped` events of the recorded session.
+    pub dropped: u64,
+    /// How many events the feed keeps; the oldest beyond that are dropped.
+    pub limit: usize,
+    /// The session being recorded, to page through what was dropped. `None`
+    /// for file replays, which aren't recorded.
+    pub session_id: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct LiveFeed {
+    limit: usize,
+    events: VecDeque<QueryEvent>,
+    /// Position of each event in the feed, counted from the capture's first
+    /// event so positions stay valid as old ones are dropped.
+    positions: HashMap<String, u64>,
+    dropped: u64,
+    /// Requests that were still running when dropped. Their completions
+    /// update the stored row only; the feed has nowhere to put them.
+    dropped_running: HashSet<String>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIVE_EVENT_LIMIT)
+    }
+}
+
+impl LiveFeed {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit: limit.max(MIN_LIVE_EVENT_LIMIT),
+            events: VecDeque::new(),
+            positions: HashMap::new(),
+            dropped: 0,
+            dropped_running: HashSet::new(),
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Changes the limit, dropping the oldest events if the feed is over it.
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit.max(MIN_LIVE_EVENT_LIMIT);
+        self.trim();
+    }
+
+    /// Empties the feed for a new capture.
+    pub fn clear(&mut self) {
+        self.events.clear();
+        self.positions.clear();
+        self.dropped = 0;
+        self.dropped_running.clear();
+    }
+
+    /// Adds new events and applies updates to ones in the feed, then drops
+    /// the oldest beyond the limit. Returns what the UI needs to see: events
+    /// dropped within the same batch, and updates to dropped events, are
+    /// left out.
+    pub fn push(&mut self, events: Vec<QueryEvent>) -> Vec<QueryEvent> {
+        let mut kept = Vec::with_capacity(events.len());
+        for event in events {
+            if let Some(&position) = self.positions.get(&event.id) {
+                let index = (position - self.dropped) as usize;
+                self.events[index] = event.clone();
+            } else if self.dropped_running.remove(&event.id) {
+                continue;
+            } else {
+                self.positions.insert(event.id.clone(), self.dropped + self.events.len() as u64);
+                self.events.push_back(event.clone());
+            }
+            kept.push(event);
+        }
+        self.trim();
+        // Events this batch pushed out again never reach the UI.
+        kept.retain(|event| self.positions.contains_key(&event.id));
+        kept
+    }
+
+    fn trim(&mut self) {
+        while self.events.len() > self.limit {
+            let Some(event) = self.events.pop_front() else {
+                break;
+            };
+            self.positions.remove(&event.id);
+            if event.event_status == "running" {
+                self.dropped_running.insert(event.id);
+            }
+            self.dropped += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(id: &str, status: &str) -> QueryEvent {
+        let mut event = QueryEvent::for_test("sql_batch_completed", 52, 0);
+        event.id = id.into();
+        event.event_status = status.into();
+        event
+    }
+
+    fn events(range: std::ops::Range<usize>) -> Vec<QueryEvent> {
+        range.map(|i| event(&i.to_string(), "completed")).collect()
+    }
+
+    fn ids(feed: &LiveFeed) -> Vec<String> {
+        feed.events.iter().map(|e| e.id.clone()).collect()
+    }
+
+    #[test]
+    fn limit_has_a_floor() {
+        assert_eq!(LiveFeed::new(10).limit(), MIN_LIVE_EVENT_LIMIT);
+        let mut feed = LiveFeed::new(1000);
+        feed.set_limit(0);
+        assert_eq!(feed.limit(), MIN_LIVE_EVENT_LIMIT);
+    }
+
+    #[test]
+    fn oldest_events_are_dropped_and_counted() {
+        let mut feed = LiveFeed::new(100);
+        assert_eq!(feed.push(events(0..80)).len(), 80);
+        assert_eq!(feed.dropped(), 0);
+
+        assert_eq!(feed.push(events(80..130)).len(), 50);
+        assert_eq!(feed.dropped(), 30);
+        assert_eq!(ids(&feed).first().map(String::as_str), Some("30"));
+        assert_eq!(ids(&feed).len(), 100);
+    }
+
+    #[test]
+    fn events_dropped_within_their_batch_are_not_returned() {
+        let mut feed = LiveFeed::new(100);
+        let kept = feed.push(events(0..250));
+        assert_eq!(kept.len(), 100);
+        assert_eq!(kept[0].id, "150");
+        assert_eq!(feed.dropped(), 150);
+    }
+
+    #[test]
+    fn updates_replace_events_in_place() {
+        let mut feed = LiveFeed::new(100);
+        feed.push(vec![event("a", "running"), event("b", "completed")]);
+        let kept = feed.push(vec![event("a", "completed")]);
+        assert_eq!(kept.len(), 1);
+        assert_eq!(ids(&feed), ["a", "b"]);
+        assert_eq!(feed.events[0].event_status, "completed");
+    }
+
+    #[test]
+    fn completions_of_dropped_requests_are_left_out() {
+        let mut feed = LiveFeed::new(100);
+        feed.push(vec![event("running", "running")]);
+        feed.push(events(0..100));
+        assert_eq!(feed.dropped(), 1);
+
+        assert!(feed.push(vec![event("running", "completed")]).is_empty());
+        assert_eq!(feed.dropped(), 1);
+        assert!(!ids(&feed).contains(&"running".to_string()));
+    }
+
+    #[test]
+    fn set_limit_trims_and_clear_resets() {
+        let mut feed = LiveFeed::new(200);
+        feed.push(events(0..200));
+        feed.set_limit(150);
+        assert_eq!(feed.dropped(), 50);
+        assert_eq!(ids(&feed).len(), 150);
+
+        feed.clear();
+        assert_eq!(feed.dropped(), 0);
+        assert_eq!(feed.push(events(0..1)).len(), 1);
+    }
+}
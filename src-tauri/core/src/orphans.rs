@@ -0,0 +1,318 @@
+use serde::Serialize;
+
+use crate::db::SqlClient;
+use crate::trace;
+use crate::xevents;
+
+/// Traces the profiler created, by the file name `TRACE_CREATE_AND_START`
+/// gives them. One that outlived its capture keeps writing files into its
+/// directory until someone closes it.
+const ORPHANED_TRACES_SQL: &str = "
+SELECT
+    t.id AS trace_id,
+    t.path,
+    CAST(CASE WHEN t.status = 1 THEN 1 ELSE 0 END AS bit) AS running,
+    ISNULL(CONVERT(varchar(27), t.start_time, 126), '') AS start_time,
+    CONVERT(varchar(27), t.last_event_time, 126) AS last_event_time,
+    ISNULL(t.event_count, 0) AS event_count
+FROM sys.traces t
+WHERE t.path LIKE N'%SimpleSQLProfiler[_]%.trc'
+ORDER BY t.id
+";
+
+const ORPHANED_XE_SESSIONS_SQL: &str = "
+IF CONVERT(int, SERVERPROPERTY('EngineEdition')) = 5
+    SELECT
+        s.name AS session_name,
+        CAST(1 AS bit) AS database_scope,
+        CAST(CASE WHEN r.name IS NULL THEN 0 ELSE 1 END AS bit) AS running
+    FROM sys.database_event_sessions s
+    LEFT JOIN sys.dm_xe_database_sessions r ON r.name = s.name
+    WHERE s.name LIKE N'SimpleSQLProfiler[_]%'
+    ORDER BY s.name;
+ELSE
+    SELECT
+        s.name AS session_name,
+        CAST(0 AS bit) AS database_scope,
+        CAST(CASE WHEN r.name IS NULL THEN 0 ELSE 1 END AS bit) AS running
+    FROM sys.server_event_sessions s
+    LEFT JOIN sys.dm_xe_sessions r ON r.name = s.name
+    WHERE s.name LIKE N'SimpleSQLProfiler[_]%'
+    ORDER BY s.name;
+";
+
+/// Where the profiler's files may be: the error log, which is in the default
+/// directory, and the current files of its traces and XE file targets, which
+/// are in the directory their capture chose. Azure SQL has no files to list.
+const FILE_LOCATIONS_SQL: &str = "
+IF CONVERT(int, SERVERPROPERTY('EngineEdition')) IN (5, 8)
+    RETURN;
+
+SELECT CONVERT(nvarchar(260), SERVERPROPERTY('ErrorLogFileName')) AS path
+UNION ALL
+SELECT t.path
+FROM sys.traces t
+WHERE t.path LIKE N'%SimpleSQLProfiler[_]%.trc'
+UNION ALL
+SELECT CONVERT(nvarchar(260), f.value)
+FROM sys.server_event_session_fields f
+JOIN sys.server_event_sessions s ON s.event_session_id = f.event_session_id
+WHERE s.name LIKE N'SimpleSQLProfiler[_]%' AND f.name = N'filename';
+";
+
+/// Trace and event files in the directory `@P1`. Needs SQL Server 2017 or
+/// later.
+const LEFTOVER_FILES_SQL: &str = "
+SELECT
+    f.full_filesystem_path AS path,
+    f.size_in_bytes AS size_bytes,
+    CONVERT(varchar(27), f.last_write_time, 126) AS modified
+FROM sys.dm_os_enumerate_filesystem(@P1, N'SimpleSQLProfiler_*') f
+WHERE f.is_directory = 0
+ORDER BY f.last_write_time;
+";
+
+/// Other profiler connections to the server (the app and the CLI both
+/// connect under this name). A trace listed as orphaned may be theirs.
+const OTHER_CLIENTS_SQL: &str = "
+SELECT COUNT(*) AS other_clients
+FROM sys.dm_exec_sessions
+WHERE program_name = N'SimpleSQLProfiler' AND session_id <> @@SPID
+";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedTrace {
+    pub id: i32,
+    pub path: String,
+    pub running: bool,
+    pub start_time: String,
+    pub last_event_time: Option<String>,
+    pub event_count: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanedXeSession {
+    pub name: String,
+    pub database_scope: bool,
+    pub running: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LeftoverFile {
+    pub path: String,
+    pub size_bytes: i64,
+    pub modified: String,
+    /// Written by a trace or session that is still defined on the server.
+    pub in_use: bool,
+}
+
+/// What earlier captures left behind on the server.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrphanReport {
+    pub traces: Vec<OrphanedTrace>,
+    pub xe_sessions: Vec<OrphanedXeSession>,
+    pub files: Vec<LeftoverFile>,
+    /// Why `files` could not be listed, or not in every directory, e.g. on
+    /// SQL Server 2016 and older.
+    pub files_error: Option<String>,
+    pub other_clients: i32,
+}
+
+/// Lists traces, XE sessions and files the profiler created on the server.
+/// Meant to run while this profiler isn't capturing, so that everything it
+/// finds belongs to a capture that ended without cleaning up, or to another
+/// profiler instance (see `other_clients`).
+pub async fn find(client: &mut SqlClient) -> Result<OrphanReport, String> {
+    let traces = client
+        .simple_query(ORPHANED_TRACES_SQL)
+        .await
+        .map_err(|e| format!("Failed to list SQL Traces: {e}"))?
+        .into_first_result()
+        .await
+        .map_err(|e| format!("Failed to list SQL Traces: {e}"))?
+        .iter()
+        .map(|row| OrphanedTrace {
+            id: row.get::<i32, _>("trace_id").unwrap_or(0),
+            path: row.get::<&str, _>("path").unwrap_or("").to_string(),
+            running: row.get::<bool, _>("running").unwrap_or(false),
+            start_time: row.get::<&str, _>("start_time").unwrap_or("").to_string(),
+            last_event_time: row.get::<&str, _>("last_event_time").map(str::to_string),
+            event_count: row.get::<i64, _>("event_count").unwrap_or(0),
+        })
+        .collect::<Vec<_>>();
+
+    let xe_sessions = client
+        .simple_query(ORPHANED_XE_SESSIONS_SQL)
+        .await
+        .map_err(|e| format!("Failed to list Extended Events sessions: {e}"))?
+        .into_first_result()
+        .await
+        .map_err(|e| format!("Failed to list Extended Events sessions: {e}"))?
+        .iter()
+        .map(|row| OrphanedXeSession {
+            name: row.get::<&str, _>("session_name").unwrap_or("").to_string(),
+            database_scope: row.get::<bool, _>("database_scope").unwrap_or(false),
+            running: row.get::<bool, _>("running").unwrap_or(false),
+        })
+        .collect::<Vec<_>>();
+
+    // Listing files takes more permissions and a newer server than the
+    // rest; not being able to is worth a note, not a failed check.
+    let (files, files_error) = match list_files(client).await {
+        Ok(listed) => listed,
+        Err(e) => (Vec::new(), Some(e)),
+    };
+    // Traces are matched by their first file's name, without the rollover
+    // number their current file may have; rollover files and XE files carry
+    // a suffix after it.
+    let owners: Vec<String> = traces
+        .iter()
+        .map(|t| trace_base_name(&t.path))
+        .chain(xe_sessions.iter().map(|s| s.name.to_lowercase()))
+        .collect();
+    let files = files
+        .into_iter()
+        .map(|mut file| {
+            let name = file_name(&file.path);
+            file.in_use = owners.iter().any(|owner| name.starts_with(owner.as_str()));
+            file
+        })
+        .collect();
+
+    let other_clients = client
+        .simple_query(OTHER_CLIENTS_SQL)
+        .await
+        .map_err(|e| format!("Failed to list profiler connections: {e}"))?
+        .into_row()
+        .await
+        .map_err(|e| format!("Failed to list profiler connections: {e}"))?
+        .and_then(|row| row.get::<i32, _>("other_clients"))
+        .unwrap_or(0);
+
+    Ok(OrphanReport {
+        traces,
+        xe_sessions,
+        files,
+        files_error,
+        other_clients,
+    })
+}
+
+/// Stops and closes the given traces and stops and drops the given XE
+/// sessions, then checks again. Only ones `find` reports are touched, so a
+/// stale or made-up id can't close someone else's trace. Files stay where
+/// they are: SQL Server can't delete them.
+pub async fn clean_up(
+    client: &mut SqlClient,
+    trace_ids: &[i32],
+    xe_sessions: &[String],
+) -> Result<OrphanReport, String> {
+    let report = find(client).await?;
+    for trace in report.traces.iter().filter(|t| trace_ids.contains(&t.id)) {
+        trace::stop_and_close_trace(client, trace.id).await?;
+    }
+    for session in report.xe_sessions.iter().filter(|s| xe_sessions.contains(&s.name)) {
+        xevents::stop_and_drop_session(client, &session.name, session.database_scope).await?;
+    }
+    find(client).await
+}
+
+/// Lists the profiler's files in every directory it is known to have used,
+/// along with why a directory could not be listed, if one couldn't.
+async fn list_files(client: &mut SqlClient) -> Result<(Vec<LeftoverFile>, Option<String>), String> {
+    let locations = client
+        .simple_query(FILE_LOCATIONS_SQL)
+        .await
+        .map_err(|e| format!("Failed to list trace files: {e}"))?
+        .into_first_result()
+        .await
+        .map_err(|e| format!("Failed to list trace files: {e}"))?;
+    let mut directories: Vec<String> = Vec::new();
+    for row in &locations {
+        let Some(directory) = row.get::<&str, _>("path").and_then(directory) else {
+            continue;
+        };
+        if !directories.iter().any(|d| d.eq_ignore_ascii_case(directory)) {
+            directories.push(directory.to_string());
+        }
+    }
+
+    let mut files: Vec<LeftoverFile> = Vec::new();
+    let mut error = None;
+    for directory in &directories {
+        match list_directory(client, directory).await {
+            Ok(listed) => files.extend(listed),
+            Err(e) => {
+                error.get_or_insert(e);
+            }
+        }
+    }
+    Ok((files, error))
+}
+
+async fn list_directory(client: &mut SqlClient, directory: &str) -> Result<Vec<LeftoverFile>, String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(LEFTOVER_FILES_SQL);
+    query.bind(directory);
+    let rows = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to list trace files in {directory}: {e}"))?
+        .into_first_result()
+        .await
+        .map_err(|e| format!("Failed to list trace files in {directory}: {e}"))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| LeftoverFile {
+            path: row.get::<&str, _>("path").unwrap_or("").to_string(),
+            size_bytes: row.get::<i64, _>("size_bytes").unwrap_or(0),
+            modified: row.get::<&str, _>("modified").unwrap_or("").to_string(),
+            in_use: false,
+        })
+        .collect())
+}
+
+/// The directory part of a server-side path, with its trailing separator.
+fn directory(path: &str) -> Option<&str> {
+    path.rfind(['\\', '/']).map(|i| &path[..=i])
+}
+
+/// The lowercased name of a trace's first file without `.trc`: `name` for
+/// `name.trc` as well as for its rollover files `name_1.trc`, `name_2.trc`.
+fn trace_base_name(path: &str) -> String {
+    let name = file_name(path);
+    let stem = name.strip_suffix(".trc").unwrap_or(&name);
+    match stem.rsplit_once('_') {
+        Some((base, number)) if number.parse::<u32>().is_ok() => base.to_string(),
+        _ => stem.to_string(),
+    }
+}
+
+/// The lowercased last component of a server-side path, which may use either
+/// separator depending on the server's OS.
+fn file_name(path: &str) -> String {
+    path.rsplit(['\\', '/']).next().unwrap_or(path).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rollover_number_is_not_part_of_the_base_name() {
+        let first = r"D:\Traces\SimpleSQLProfiler_0f3a9c2e7b6d4e1f8a5b3c2d1e0f9a8b.trc";
+        let rolled = r"D:\Traces\SimpleSQLProfiler_0f3a9c2e7b6d4e1f8a5b3c2d1e0f9a8b_12.trc";
+        assert_eq!(trace_base_name(first), "simplesqlprofiler_0f3a9c2e7b6d4e1f8a5b3c2d1e0f9a8b");
+        assert_eq!(trace_base_name(rolled), trace_base_name(first));
+        assert_eq!(trace_base_name("/var/opt/mssql/log/SimpleSQLProfiler_ab_3.trc"), "simplesqlprofiler_ab");
+    }
+
+    #[test]
+    fn directory_keeps_its_separator() {
+        assert_eq!(directory(r"C:\MSSQL\Log\ERRORLOG"), Some(r"C:\MSSQL\Log\"));
+        assert_eq!(directory("/var/opt/mssql/log/errorlog"), Some("/var/opt/mssql/log/"));
+        assert_eq!(directory("errorlog"), None);
+    }
+}
@@ -0,0 +1,927 @@
+use std::path::{Path, PathBuf};
+use std::sync::{
+    Arc, Mutex,
+    atomic::{AtomicBool, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+use crate::blocking::{self, BlockedProcessReport};
+use crate::capture::{self, CaptureFilter, CaptureOptions, CaptureSource, CaptureSourceKind, InFlightTracker, PollPacer};
+use crate::db::{self, ConnectionConfig, SqlClient};
+use crate::deadlock::{self, DeadlockGraph};
+use crate::fingerprint;
+use crate::live::{LiveBatch, LiveFeed};
+use crate::orphans::{self, OrphanReport};
+use crate::sessions::{
+    EventWindow, SessionData, SessionInfo, SessionRecord, SessionRecorder, SessionStore, WindowAnchor,
+};
+use crate::stats::{QueryAggregate, QueryStats, QueryStatsOrder, QueryStatsSnapshot};
+
+const CAPTURE_SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(15);
+const STATS_EMIT_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+const STATS_EVENT_LIMIT: usize = 100;
+/// Commands on the control connection try this many times to reopen it.
+const CONTROL_RECONNECT_ATTEMPTS: u32 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryEvent {
+    pub id: String,
+    pub session_id: i32,
+    pub start_time: String,
+    /// Empty for starting events and events without a duration.
+    pub end_time: String,
+    pub event_name: String,
+    pub database_name: String,
+    /// Microseconds.
+    pub cpu_time: i64,
+    /// Microseconds.
+    pub elapsed_time: i64,
+    pub cpu_time_ms: f64,
+    pub elapsed_time_ms: f64,
+    /// `None` where the source has no such column (SQL Trace).
+    pub physical_reads: Option<i64>,
+    pub writes: i64,
+    pub logical_reads: i64,
+    pub row_count: i64,
+    pub sql_text: String,
+    pub current_statement: String,
+    /// `current_statement` (or `sql_text`) with literals and formatting
+    /// normalized away; see `fingerprint::fingerprint`.
+    pub fingerprint: String,
+    /// Stable hash of `fingerprint`, for grouping executions of one query.
+    pub query_hash: String,
+    pub login_name: String,
+    pub host_name: String,
+    pub program_name: String,
+    /// The stored procedure or other module the event ran in.
+    pub object_name: String,
+    pub client_process_id: i32,
+    pub nt_user_name: String,
+    pub transaction_id: i64,
+    pub captured_at: String,
+    /// `running` until the matching completion arrives, then `completed`,
+    /// `failed` or `cancelled`; `unknown` when the completion was never seen.
+    pub event_status: String,
+    pub event_sequence: i64,
+    /// The batch or RPC a statement-level event ran in, when it was seen.
+    pub parent_id: Option<String>,
+    /// Set on error events, and on a batch or RPC that raised one.
+    pub error: Option<QueryError>,
+    /// Columns an imported file had that the profiler doesn't know about.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+pub(crate) fn micros_to_ms(us: i64) -> f64 {
+    us as f64 / 1000.0
+}
+
+#[cfg(test)]
+impl QueryEvent {
+    /// A blank event for unit tests.
+    pub(crate) fn for_test(event_name: &str, session_id: i32, event_sequence: i64) -> Self {
+        Self {
+            id: String::new(),
+            session_id,
+            start_time: "2024-01-01T00:00:00.000".into(),
+            end_time: String::new(),
+            event_name: event_name.into(),
+            database_name: String::new(),
+            cpu_time: 0,
+            elapsed_time: 0,
+            cpu_time_ms: 0.0,
+            elapsed_time_ms: 0.0,
+            physical_reads: None,
+            writes: 0,
+            logical_reads: 0,
+            row_count: 0,
+            sql_text: String::new(),
+            current_statement: String::new(),
+            fingerprint: String::new(),
+            query_hash: String::new(),
+            login_name: String::new(),
+            host_name: String::new(),
+            program_name: String::new(),
+            object_name: String::new(),
+            client_process_id: 0,
+            nt_user_name: String::new(),
+            transaction_id: 0,
+            captured_at: String::new(),
+            event_status: String::new(),
+            event_sequence,
+            parent_id: None,
+            error: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryError {
+    pub number: i32,
+    pub severity: i32,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProfilerStatus {
+    pub connected: bool,
+    pub capturing: bool,
+    pub error: Option<String>,
+    /// Set while a capture that lost its connection is trying to get it
+    /// back: the attempt under way. `error` holds why it was lost.
+    pub reconnect_attempt: Option<u32>,
+    /// The last poll hit its row limit: events arrive late because the
+    /// server produces them faster than the capture reads them.
+    pub behind: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResultData {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+}
+
+/// Receives what the profiler captures. The desktop app forwards each call
+/// to the webview as an event; anything else (a CLI, a server, a test) can
+/// collect them its own way. Calls come from the capture task and must not
+/// block it.
+pub trait EventSink: Send + Sync + 'static {
+    fn status(&self, status: &ProfilerStatus);
+    /// Query events from one poll, after the live feed dropped what it has
+    /// no room for.
+    fn query_events(&self, batch: &LiveBatch);
+    fn deadlock(&self, graph: &DeadlockGraph);
+    fn blocked_process(&self, report: &BlockedProcessReport);
+    fn query_stats(&self, snapshot: &QueryStatsSnapshot);
+}
+
+pub enum ProfilerCommand {
+    Connect {
+        config: ConnectionConfig,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    Disconnect {
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    StartCapture {
+        source: CaptureSourceKind,
+        options: CaptureOptions,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    StopCapture {
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    ExecuteQuery {
+        sql: String,
+        reply: oneshot::Sender<Result<QueryResultData, String>>,
+    },
+    GetBlockedProcessThreshold {
+        reply: oneshot::Sender<Result<i32, String>>,
+    },
+    SetBlockedProcessThreshold {
+        seconds: i32,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    FindOrphanedCaptures {
+        reply: oneshot::Sender<Result<OrphanReport, String>>,
+    },
+    CleanUpOrphanedCaptures {
+        trace_ids: Vec<i32>,
+        xe_sessions: Vec<String>,
+        reply: oneshot::Sender<Result<OrphanReport, String>>,
+    },
+    GetQueryStats {
+        order: QueryStatsOrder,
+        limit: usize,
+        reply: oneshot::Sender<Vec<QueryAggregate>>,
+    },
+    ClearQueryStats {
+        reply: oneshot::Sender<()>,
+    },
+    ListSessions {
+        reply: oneshot::Sender<Result<Vec<SessionInfo>, String>>,
+    },
+    /// Opens a session with its last `limit` query events.
+    OpenSession {
+        id: String,
+        limit: u64,
+        reply: oneshot::Sender<Result<SessionData, String>>,
+    },
+    RenameSession {
+        id: String,
+        name: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    DeleteSession {
+        id: String,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    SetLiveEventLimit {
+        limit: usize,
+        reply: oneshot::Sender<()>,
+    },
+    /// Up to `limit` query events just before the event `before`, just
+    /// after the event `after`, or the latest ones when neither is given.
+    FetchSessionEvents {
+        id: String,
+        before: Option<String>,
+        after: Option<String>,
+        limit: u64,
+        reply: oneshot::Sender<Result<EventWindow, String>>,
+    },
+}
+
+/// What the capture task shares with the profiler loop.
+struct CaptureFeeds {
+    stats: Arc<Mutex<QueryStats>>,
+    live: Arc<Mutex<LiveFeed>>,
+}
+
+/// Runs the profiler until every sender of `rx` is dropped. Must run on a
+/// Tokio runtime, which the capture task is spawned onto. Live captures are
+/// recorded in the session store at `sessions_path`.
+pub async fn profiler_loop(
+    mut rx: mpsc::Receiver<ProfilerCommand>,
+    sink: Arc<dyn EventSink>,
+    sessions_path: PathBuf,
+) {
+    // Dropped when a command finds it broken and reopened by the next one;
+    // the profiler counts as connected as long as there is a config.
+    let mut control_client: Option<SqlClient> = None;
+    let mut active_config: Option<ConnectionConfig> = None;
+    let mut capture_task: Option<JoinHandle<()>> = None;
+    let mut capture_run_flag: Option<Arc<AtomicBool>> = None;
+    // Shared with the capture task; kept across captures until cleared.
+    let stats = Arc::new(Mutex::new(QueryStats::default()));
+    // Shared with the capture task; emptied when a capture starts.
+    let live = Arc::new(Mutex::new(LiveFeed::default()));
+    // Live captures are recorded here; a store that fails to open only
+    // disables recording and the session commands.
+    let sessions = SessionStore::open(&sessions_path);
+    let mut recording_session: Option<String> = None;
+    // Loading a saved session, which replaces the stats when it finishes.
+    let mut opening_session: Option<JoinHandle<()>> = None;
+
+    fn emit_status(sink: &dyn EventSink, connected: bool, capturing: bool, error: Option<String>) {
+        sink.status(&ProfilerStatus {
+            connected,
+            capturing,
+            error,
+            reconnect_attempt: None,
+            behind: false,
+        });
+    }
+
+    /// The control connection, reopened first if a previous command lost it.
+    async fn open_control<'a>(
+        client: &'a mut Option<SqlClient>,
+        config: Option<&ConnectionConfig>,
+    ) -> Result<&'a mut SqlClient, String> {
+        let Some(config) = config else {
+            return Err("Not connected".into());
+        };
+        if client.is_none() {
+            let mut backoff = capture::RECONNECT_BACKOFF_INITIAL;
+            let mut attempt = 1;
+            *client = Some(loop {
+                match db::connect(config).await {
+                    Ok(c) => break c,
+                    Err(e) if attempt == CONTROL_RECONNECT_ATTEMPTS || !db::is_connection_error(&e) => {
+                        return Err(format!("Connection lost and could not be reopened: {e}"));
+                    }
+                    Err(_) => {
+                        tokio::time::sleep(backoff).await;
+                        backoff *= 2;
+                        attempt += 1;
+                    }
+                }
+            });
+        }
+        Ok(client.as_mut().expect("opened above"))
+    }
+
+    /// Forgets a control connection that failed, so the next command reopens it.
+    fn check_control<T>(client: &mut Option<SqlClient>, result: &Result<T, String>) {
+        if result.as_ref().is_err_and(|e| db::is_connection_error(e)) {
+            *client = None;
+        }
+    }
+
+    async fn stop_capture_now(
+        capture_run_flag: &mut Option<Arc<AtomicBool>>,
+        capture_task: &mut Option<JoinHandle<()>>,
+    ) {
+        if let Some(flag) = capture_run_flag.take() {
+            flag.store(false, Ordering::Release);
+        }
+        if let Some(mut task) = capture_task.take() {
+            // The task stops and cleans up its source on the way out; only
+            // abandon it if the server does not answer in time.
+            if tokio::time::timeout(CAPTURE_SHUTDOWN_TIMEOUT, &mut task).await.is_err() {
+                task.abort();
+            }
+        }
+    }
+
+    fn capture_running(capture_task: &Option<JoinHandle<()>>) -> bool {
+        capture_task.as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    while let Some(cmd) = rx.recv().await {
+        match cmd {
+            ProfilerCommand::Connect { config, reply } => {
+                stop_capture_now(&mut capture_run_flag, &mut capture_task).await;
+
+                match db::connect(&config).await {
+                    Ok(c) => {
+                        control_client = Some(c);
+                        active_config = Some(config);
+                        emit_status(&*sink, true, false, None);
+                        let _ = reply.send(Ok(()));
+                    }
+                    Err(e) => {
+                        control_client = None;
+                        active_config = None;
+                        emit_status(&*sink, false, false, Some(e.clone()));
+                        let _ = reply.send(Err(e));
+                    }
+                }
+            }
+            ProfilerCommand::Disconnect { reply } => {
+                stop_capture_now(&mut capture_run_flag, &mut capture_task).await;
+
+                control_client = None;
+                active_config = None;
+                emit_status(&*sink, false, false, None);
+                let _ = reply.send(Ok(()));
+            }
+            ProfilerCommand::StartCapture { source, options, reply } => {
+                stop_capture_now(&mut capture_run_flag, &mut capture_task).await;
+                // Its stats must not land on top of the new capture's.
+                if let Some(task) = opening_session.take() {
+                    let _ = task.await;
+                }
+
+                let connected = active_config.is_some();
+                let source_kind = source.clone();
+                let mut source = match capture::create_source(source, &options, active_config.as_ref()) {
+                    Ok(source) => source,
+                    Err(e) => {
+                        let _ = reply.send(Err(e));
+                        continue;
+                    }
+                };
+
+                if let Err(e) = source.start().await {
+                    let _ = source.cleanup().await;
+                    emit_status(&*sink, connected, false, Some(e.clone()));
+                    let _ = reply.send(Err(e));
+                    continue;
+                }
+
+                // Replays are saved too, so events beyond the live feed can
+                // be paged back in from the session.
+                let started = match (&sessions, active_config.as_ref(), source_kind.file_path()) {
+                    (Ok(store), _, Some(path)) => Some(store.start_replay(file_name(path), &source_kind)),
+                    (Ok(store), Some(config), None) => {
+                        Some(store.start_recording(&config.server_name, &source_kind, &options))
+                    }
+                    _ => None,
+                };
+                let (recorder, recording_error) = match started {
+                    Some(Ok(recorder)) => (Some(recorder), None),
+                    Some(Err(e)) => (None, Some(format!("Capturing without saving the session: {e}"))),
+                    None => (None, None),
+                };
+                recording_session = recorder.as_ref().map(|r| r.id().to_string());
+
+                // Live sources checked the stop time when they were created.
+                let stop_at = match source_kind.is_offline() {
+                    true => None,
+                    false => options.stop_deadline().ok().flatten(),
+                };
+                live.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                let feeds = CaptureFeeds {
+                    stats: stats.clone(),
+                    live: live.clone(),
+                };
+                let run_flag = Arc::new(AtomicBool::new(true));
+                capture_run_flag = Some(run_flag.clone());
+                capture_task = Some(spawn_capture_task(
+                    sink.clone(),
+                    source,
+                    run_flag,
+                    feeds,
+                    recorder,
+                    connected,
+                    stop_at,
+                ));
+                emit_status(&*sink, connected, true, recording_error);
+                let _ = reply.send(Ok(()));
+            }
+            ProfilerCommand::StopCapture { reply } => {
+                if let Some(flag) = capture_run_flag.as_ref() {
+                    flag.store(false, Ordering::Release);
+                }
+                emit_status(&*sink, active_config.is_some(), false, None);
+                let _ = reply.send(Ok(()));
+
+                stop_capture_now(&mut capture_run_flag, &mut capture_task).await;
+            }
+            ProfilerCommand::ExecuteQuery { sql, reply } => {
+                let result = match open_control(&mut control_client, active_config.as_ref()).await {
+                    Ok(client) => execute_user_query(client, &sql).await,
+                    Err(e) => Err(e),
+                };
+                check_control(&mut control_client, &result);
+                let _ = reply.send(result);
+            }
+            ProfilerCommand::GetBlockedProcessThreshold { reply } => {
+                let result = match open_control(&mut control_client, active_config.as_ref()).await {
+                    Ok(client) => blocking::get_blocked_process_threshold(client).await,
+                    Err(e) => Err(e),
+                };
+                check_control(&mut control_client, &result);
+                let _ = reply.send(result);
+            }
+            ProfilerCommand::SetBlockedProcessThreshold { seconds, reply } => {
+                let result = match open_control(&mut control_client, active_config.as_ref()).await {
+                    Ok(client) => blocking::set_blocked_process_threshold(client, seconds).await,
+                    Err(e) => Err(e),
+                };
+                check_control(&mut control_client, &result);
+                let _ = reply.send(result);
+            }
+            ProfilerCommand::FindOrphanedCaptures { reply } => {
+                // A running capture's own trace would be listed as orphaned.
+                if capture_running(&capture_task) {
+                    let _ = reply.send(Err("Stop the capture before checking for orphaned traces".into()));
+                    continue;
+                }
+                let result = match open_control(&mut control_client, active_config.as_ref()).await {
+                    Ok(client) => orphans::find(client).await,
+                    Err(e) => Err(e),
+                };
+                check_control(&mut control_client, &result);
+                let _ = reply.send(result);
+            }
+            ProfilerCommand::CleanUpOrphanedCaptures { trace_ids, xe_sessions, reply } => {
+                if capture_running(&capture_task) {
+                    let _ = reply.send(Err("Stop the capture before cleaning up orphaned traces".into()));
+                    continue;
+                }
+                let result = match open_control(&mut control_client, active_config.as_ref()).await {
+                    Ok(client) => orphans::clean_up(client, &trace_ids, &xe_sessions).await,
+                    Err(e) => Err(e),
+                };
+                check_control(&mut control_client, &result);
+                let _ = reply.send(result);
+            }
+            ProfilerCommand::GetQueryStats { order, limit, reply } => {
+                let top = stats.lock().unwrap_or_else(|e| e.into_inner()).top(order, limit);
+                let _ = reply.send(top);
+            }
+            ProfilerCommand::ClearQueryStats { reply } => {
+                stats.lock().unwrap_or_else(|e| e.into_inner()).clear();
+                emit_stats(&*sink, &stats);
+                let _ = reply.send(());
+            }
+            ProfilerCommand::ListSessions { reply } => {
+                let _ = reply.send(sessions.as_ref().map_err(Clone::clone).and_then(SessionStore::list));
+            }
+            ProfilerCommand::OpenSession { id, limit, reply } => {
+                if capture_running(&capture_task) {
+                    let _ = reply.send(Err("Stop the capture before opening a saved session".into()));
+                    continue;
+                }
+                if let Err(e) = &sessions {
+                    let _ = reply.send(Err(e.clone()));
+                    continue;
+                }
+                if let Some(task) = opening_session.take() {
+                    let _ = task.await;
+                }
+                // Reading a large session takes a while; do it on its own
+                // handle, off the profiler loop, like exports and imports.
+                let path = sessions_path.clone();
+                let sink = sink.clone();
+                let stats = stats.clone();
+                opening_session = Some(tokio::task::spawn_blocking(move || {
+                    let result = load_session(&path, &id, limit).map(|(data, session_stats)| {
+                        *stats.lock().unwrap_or_else(|e| e.into_inner()) = session_stats;
+                        data
+                    });
+                    if result.is_ok() {
+                        emit_stats(&*sink, &stats);
+                    }
+                    let _ = reply.send(result);
+                }));
+            }
+            ProfilerCommand::RenameSession { id, name, reply } => {
+                let _ = reply.send(sessions.as_ref().map_err(Clone::clone).and_then(|store| store.rename(&id, &name)));
+            }
+            ProfilerCommand::DeleteSession { id, reply } => {
+                if capture_running(&capture_task) && recording_session.as_deref() == Some(id.as_str()) {
+                    let _ = reply.send(Err("Stop the capture before deleting the session it is recording".into()));
+                    continue;
+                }
+                let _ = reply.send(sessions.as_ref().map_err(Clone::clone).and_then(|store| store.delete(&id)));
+            }
+            ProfilerCommand::SetLiveEventLimit { limit, reply } => {
+                live.lock().unwrap_or_else(|e| e.into_inner()).set_limit(limit);
+                let _ = reply.send(());
+            }
+            ProfilerCommand::FetchSessionEvents { id, before, after, limit, reply } => {
+                let anchor = match (before.as_deref(), after.as_deref()) {
+                    (_, Some(after)) => WindowAnchor::After(after),
+                    (Some(before), None) => WindowAnchor::Before(before),
+                    (None, None) => WindowAnchor::Latest,
+                };
+                let result = sessions
+                    .as_ref()
+                    .map_err(Clone::clone)
+                    .and_then(|store| store.events_window(&id, anchor, limit));
+                let _ = reply.send(result);
+            }
+        }
+    }
+
+    stop_capture_now(&mut capture_run_flag, &mut capture_task).await;
+}
+
+fn spawn_capture_task(
+    sink: Arc<dyn EventSink>,
+    mut source: Box<dyn CaptureSource>,
+    run_flag: Arc<AtomicBool>,
+    feeds: CaptureFeeds,
+    mut recorder: Option<SessionRecorder>,
+    connected: bool,
+    stop_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let CaptureFeeds { stats, live } = feeds;
+        let session_id = recorder.as_ref().map(|r| r.id().to_string());
+        let mut pacer = PollPacer::default();
+        let mut delay = std::time::Duration::ZERO;
+        let mut behind = false;
+        let mut in_flight = InFlightTracker::default();
+        let mut stats_dirty = false;
+        let mut last_stats_emit = tokio::time::Instant::now();
+        // The error that led to the last reconnect, until a poll succeeds.
+        let mut reconnected_after: Option<String> = None;
+
+        loop {
+            if !run_flag.load(Ordering::Acquire) {
+                break;
+            }
+            capture::wait_while_running(&run_flag, delay).await;
+            if !run_flag.load(Ordering::Acquire) {
+                break;
+            }
+
+            if stats_dirty && last_stats_emit.elapsed() >= STATS_EMIT_INTERVAL {
+                emit_stats(&*sink, &stats);
+                stats_dirty = false;
+                last_stats_emit = tokio::time::Instant::now();
+            }
+
+            let polled = tokio::time::timeout(capture::POLL_TIMEOUT, source.poll_batch())
+                .await
+                .unwrap_or_else(|_| Err(format!("Polling failed: {}", db::CONNECTION_TIMED_OUT)));
+            let events = match polled {
+                Ok(Some(events)) => {
+                    reconnected_after = None;
+                    events
+                }
+                Ok(None) => {
+                    sink.status(&ProfilerStatus {
+                        connected,
+                        capturing: false,
+                        error: None,
+                        reconnect_attempt: None,
+                        behind: false,
+                    });
+                    break;
+                }
+                // Failing the same way straight after reconnecting means the
+                // connection was not the problem.
+                Err(e) if db::is_connection_error(&e) && reconnected_after.as_deref() != Some(e.as_str()) => {
+                    let reconnected = capture::reconnect(&mut *source, &run_flag, e.clone(), |attempt, error| {
+                        sink.status(&ProfilerStatus {
+                            connected,
+                            capturing: true,
+                            error: Some(error.to_string()),
+                            reconnect_attempt: Some(attempt),
+                            behind: false,
+                        });
+                    })
+                    .await;
+                    match reconnected {
+                        Ok(()) => {
+                            sink.status(&ProfilerStatus {
+                                connected,
+                                capturing: true,
+                                error: None,
+                                reconnect_attempt: None,
+                                behind: false,
+                            });
+                            reconnected_after = Some(e);
+                            continue;
+                        }
+                        Err(e) => {
+                            sink.status(&ProfilerStatus {
+                                connected,
+                                capturing: false,
+                                error: e,
+                                reconnect_attempt: None,
+                                behind: false,
+                            });
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    sink.status(&ProfilerStatus {
+                        connected,
+                        capturing: false,
+                        error: Some(e),
+                        reconnect_attempt: None,
+                        behind: false,
+                    });
+                    break;
+                }
+            };
+
+            delay = pacer.next_delay(events.len(), source.behind());
+            if source.behind() != behind {
+                behind = source.behind();
+                sink.status(&ProfilerStatus {
+                    connected,
+                    capturing: true,
+                    error: None,
+                    reconnect_attempt: None,
+                    behind,
+                });
+            }
+
+            // Checked after polling so the events from just before the stop
+            // time are kept.
+            let stop_time_reached = stop_at.is_some_and(|stop_at| chrono::Utc::now() >= stop_at);
+            if stop_time_reached {
+                sink.status(&ProfilerStatus {
+                    connected,
+                    capturing: false,
+                    error: None,
+                    reconnect_attempt: None,
+                    behind: false,
+                });
+            }
+            if events.is_empty() {
+                if stop_time_reached {
+                    break;
+                }
+                continue;
+            }
+
+            let now = chrono::Utc::now().to_rfc3339();
+            let mut batch_stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+            let mut records = Vec::new();
+            let mut queries = Vec::new();
+            for mut event in events {
+                if !run_flag.load(Ordering::Acquire) {
+                    break;
+                }
+                match event.event_name.as_str() {
+                    "deadlock_graph" => {
+                        records.push(SessionRecord::Deadlock(emit_deadlock(&*sink, event, &now)));
+                        continue;
+                    }
+                    "blocked_process_report" => {
+                        records.push(SessionRecord::Blocked(emit_blocked_process_report(&*sink, event, &now)));
+                        continue;
+                    }
+                    _ => fingerprint::apply(&mut event),
+                }
+                for mut event in in_flight.track(event) {
+                    event.captured_at = now.clone();
+                    batch_stats.record(&event);
+                    queries.push(event);
+                }
+            }
+            drop(batch_stats);
+            records.extend(queries.iter().cloned().map(SessionRecord::Query));
+            emit_live(&*sink, &live, session_id.as_deref(), queries);
+            record(&*sink, &mut recorder, records, connected, true);
+            stats_dirty = true;
+            if stop_time_reached {
+                break;
+            }
+        }
+
+        if stats_dirty {
+            emit_stats(&*sink, &stats);
+        }
+
+        // Nothing will complete these any more; stop their live counters.
+        let now = chrono::Utc::now().to_rfc3339();
+        let mut queries = in_flight.abandon_all();
+        for event in &mut queries {
+            event.captured_at = now.clone();
+        }
+        let records = queries.iter().cloned().map(SessionRecord::Query).collect();
+        emit_live(&*sink, &live, session_id.as_deref(), queries);
+        record(&*sink, &mut recorder, records, connected, false);
+
+        let _ = source.stop().await;
+        let _ = source.cleanup().await;
+    })
+}
+
+/// Loads a saved session along with stats for all of its queries, since top
+/// queries cover the whole session, not only the events sent to the feed.
+fn load_session(path: &Path, id: &str, limit: u64) -> Result<(SessionData, QueryStats), String> {
+    let store = SessionStore::open_handle(path)?;
+    let data = store.load(id, limit)?;
+    let mut stats = QueryStats::default();
+    store.for_each_event(id, &CaptureFilter::default(), |event| {
+        stats.record(&event);
+        Ok(())
+    })?;
+    Ok((data, stats))
+}
+
+/// The last component of `path`, which may use either separator.
+fn file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).find(|part| !part.is_empty()).unwrap_or(path)
+}
+
+/// Hands `records` to the session recorder. If saving has failed, the
+/// capture carries on unsaved and the status says why.
+fn record(
+    sink: &dyn EventSink,
+    recorder: &mut Option<SessionRecorder>,
+    records: Vec<SessionRecord>,
+    connected: bool,
+    capturing: bool,
+) {
+    let Some(Err(e)) = recorder.as_ref().map(|r| r.record(records)) else {
+        return;
+    };
+    *recorder = None;
+    sink.status(&ProfilerStatus {
+        connected,
+        capturing,
+        error: Some(format!("Stopped saving the session: {e}")),
+        reconnect_attempt: None,
+        behind: false,
+    });
+}
+
+/// Passes a poll's query events through the live feed and sends the sink
+/// what is left of them.
+fn emit_live(sink: &dyn EventSink, live: &Mutex<LiveFeed>, session_id: Option<&str>, events: Vec<QueryEvent>) {
+    if events.is_empty() {
+        return;
+    }
+    let mut live = live.lock().unwrap_or_else(|e| e.into_inner());
+    let batch = LiveBatch {
+        events: live.push(events),
+        dropped: live.dropped(),
+        limit: live.limit(),
+        session_id: session_id.map(str::to_string),
+    };
+    drop(live);
+    sink.query_events(&batch);
+}
+
+fn emit_stats(sink: &dyn EventSink, stats: &Mutex<QueryStats>) {
+    let snapshot: QueryStatsSnapshot = stats
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .snapshot(QueryStatsOrder::default(), STATS_EVENT_LIMIT);
+    sink.query_stats(&snapshot);
+}
+
+/// Sources deliver deadlock reports as events carrying the XML in
+/// `sql_text`; they go to the sink parsed, as a `DeadlockGraph`.
+fn emit_deadlock(sink: &dyn EventSink, event: QueryEvent, captured_at: &str) -> DeadlockGraph {
+    // Keep reports that fail to parse so the raw XML can still be copied.
+    let mut graph = deadlock::parse_deadlock_graph(&event.sql_text).unwrap_or_else(|_| DeadlockGraph {
+        xml: event.sql_text,
+        ..DeadlockGraph::default()
+    });
+    graph.id = uuid::Uuid::new_v4().to_string();
+    graph.event_time = event.start_time;
+    graph.captured_at = captured_at.to_string();
+    sink.deadlock(&graph);
+    graph
+}
+
+/// Like deadlocks, blocked process reports arrive with the XML in `sql_text`
+/// and go to the sink parsed, or with the parse error and the raw XML.
+fn emit_blocked_process_report(sink: &dyn EventSink, event: QueryEvent, captured_at: &str) -> BlockedProcessReport {
+    let mut report = blocking::parse_blocked_process_report(&event.sql_text).unwrap_or_else(|e| BlockedProcessReport {
+        xml: event.sql_text,
+        parse_error: Some(e),
+        ..BlockedProcessReport::default()
+    });
+    report.id = uuid::Uuid::new_v4().to_string();
+    report.event_time = event.start_time;
+    report.captured_at = captured_at.to_string();
+    sink.blocked_process(&report);
+    report
+}
+
+async fn execute_user_query(
+    client: &mut SqlClient,
+    sql: &str,
+) -> Result<QueryResultData, String> {
+    let stream = client
+        .simple_query(sql)
+        .await
+        .map_err(|e| format!("{e}"))?;
+
+    let result_sets = stream
+        .into_results()
+        .await
+        .map_err(|e| format!("{e}"))?;
+
+    // Use the first result set that has columns
+    for result_set in &result_sets {
+        if result_set.is_empty() {
+            continue;
+        }
+
+        let columns: Vec<String> = result_set[0]
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+
+        if columns.is_empty() {
+            continue;
+        }
+
+        let rows: Vec<Vec<serde_json::Value>> = result_set
+            .iter()
+            .map(|row| {
+                columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, _)| row_value_to_json(row, i))
+                    .collect()
+            })
+            .collect();
+
+        return Ok(QueryResultData { columns, rows });
+    }
+
+    Ok(QueryResultData {
+        columns: vec![],
+        rows: vec![],
+    })
+}
+
+fn row_value_to_json(row: &tiberius::Row, idx: usize) -> serde_json::Value {
+    use serde_json::Value;
+
+    // Try common types in order of likelihood
+    if let Some(v) = row.try_get::<&str, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<i32, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<i64, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<i16, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<u8, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<f64, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<f32, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<bool, _>(idx).ok().flatten() {
+        return Value::from(v);
+    }
+    if let Some(v) = row.try_get::<tiberius::numeric::Numeric, _>(idx).ok().flatten() {
+        return Value::from(v.to_string());
+    }
+    if let Some(v) = row.try_get::<&[u8], _>(idx).ok().flatten() {
+        let hex: String = v.iter().map(|b| format!("{b:02X}")).collect();
+        return Value::from(format!("0x{hex}"));
+    }
+
+    // For datetime and other types, use Debug formatting as fallback
+    Value::from(format!("{:?}", row.try_get::<&str, _>(idx)))
+}
@@ -0,0 +1,726 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, mpsc};
+
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::blocking::BlockedProcessReport;
+use crate::capture::{CaptureFilter, CaptureOptions, CaptureSourceKind};
+use crate::deadlock::DeadlockGraph;
+use crate::profiler::QueryEvent;
+
+/// Imported events are written in transactions of this many, so a large
+/// file doesn't lock out a recording capture for the whole import.
+const IMPORT_BATCH_SIZE: usize = 10_000;
+
+/// Schema changes, applied in order; `PRAGMA user_version` holds how many
+/// have been applied.
+const MIGRATIONS: &[&str] = &[SCHEMA_V1_SQL, SCHEMA_V2_SQL, SCHEMA_V3_SQL];
+
+const SCHEMA_V1_SQL: &str = "
+CREATE TABLE IF NOT EXISTS sessions (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    server_name TEXT NOT NULL,
+    source TEXT NOT NULL,
+    options TEXT NOT NULL,
+    started_at TEXT NOT NULL,
+    stopped_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS session_records (
+    session_id TEXT NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    record_id TEXT NOT NULL,
+    data TEXT NOT NULL,
+    PRIMARY KEY (session_id, kind, record_id)
+);
+";
+
+/// Imported sessions are read-only: nothing records into them.
+const SCHEMA_V2_SQL: &str = "
+ALTER TABLE sessions ADD COLUMN read_only INTEGER NOT NULL DEFAULT 0;
+";
+
+/// Index entries are ordered by rowid after their key, so a page of a
+/// session's events by position is a range of this index.
+const SCHEMA_V3_SQL: &str = "
+CREATE INDEX IF NOT EXISTS session_records_position ON session_records (session_id, kind);
+";
+
+/// Sessions still open when the app last exited (or crashed) never got a
+/// stop time; use their last record instead.
+const CLOSE_ABANDONED_SQL: &str = "
+UPDATE sessions
+SET stopped_at = COALESCE(
+    (SELECT MAX(json_extract(r.data, '$.captured_at')) FROM session_records r WHERE r.session_id = sessions.id),
+    started_at)
+WHERE stopped_at IS NULL
+";
+
+const LIST_SESSIONS_SQL: &str = "
+SELECT s.id, s.name, s.server_name, s.source, s.options, s.started_at, s.stopped_at, s.read_only,
+       (SELECT COUNT(*) FROM session_records r WHERE r.session_id = s.id AND r.kind = 'query')
+FROM sessions s
+";
+
+/// A session's query events in capture order, with a `CaptureFilter`
+/// applied. SQLite's LIKE has the same wildcards as SQL Server's, minus
+/// character classes.
+const FILTERED_EVENTS_SQL: &str = "
+SELECT data FROM session_records
+WHERE session_id = ?1 AND kind = 'query'
+  AND (?2 IS NULL OR json_extract(data, '$.database_name') LIKE ?2)
+  AND (?3 IS NULL OR json_extract(data, '$.login_name') LIKE ?3)
+  AND (?4 IS NULL OR json_extract(data, '$.host_name') LIKE ?4)
+  AND (?5 IS NULL OR json_extract(data, '$.program_name') LIKE ?5)
+  AND (?6 IS NULL OR json_extract(data, '$.elapsed_time') >= ?6)
+  AND (?7 IS NULL OR json_extract(data, '$.logical_reads') >= ?7)
+  AND (?8 IS NULL OR json_extract(data, '$.sql_text') LIKE ?8 OR json_extract(data, '$.current_statement') LIKE ?8)
+ORDER BY rowid
+";
+
+/// The position of a query event in its session: its rowid, which the
+/// UPSERT keeps when a completion replaces its start.
+const EVENT_POSITION_SQL: &str = "
+SELECT rowid FROM session_records
+WHERE session_id = ?1 AND kind = 'query' AND record_id = ?2
+";
+
+/// The last `?3` query events before position `?2`, newest first.
+const EVENTS_BEFORE_SQL: &str = "
+SELECT rowid, data FROM session_records
+WHERE session_id = ?1 AND kind = 'query' AND rowid < ?2
+ORDER BY rowid DESC
+LIMIT ?3
+";
+
+/// The first `?3` query events after position `?2`, oldest first.
+const EVENTS_AFTER_SQL: &str = "
+SELECT rowid, data FROM session_records
+WHERE session_id = ?1 AND kind = 'query' AND rowid > ?2
+ORDER BY rowid
+LIMIT ?3
+";
+
+/// Query events before position `?2`; all of them with `i64::MAX`.
+const COUNT_EVENTS_BEFORE_SQL: &str = "
+SELECT COUNT(*) FROM session_records
+WHERE session_id = ?1 AND kind = 'query' AND rowid < ?2
+";
+
+/// Completions reuse the id of their starting event, so they replace the
+/// stored row while keeping its position.
+const UPSERT_RECORD_SQL: &str = "
+INSERT INTO session_records (session_id, kind, record_id, data)
+VALUES (?1, ?2, ?3, ?4)
+ON CONFLICT (session_id, kind, record_id) DO UPDATE SET data = excluded.data
+";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionInfo {
+    pub id: String,
+    pub name: String,
+    pub server_name: String,
+    pub source: serde_json::Value,
+    /// The `CaptureOptions` the capture ran with, including its filters.
+    pub options: serde_json::Value,
+    pub started_at: String,
+    /// `None` while the session is still being recorded.
+    pub stopped_at: Option<String>,
+    /// Imported from a file rather than recorded.
+    pub read_only: bool,
+    pub event_count: i64,
+}
+
+/// An opened session. Only its latest query events are loaded; the rest are
+/// paged through with `SessionStore::events_window`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionData {
+    pub session: SessionInfo,
+    pub window: EventWindow,
+    pub deadlocks: Vec<DeadlockGraph>,
+    pub blocked_reports: Vec<BlockedProcessReport>,
+}
+
+/// Part of a session's query events, see `SessionStore::events_window`.
+#[derive(Debug, Clone, Serialize)]
+pub struct EventWindow {
+    /// How many query events come before the window.
+    pub offset: u64,
+    pub events: Vec<QueryEvent>,
+    /// How many query events the session has in all.
+    pub total: u64,
+}
+
+/// Where a window of query events is taken from, by the id of an event
+/// already shown.
+#[derive(Debug, Clone, Copy)]
+pub enum WindowAnchor<'a> {
+    Latest,
+    Before(&'a str),
+    After(&'a str),
+}
+
+/// Something the sink was sent during a capture, in the form it was sent.
+pub enum SessionRecord {
+    Query(QueryEvent),
+    Deadlock(DeadlockGraph),
+    Blocked(BlockedProcessReport),
+}
+
+/// Past captures kept in a SQLite database (the desktop app's lives in its
+/// data dir). Events are stored as the JSON the sink receives, so opening a
+/// session replays exactly what was shown live.
+pub struct SessionStore {
+    path: PathBuf,
+    conn: Connection,
+}
+
+impl SessionStore {
+    /// Opens or creates the store at `path`, bringing its schema up to date.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let path = path.to_path_buf();
+        let mut conn = connect(&path)?;
+        migrate(&mut conn)?;
+        conn.execute(CLOSE_ABANDONED_SQL, [])
+            .map_err(|e| format!("Failed to close abandoned sessions: {e}"))?;
+        Ok(Self { path, conn })
+    }
+
+    /// A second handle on the store for long operations such as exports
+    /// and imports, which would otherwise hold up the profiler loop. The
+    /// store must already have been created by `open`.
+    pub fn open_handle(path: &Path) -> Result<Self, String> {
+        let path = path.to_path_buf();
+        let conn = connect(&path)?;
+        Ok(Self { path, conn })
+    }
+
+    /// All sessions, most recent first.
+    pub fn list(&self) -> Result<Vec<SessionInfo>, String> {
+        let mut stmt = self
+            .conn
+            .prepare(&format!("{LIST_SESSIONS_SQL} ORDER BY s.started_at DESC"))
+            .map_err(|e| format!("Failed to list sessions: {e}"))?;
+        let rows = stmt
+            .query_map([], session_from_row)
+            .map_err(|e| format!("Failed to list sessions: {e}"))?;
+        rows.collect::<Result<_, _>>()
+            .map_err(|e| format!("Failed to list sessions: {e}"))
+    }
+
+    /// The session with its last `limit` query events and all of its
+    /// deadlocks and blocked process reports.
+    pub fn load(&self, id: &str, limit: u64) -> Result<SessionData, String> {
+        let session = self
+            .conn
+            .query_row(&format!("{LIST_SESSIONS_SQL} WHERE s.id = ?1"), [id], session_from_row)
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => "Session not found".to_string(),
+                e => format!("Failed to open session: {e}"),
+            })?;
+
+        let mut data = SessionData {
+            session,
+            window: self.events_window(id, WindowAnchor::Latest, limit)?,
+            deadlocks: Vec::new(),
+            blocked_reports: Vec::new(),
+        };
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT kind, data FROM session_records WHERE session_id = ?1 AND kind <> 'query' ORDER BY rowid")
+            .map_err(|e| format!("Failed to open session: {e}"))?;
+        let mut rows = stmt.query([id]).map_err(|e| format!("Failed to open session: {e}"))?;
+        while let Some(row) = rows.next().map_err(|e| format!("Failed to read session: {e}"))? {
+            let kind: String = row.get(0).map_err(|e| format!("Failed to read session: {e}"))?;
+            let json: String = row.get(1).map_err(|e| format!("Failed to read session: {e}"))?;
+            // Skip records an older or newer version wrote in a shape this
+            // one can't read rather than failing the whole session.
+            match kind.as_str() {
+                "deadlock" => data.deadlocks.extend(serde_json::from_str(&json).ok()),
+                "blocked" => data.blocked_reports.extend(serde_json::from_str(&json).ok()),
+                _ => {}
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Up to `limit` of a session's query events next to `anchor`, in
+    /// capture order. The page itself is read by position, so reading it
+    /// costs the same however deep into the session it is; the offset and
+    /// total are counted over the session's index entries, which takes time
+    /// in proportion to its size. Works on a session that is still being
+    /// recorded.
+    pub fn events_window(&self, id: &str, anchor: WindowAnchor, limit: u64) -> Result<EventWindow, String> {
+        let (sql, position) = match anchor {
+            WindowAnchor::Latest => (EVENTS_BEFORE_SQL, i64::MAX),
+            WindowAnchor::Before(event_id) => (EVENTS_BEFORE_SQL, self.event_position(id, event_id)?),
+            WindowAnchor::After(event_id) => (EVENTS_AFTER_SQL, self.event_position(id, event_id)?),
+        };
+        let newest_first = !matches!(anchor, WindowAnchor::After(_));
+
+        let mut stmt = self
+            .conn
+            .prepare_cached(sql)
+            .map_err(|e| format!("Failed to read session: {e}"))?;
+        let mut rows = stmt
+            .query(params![id, position, limit])
+            .map_err(|e| format!("Failed to read session: {e}"))?;
+        let mut first_position = None;
+        let mut events = Vec::new();
+        while let Some(row) = rows.next().map_err(|e| format!("Failed to read session: {e}"))? {
+            let position: i64 = row.get(0).map_err(|e| format!("Failed to read session: {e}"))?;
+            let json: String = row.get(1).map_err(|e| format!("Failed to read session: {e}"))?;
+            if newest_first || first_position.is_none() {
+                first_position = Some(position);
+            }
+            events.extend(serde_json::from_str::<QueryEvent>(&json).ok());
+        }
+        if newest_first {
+            events.reverse();
+        }
+
+        let count_before = |position: i64| -> Result<u64, String> {
+            self.conn
+                .query_row(COUNT_EVENTS_BEFORE_SQL, params![id, position], |row| row.get(0))
+                .map_err(|e| format!("Failed to read session: {e}"))
+        };
+        let total = count_before(i64::MAX)?;
+        let offset = match first_position {
+            Some(position) => count_before(position)?,
+            None if newest_first => 0,
+            None => total,
+        };
+
+        Ok(EventWindow { offset, events, total })
+    }
+
+    fn event_position(&self, id: &str, event_id: &str) -> Result<i64, String> {
+        self.conn
+            .query_row(EVENT_POSITION_SQL, [id, event_id], |row| row.get(0))
+            .map_err(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => "That event is not in the session yet".to_string(),
+                e => format!("Failed to read session: {e}"),
+            })
+    }
+
+    /// Streams a session's query events matching `filter` to `f` without
+    /// loading the session into memory.
+    pub fn for_each_event(
+        &self,
+        id: &str,
+        filter: &CaptureFilter,
+        mut f: impl FnMut(QueryEvent) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let exists: bool = self
+            .conn
+            .query_row("SELECT EXISTS (SELECT 1 FROM sessions WHERE id = ?1)", [id], |row| row.get(0))
+            .map_err(|e| format!("Failed to read session: {e}"))?;
+        if !exists {
+            return Err("Session not found".into());
+        }
+
+        let filter = filter.normalized();
+        let mut stmt = self
+            .conn
+            .prepare(FILTERED_EVENTS_SQL)
+            .map_err(|e| format!("Failed to read session: {e}"))?;
+        let mut rows = stmt
+            .query(params![
+                id,
+                filter.database_name,
+                filter.login_name,
+                filter.host_name,
+                filter.program_name,
+                filter.min_duration_ms.map(|ms| ms * 1000),
+                filter.min_logical_reads,
+                filter.text_like,
+            ])
+            .map_err(|e| format!("Failed to read session: {e}"))?;
+        while let Some(row) = rows.next().map_err(|e| format!("Failed to read session: {e}"))? {
+            let json: String = row.get(0).map_err(|e| format!("Failed to read session: {e}"))?;
+            if let Ok(event) = serde_json::from_str(&json) {
+                f(event)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates a read-only session from `events`. On error nothing is kept.
+    pub fn import(
+        &mut self,
+        name: &str,
+        source: &serde_json::Value,
+        events: impl Iterator<Item = Result<QueryEvent, String>>,
+    ) -> Result<SessionInfo, String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().to_rfc3339();
+        self.conn
+            .execute(
+                "INSERT INTO sessions (id, name, server_name, source, options, started_at, read_only) VALUES (?1, ?2, '', ?3, 'null', ?4, 1)",
+                params![id, name, source.to_string(), now],
+            )
+            .map_err(|e| format!("Failed to create session: {e}"))?;
+
+        let result = self.import_events(&id, events);
+        if let Err(e) = result {
+            let _ = self.delete(&id);
+            return Err(e);
+        }
+
+        self.conn
+            .query_row(&format!("{LIST_SESSIONS_SQL} WHERE s.id = ?1"), [&id], session_from_row)
+            .map_err(|e| format!("Failed to read session: {e}"))
+    }
+
+    /// Writes the events and sets the session's time span to the span of
+    /// their capture times.
+    fn import_events(&mut self, id: &str, events: impl Iterator<Item = Result<QueryEvent, String>>) -> Result<(), String> {
+        let mut first: Option<String> = None;
+        let mut last: Option<String> = None;
+        let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+
+        for event in events {
+            let event = event?;
+            let time = if event.captured_at.is_empty() { &event.start_time } else { &event.captured_at };
+            if !time.is_empty() {
+                if first.as_ref().is_none_or(|t| time < t) {
+                    first = Some(time.clone());
+                }
+                if last.as_ref().is_none_or(|t| time > t) {
+                    last = Some(time.clone());
+                }
+            }
+            batch.push(SessionRecord::Query(event));
+            if batch.len() >= IMPORT_BATCH_SIZE {
+                write_batch(&mut self.conn, id, &batch)?;
+                batch.clear();
+            }
+        }
+        write_batch(&mut self.conn, id, &batch)?;
+
+        let now = chrono::Utc::now().to_rfc3339();
+        self.conn
+            .execute(
+                "UPDATE sessions SET started_at = ?2, stopped_at = ?3 WHERE id = ?1",
+                params![id, first.as_deref().unwrap_or(&now), last.as_deref().unwrap_or(&now)],
+            )
+            .map_err(|e| format!("Failed to write session: {e}"))?;
+        Ok(())
+    }
+
+    pub fn rename(&self, id: &str, name: &str) -> Result<(), String> {
+        let changed = self
+            .conn
+            .execute("UPDATE sessions SET name = ?2 WHERE id = ?1", params![id, name])
+            .map_err(|e| format!("Failed to rename session: {e}"))?;
+        if changed == 0 {
+            return Err("Session not found".into());
+        }
+        Ok(())
+    }
+
+    pub fn delete(&self, id: &str) -> Result<(), String> {
+        let changed = self
+            .conn
+            .execute("DELETE FROM sessions WHERE id = ?1", [id])
+            .map_err(|e| format!("Failed to delete session: {e}"))?;
+        if changed == 0 {
+            return Err("Session not found".into());
+        }
+        Ok(())
+    }
+
+    /// Creates a session and starts a writer thread for it. Records are
+    /// written in the background so a slow disk never stalls the capture;
+    /// the session is stopped once the recorder is dropped.
+    pub fn start_recording(
+        &self,
+        server_name: &str,
+        source: &CaptureSourceKind,
+        options: &CaptureOptions,
+    ) -> Result<SessionRecorder, String> {
+        let name = format!("{server_name} {}", chrono::Local::now().format("%Y-%m-%d %H:%M"));
+        self.start_session(&name, server_name, source, Some(options), false)
+    }
+
+    /// Like `start_recording`, for replaying a capture file: the session is
+    /// read-only, like an imported one, and named `name`.
+    pub fn start_replay(&self, name: &str, source: &CaptureSourceKind) -> Result<SessionRecorder, String> {
+        self.start_session(name, "", source, None, true)
+    }
+
+    fn start_session(
+        &self,
+        name: &str,
+        server_name: &str,
+        source: &CaptureSourceKind,
+        options: Option<&CaptureOptions>,
+        read_only: bool,
+    ) -> Result<SessionRecorder, String> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let source = serde_json::to_string(source).map_err(|e| format!("Serialization error: {e}"))?;
+        let options = serde_json::to_string(&options).map_err(|e| format!("Serialization error: {e}"))?;
+
+        self.conn
+            .execute(
+                "INSERT INTO sessions (id, name, server_name, source, options, started_at, read_only) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![id, name, server_name, source, options, chrono::Utc::now().to_rfc3339(), read_only],
+            )
+            .map_err(|e| format!("Failed to create session: {e}"))?;
+
+        let conn = connect(&self.path)?;
+        let (tx, rx) = mpsc::channel();
+        let failure = Arc::new(Mutex::new(None));
+        let session_id = id.clone();
+        let thread_failure = failure.clone();
+        std::thread::spawn(move || write_records(conn, &session_id, rx, &thread_failure));
+
+        Ok(SessionRecorder { id, tx, failure })
+    }
+}
+
+pub struct SessionRecorder {
+    id: String,
+    tx: mpsc::Sender<Vec<SessionRecord>>,
+    /// Why the writer thread gave up, once it has.
+    failure: Arc<Mutex<Option<String>>>,
+}
+
+impl SessionRecorder {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Queues `records` for the writer thread. Fails once a write has
+    /// failed; nothing more is saved after that.
+    pub fn record(&self, records: Vec<SessionRecord>) -> Result<(), String> {
+        if let Some(e) = self.failure() {
+            return Err(e);
+        }
+        if records.is_empty() {
+            return Ok(());
+        }
+        self.tx
+            .send(records)
+            .map_err(|_| self.failure().unwrap_or_else(|| "Failed to write session: the writer stopped".into()))
+    }
+
+    fn failure(&self) -> Option<String> {
+        self.failure.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+fn connect(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("Failed to open session store: {e}"))?;
+    // WAL lets the UI read past sessions while a recorder is writing; the
+    // busy timeout covers a recorder and an import writing at once.
+    conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA foreign_keys = ON;")
+        .map_err(|e| format!("Failed to open session store: {e}"))?;
+    conn.busy_timeout(std::time::Duration::from_secs(10))
+        .map_err(|e| format!("Failed to open session store: {e}"))?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> Result<(), String> {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| format!("Failed to read session store version: {e}"))?;
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().map_err(|e| format!("Failed to upgrade session store: {e}"))?;
+        tx.execute_batch(sql)
+            .and_then(|_| tx.pragma_update(None, "user_version", index + 1))
+            .and_then(|_| tx.commit())
+            .map_err(|e| format!("Failed to upgrade session store: {e}"))?;
+    }
+    Ok(())
+}
+
+fn session_from_row(row: &rusqlite::Row) -> rusqlite::Result<SessionInfo> {
+    let json = |idx: usize| -> rusqlite::Result<serde_json::Value> {
+        let text: String = row.get(idx)?;
+        Ok(serde_json::from_str(&text).unwrap_or(serde_json::Value::Null))
+    };
+    Ok(SessionInfo {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        server_name: row.get(2)?,
+        source: json(3)?,
+        options: json(4)?,
+        started_at: row.get(5)?,
+        stopped_at: row.get(6)?,
+        read_only: row.get(7)?,
+        event_count: row.get(8)?,
+    })
+}
+
+/// Writer thread body: drains whatever has queued up into one transaction
+/// per wake-up, and stamps the stop time when the recorder goes away or a
+/// write fails. A failed write is left in `failure` for the recorder.
+fn write_records(
+    mut conn: Connection,
+    session_id: &str,
+    rx: mpsc::Receiver<Vec<SessionRecord>>,
+    failure: &Mutex<Option<String>>,
+) {
+    while let Ok(mut records) = rx.recv() {
+        while let Ok(more) = rx.try_recv() {
+            records.extend(more);
+        }
+        if let Err(e) = write_batch(&mut conn, session_id, &records) {
+            *failure.lock().unwrap_or_else(|e| e.into_inner()) = Some(e);
+            break;
+        }
+    }
+    // Closed only after `failure` is set, so a failed send finds the reason.
+    drop(rx);
+
+    let _ = conn.execute(
+        "UPDATE sessions SET stopped_at = ?2 WHERE id = ?1",
+        params![session_id, chrono::Utc::now().to_rfc3339()],
+    );
+}
+
+fn write_batch(conn: &mut Connection, session_id: &str, records: &[SessionRecord]) -> Result<(), String> {
+    let tx = conn.transaction().map_err(|e| format!("Failed to write session: {e}"))?;
+    {
+        let mut stmt = tx
+            .prepare_cached(UPSERT_RECORD_SQL)
+            .map_err(|e| format!("Failed to write session: {e}"))?;
+        for record in records {
+            let (kind, id, json) = match record {
+                SessionRecord::Query(e) => ("query", &e.id, serde_json::to_string(e)),
+                SessionRecord::Deadlock(g) => ("deadlock", &g.id, serde_json::to_string(g)),
+                SessionRecord::Blocked(r) => ("blocked", &r.id, serde_json::to_string(r)),
+            };
+            let json = json.map_err(|e| format!("Serialization error: {e}"))?;
+            stmt.execute(params![session_id, kind, id, json])
+                .map_err(|e| format!("Failed to write session: {e}"))?;
+        }
+    }
+    tx.commit().map_err(|e| format!("Failed to write session: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempStore {
+        path: PathBuf,
+        store: SessionStore,
+    }
+
+    impl Drop for TempStore {
+        fn drop(&mut self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let _ = std::fs::remove_file(format!("{}{suffix}", self.path.display()));
+            }
+        }
+    }
+
+    /// A store holding one imported session with events `0`..`count`.
+    fn store_with_events(count: usize) -> (TempStore, String) {
+        let path = std::env::temp_dir().join(format!("sqlprof-sessions-{}.db", uuid::Uuid::new_v4()));
+        let mut store = SessionStore::open(&path).unwrap();
+        let events = (0..count).map(|i| {
+            let mut event = QueryEvent::for_test("sql_batch_completed", 52, i as i64);
+            event.id = i.to_string();
+            Ok(event)
+        });
+        let session = store.import("test", &serde_json::Value::Null, events).unwrap();
+        (TempStore { path, store }, session.id)
+    }
+
+    fn ids(window: &EventWindow) -> Vec<String> {
+        window.events.iter().map(|e| e.id.clone()).collect()
+    }
+
+    #[test]
+    fn latest_window_is_the_tail() {
+        let (temp, id) = store_with_events(10);
+        let window = temp.store.events_window(&id, WindowAnchor::Latest, 3).unwrap();
+        assert_eq!(ids(&window), ["7", "8", "9"]);
+        assert_eq!((window.offset, window.total), (7, 10));
+
+        let data = temp.store.load(&id, 4).unwrap();
+        assert_eq!(ids(&data.window), ["6", "7", "8", "9"]);
+        assert_eq!(data.session.event_count, 10);
+    }
+
+    #[test]
+    fn windows_page_by_position() {
+        let (temp, id) = store_with_events(10);
+        let older = temp.store.events_window(&id, WindowAnchor::Before("7"), 3).unwrap();
+        assert_eq!(ids(&older), ["4", "5", "6"]);
+        assert_eq!(older.offset, 4);
+
+        let first = temp.store.events_window(&id, WindowAnchor::Before("2"), 3).unwrap();
+        assert_eq!(ids(&first), ["0", "1"]);
+        assert_eq!(first.offset, 0);
+
+        let newer = temp.store.events_window(&id, WindowAnchor::After("6"), 2).unwrap();
+        assert_eq!(ids(&newer), ["7", "8"]);
+        assert_eq!(newer.offset, 7);
+
+        let past_end = temp.store.events_window(&id, WindowAnchor::After("9"), 2).unwrap();
+        assert!(past_end.events.is_empty());
+        assert_eq!(past_end.offset, 10);
+
+        assert!(temp.store.events_window(&id, WindowAnchor::Before("missing"), 2).is_err());
+    }
+
+    #[test]
+    fn replays_are_saved_as_read_only_sessions() {
+        let (temp, _) = store_with_events(0);
+        let source = CaptureSourceKind::TraceFile {
+            path: r"D:\Traces\capture.trc".into(),
+            on_server: false,
+        };
+        let recorder = temp.store.start_replay("capture.trc", &source).unwrap();
+        let id = recorder.id().to_string();
+        let records = (0..6)
+            .map(|i| {
+                let mut event = QueryEvent::for_test("sql_batch_completed", 52, i);
+                event.id = i.to_string();
+                SessionRecord::Query(event)
+            })
+            .collect();
+        recorder.record(records).unwrap();
+        drop(recorder);
+
+        // The writer thread stops the session once it has written everything.
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(10);
+        let session = loop {
+            let session = temp.store.list().unwrap().into_iter().find(|s| s.id == id).unwrap();
+            if session.stopped_at.is_some() || std::time::Instant::now() > deadline {
+                break session;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert!(session.stopped_at.is_some());
+        assert!(session.read_only);
+        assert_eq!(session.name, "capture.trc");
+        assert_eq!(session.source["kind"], "trace_file");
+
+        let older = temp.store.events_window(&id, WindowAnchor::Before("3"), 2).unwrap();
+        assert_eq!(ids(&older), ["1", "2"]);
+        assert_eq!((older.offset, older.total), (1, 6));
+    }
+
+    #[test]
+    fn a_completion_keeps_the_position_of_its_start() {
+        let (mut temp, id) = store_with_events(5);
+        let mut completed = QueryEvent::for_test("sql_batch_completed", 52, 99);
+        completed.id = "1".into();
+        completed.event_status = "completed".into();
+        write_batch(&mut temp.store.conn, &id, &[SessionRecord::Query(completed)]).unwrap();
+
+        let window = temp.store.events_window(&id, WindowAnchor::Latest, 10).unwrap();
+        assert_eq!(ids(&window), ["0", "1", "2", "3", "4"]);
+        assert_eq!(window.events[1].event_sequence, 99);
+    }
+}
@@ -0,0 +1,392 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::profiler::QueryEvent;
+
+/// Linear sub-buckets per power of two in the histograms; percentiles are
+/// within about 1/16 (6%) of the true value.
+const SUB_BUCKETS: u32 = 16;
+const SUB_BUCKET_BITS: u32 = 4;
+
+/// Running aggregates per `query_hash`, built from completed batches, RPCs
+/// and statements. Memory per query is bounded: percentiles come from
+/// log-linear histograms rather than stored samples.
+#[derive(Default)]
+pub struct QueryStats {
+    entries: HashMap<String, Entry>,
+    executions: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryStatsOrder {
+    Executions,
+    #[default]
+    TotalDuration,
+    AvgDuration,
+    MaxDuration,
+    P95Duration,
+    TotalCpu,
+    AvgCpu,
+    TotalReads,
+    TotalWrites,
+    TotalRows,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryAggregate {
+    pub query_hash: String,
+    pub fingerprint: String,
+    pub event_name: String,
+    pub database_name: String,
+    /// The most recent execution's text, for running it or reading literals.
+    pub sample_text: String,
+    pub executions: u64,
+    /// Executions that failed or were cancelled.
+    pub failures: u64,
+    pub first_seen: String,
+    pub last_seen: String,
+    /// Microseconds.
+    pub duration: MetricSummary,
+    /// Microseconds.
+    pub cpu_time: MetricSummary,
+    pub logical_reads: MetricSummary,
+    pub writes: MetricSummary,
+    pub row_count: MetricSummary,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricSummary {
+    pub total: i64,
+    pub avg: f64,
+    pub min: i64,
+    pub max: i64,
+    pub p50: i64,
+    pub p95: i64,
+    pub p99: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryStatsSnapshot {
+    pub distinct_queries: usize,
+    pub executions: u64,
+    pub queries: Vec<QueryAggregate>,
+}
+
+struct Entry {
+    fingerprint: String,
+    event_name: String,
+    database_name: String,
+    sample_text: String,
+    executions: u64,
+    failures: u64,
+    first_seen: String,
+    last_seen: String,
+    duration: Accumulator,
+    cpu_time: Accumulator,
+    logical_reads: Accumulator,
+    writes: Accumulator,
+    row_count: Accumulator,
+}
+
+#[derive(Default)]
+struct Accumulator {
+    total: i64,
+    min: i64,
+    max: i64,
+    histogram: BTreeMap<u32, u64>,
+}
+
+impl QueryStats {
+    /// Adds a finished execution. Starting events, errors and events
+    /// without a hash are ignored.
+    pub fn record(&mut self, event: &QueryEvent) {
+        if event.query_hash.is_empty()
+            || !event.event_name.ends_with("_completed")
+            || !matches!(event.event_status.as_str(), "completed" | "failed" | "cancelled")
+        {
+            return;
+        }
+
+        let entry = self.entries.entry(event.query_hash.clone()).or_insert_with(|| Entry {
+            fingerprint: event.fingerprint.clone(),
+            event_name: event.event_name.clone(),
+            database_name: String::new(),
+            sample_text: String::new(),
+            executions: 0,
+            failures: 0,
+            first_seen: event.captured_at.clone(),
+            last_seen: String::new(),
+            duration: Accumulator::default(),
+            cpu_time: Accumulator::default(),
+            logical_reads: Accumulator::default(),
+            writes: Accumulator::default(),
+            row_count: Accumulator::default(),
+        });
+
+        let first = entry.executions == 0;
+        entry.executions += 1;
+        if event.event_status != "completed" {
+            entry.failures += 1;
+        }
+        entry.database_name.clone_from(&event.database_name);
+        entry.sample_text.clone_from(&event.sql_text);
+        entry.last_seen.clone_from(&event.captured_at);
+        entry.duration.add(event.elapsed_time, first);
+        entry.cpu_time.add(event.cpu_time, first);
+        entry.logical_reads.add(event.logical_reads, first);
+        entry.writes.add(event.writes, first);
+        entry.row_count.add(event.row_count, first);
+        self.executions += 1;
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.executions = 0;
+    }
+
+    /// The `limit` heaviest queries by `order`, heaviest first.
+    pub fn top(&self, order: QueryStatsOrder, limit: usize) -> Vec<QueryAggregate> {
+        let mut ranked: Vec<(&String, &Entry, f64)> = self
+            .entries
+            .iter()
+            .map(|(hash, entry)| (hash, entry, entry.sort_key(order)))
+            .collect();
+        ranked.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(b.0)));
+        ranked
+            .into_iter()
+            .take(limit)
+            .map(|(hash, entry, _)| entry.aggregate(hash))
+            .collect()
+    }
+
+    pub fn snapshot(&self, order: QueryStatsOrder, limit: usize) -> QueryStatsSnapshot {
+        QueryStatsSnapshot {
+            distinct_queries: self.entries.len(),
+            executions: self.executions,
+            queries: self.top(order, limit),
+        }
+    }
+}
+
+impl Entry {
+    fn sort_key(&self, order: QueryStatsOrder) -> f64 {
+        let count = self.executions;
+        match order {
+            QueryStatsOrder::Executions => count as f64,
+            QueryStatsOrder::TotalDuration => self.duration.total as f64,
+            QueryStatsOrder::AvgDuration => self.duration.avg(count),
+            QueryStatsOrder::MaxDuration => self.duration.max as f64,
+            QueryStatsOrder::P95Duration => self.duration.percentile(count, 0.95) as f64,
+            QueryStatsOrder::TotalCpu => self.cpu_time.total as f64,
+            QueryStatsOrder::AvgCpu => self.cpu_time.avg(count),
+            QueryStatsOrder::TotalReads => self.logical_reads.total as f64,
+            QueryStatsOrder::TotalWrites => self.writes.total as f64,
+            QueryStatsOrder::TotalRows => self.row_count.total as f64,
+        }
+    }
+
+    fn aggregate(&self, hash: &str) -> QueryAggregate {
+        let count = self.executions;
+        QueryAggregate {
+            query_hash: hash.to_string(),
+            fingerprint: self.fingerprint.clone(),
+            event_name: self.event_name.clone(),
+            database_name: self.database_name.clone(),
+            sample_text: self.sample_text.clone(),
+            executions: count,
+            failures: self.failures,
+            first_seen: self.first_seen.clone(),
+            last_seen: self.last_seen.clone(),
+            duration: self.duration.summary(count),
+            cpu_time: self.cpu_time.summary(count),
+            logical_reads: self.logical_reads.summary(count),
+            writes: self.writes.summary(count),
+            row_count: self.row_count.summary(count),
+        }
+    }
+}
+
+impl Accumulator {
+    fn add(&mut self, value: i64, first: bool) {
+        let value = value.max(0);
+        self.total = self.total.saturating_add(value);
+        if first || value < self.min {
+            self.min = value;
+        }
+        if first || value > self.max {
+            self.max = value;
+        }
+        *self.histogram.entry(bucket_of(value)).or_insert(0) += 1;
+    }
+
+    fn avg(&self, count: u64) -> f64 {
+        if count == 0 {
+            0.0
+        } else {
+            self.total as f64 / count as f64
+        }
+    }
+
+    fn percentile(&self, count: u64, p: f64) -> i64 {
+        let rank = ((count as f64 * p).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (bucket, n) in &self.histogram {
+            seen += n;
+            if seen >= rank {
+                return bucket_midpoint(*bucket).clamp(self.min, self.max);
+            }
+        }
+        self.max
+    }
+
+    fn summary(&self, count: u64) -> MetricSummary {
+        MetricSummary {
+            total: self.total,
+            avg: self.avg(count),
+            min: self.min,
+            max: self.max,
+            p50: self.percentile(count, 0.50),
+            p95: self.percentile(count, 0.95),
+            p99: self.percentile(count, 0.99),
+        }
+    }
+}
+
+/// Values below `SUB_BUCKETS` get a bucket each; above that every power of
+/// two is split into `SUB_BUCKETS` equal buckets.
+fn bucket_of(value: i64) -> u32 {
+    let value = value as u64;
+    if value < u64::from(SUB_BUCKETS) {
+        return value as u32;
+    }
+    let shift = 63 - value.leading_zeros() - SUB_BUCKET_BITS;
+    let mantissa = (value >> shift) as u32;
+    (shift + 1) * SUB_BUCKETS + (mantissa - SUB_BUCKETS)
+}
+
+fn bucket_midpoint(bucket: u32) -> i64 {
+    if bucket < SUB_BUCKETS {
+        return i64::from(bucket);
+    }
+    let shift = bucket / SUB_BUCKETS - 1;
+    let mantissa = u64::from(bucket % SUB_BUCKETS + SUB_BUCKETS);
+    let low = mantissa << shift;
+    (low + ((1_u64 << shift) >> 1)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn completed(hash: &str, duration_us: i64) -> QueryEvent {
+        let mut event = QueryEvent::for_test("sql_batch_completed", 52, 0);
+        event.query_hash = hash.to_string();
+        event.event_status = "completed".into();
+        event.elapsed_time = duration_us;
+        event
+    }
+
+    #[test]
+    fn buckets_round_trip_across_powers_of_two() {
+        for value in 0..i64::from(SUB_BUCKETS) {
+            assert_eq!(bucket_midpoint(bucket_of(value)), value);
+        }
+
+        let mut last_bucket = bucket_of(i64::from(SUB_BUCKETS) - 1);
+        for power in SUB_BUCKET_BITS..62 {
+            let low = 1_i64 << power;
+            for value in [low, low + 1, low + low / 2, 2 * low - 1] {
+                let bucket = bucket_of(value);
+                let midpoint = bucket_midpoint(bucket);
+                assert!(bucket >= last_bucket, "buckets must not decrease at {value}");
+                assert_eq!(bucket_of(midpoint), bucket, "midpoint of {value} leaves its bucket");
+                let error = (midpoint - value).abs() as f64 / value as f64;
+                assert!(error <= 1.0 / f64::from(SUB_BUCKETS), "{value} -> {midpoint}");
+                last_bucket = bucket;
+            }
+        }
+    }
+
+    #[test]
+    fn percentiles_of_known_distributions() {
+        let mut stats = QueryStats::default();
+        for duration in 1..=1000 {
+            stats.record(&completed("uniform", duration));
+        }
+        for _ in 0..50 {
+            stats.record(&completed("constant", 7_500));
+        }
+
+        let top = stats.top(QueryStatsOrder::TotalDuration, 10);
+        let uniform = &top.iter().find(|q| q.query_hash == "uniform").unwrap().duration;
+        assert_eq!((uniform.min, uniform.max, uniform.total), (1, 1000, 500_500));
+        assert!((uniform.avg - 500.5).abs() < f64::EPSILON);
+        let within = |actual: i64, expected: i64| (actual - expected).abs() as f64 <= expected as f64 / 16.0;
+        assert!(within(uniform.p50, 500), "p50 {}", uniform.p50);
+        assert!(within(uniform.p95, 950), "p95 {}", uniform.p95);
+        assert!(within(uniform.p99, 990), "p99 {}", uniform.p99);
+
+        // Percentiles are clamped to what was actually seen.
+        let constant = &top.iter().find(|q| q.query_hash == "constant").unwrap().duration;
+        assert_eq!((constant.p50, constant.p95, constant.p99), (7_500, 7_500, 7_500));
+    }
+
+    #[test]
+    fn only_finished_executions_are_counted() {
+        let mut stats = QueryStats::default();
+        stats.record(&completed("a", 100));
+
+        let mut starting = completed("a", 0);
+        starting.event_name = "sql_batch_starting".into();
+        starting.event_status = String::new();
+        stats.record(&starting);
+
+        let mut error = completed("a", 0);
+        error.event_name = "error_reported".into();
+        stats.record(&error);
+
+        let mut running = completed("a", 0);
+        running.event_status = "running".into();
+        stats.record(&running);
+
+        stats.record(&completed("", 100));
+
+        let mut failed = completed("a", 300);
+        failed.event_status = "failed".into();
+        stats.record(&failed);
+        let mut cancelled = completed("a", 500);
+        cancelled.event_status = "cancelled".into();
+        stats.record(&cancelled);
+
+        let snapshot = stats.snapshot(QueryStatsOrder::Executions, 10);
+        assert_eq!((snapshot.distinct_queries, snapshot.executions), (1, 3));
+        let query = &snapshot.queries[0];
+        assert_eq!((query.executions, query.failures), (3, 2));
+        assert_eq!(query.duration.total, 900);
+    }
+
+    #[test]
+    fn top_ranks_by_the_chosen_order() {
+        let mut stats = QueryStats::default();
+        // "often": many cheap runs; "slow": one expensive run; "mid" and
+        // "tie" in between with equal totals.
+        for _ in 0..10 {
+            stats.record(&completed("often", 10));
+        }
+        stats.record(&completed("slow", 1_000));
+        for _ in 0..2 {
+            stats.record(&completed("mid", 200));
+            stats.record(&completed("tie", 200));
+        }
+
+        let hashes = |order, limit| -> Vec<String> {
+            stats.top(order, limit).into_iter().map(|q| q.query_hash).collect()
+        };
+        assert_eq!(hashes(QueryStatsOrder::TotalDuration, 10), ["slow", "mid", "tie", "often"]);
+        assert_eq!(hashes(QueryStatsOrder::Executions, 10), ["often", "mid", "tie", "slow"]);
+        assert_eq!(hashes(QueryStatsOrder::AvgDuration, 2), ["slow", "mid"]);
+        assert_eq!(hashes(QueryStatsOrder::MaxDuration, 1), ["slow"]);
+    }
+}
@@ -0,0 +1,712 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use crate::capture::{self, CaptureFileOptions, CaptureFilter, CaptureOptions, CaptureSource};
+use crate::db::{self, ConnectionConfig, SqlClient};
+use crate::profiler::{micros_to_ms, QueryError, QueryEvent};
+
+const TRACE_CREATE_AND_START: &str = "
+DECLARE @trace_id int;
+DECLARE @rc int;
+-- 2 = TRACE_FILE_ROLLOVER
+DECLARE @trace_options int = CASE WHEN @P11 = 1 THEN 2 ELSE 0 END;
+DECLARE @max_file_mb bigint = @P10;
+DECLARE @file_count int = CASE WHEN @P11 = 1 THEN @P12 END;
+-- The stop time arrives in UTC; sp_trace_create takes server local time.
+DECLARE @stop_time datetime =
+    DATEADD(minute, DATEDIFF(minute, GETUTCDATE(), GETDATE()), CONVERT(datetime, @P13, 126));
+DECLARE @on bit = 1;
+
+DECLARE @directory nvarchar(260) = @P9;
+IF @directory IS NULL
+BEGIN
+    DECLARE @errorlog nvarchar(260) = CONVERT(nvarchar(260), SERVERPROPERTY('ErrorLogFileName'));
+    SET @directory = LEFT(@errorlog, LEN(@errorlog) - CHARINDEX('\\', REVERSE(@errorlog)) + 1);
+END
+ELSE IF RIGHT(@directory, 1) NOT IN (N'\\', N'/')
+BEGIN
+    SET @directory = @directory + CASE WHEN CHARINDEX(N'/', @directory) > 0 THEN N'/' ELSE N'\\' END;
+END
+
+DECLARE @trace_file nvarchar(260) =
+    @directory + N'SimpleSQLProfiler_' + REPLACE(CONVERT(nvarchar(36), NEWID()), N'-', N'') + N'.trc';
+
+EXEC @rc = sp_trace_create @trace_id OUTPUT, @trace_options, @trace_file, @max_file_mb, @stop_time, @file_count;
+IF @rc <> 0
+BEGIN
+    DECLARE @reason nvarchar(200) = CASE @rc
+        WHEN 12 THEN N'the file could not be created; check that the directory exists on the server and the service account can write to it'
+        WHEN 14 THEN N'the stop time is not valid'
+        WHEN 15 THEN N'the file size or file count is not valid'
+        ELSE N'error code ' + CONVERT(nvarchar(10), @rc)
+    END;
+    RAISERROR(N'Failed to create the trace file %s: %s.', 16, 1, @trace_file, @reason);
+    RETURN;
+END
+
+DECLARE @events TABLE(id int);
+INSERT INTO @events(id)
+VALUES
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'RPC:Completed')),
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'SQL:BatchCompleted')),
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'Exception')),
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'User Error Message')),
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'Attention')),
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'Deadlock graph')),
+    ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'Blocked process report'));
+
+-- Starting events have no Duration or Reads, so those filters would let every
+-- one through with no completion to follow.
+IF @P6 IS NULL AND @P7 IS NULL
+BEGIN
+    INSERT INTO @events(id)
+    VALUES
+        ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'RPC:Starting')),
+        ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'SQL:BatchStarting'));
+END
+
+-- Statement-level capture (opt-in)
+IF @P1 = 1
+BEGIN
+    INSERT INTO @events(id)
+    VALUES
+        ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'SP:StmtCompleted')),
+        ((SELECT trace_event_id FROM sys.trace_events WHERE name = N'SQL:StmtCompleted'));
+END
+
+IF EXISTS (SELECT 1 FROM @events WHERE id IS NULL)
+BEGIN
+    RAISERROR('Required SQL Trace events are unavailable on this server.', 16, 1);
+    RETURN;
+END
+
+DECLARE @columns TABLE(id int);
+INSERT INTO @columns(id)
+VALUES
+    (1),  -- TextData
+    (4),  -- TransactionID
+    (6),  -- NTUserName
+    (8),  -- HostName
+    (9),  -- ClientProcessID
+    (10), -- ApplicationName
+    (11), -- LoginName
+    (12), -- SPID
+    (13), -- Duration
+    (14), -- StartTime
+    (15), -- EndTime
+    (16), -- Reads
+    (17), -- Writes
+    (18), -- CPU
+    (20), -- Severity
+    (31), -- Error
+    (34), -- ObjectName
+    (35), -- DatabaseName
+    (48), -- RowCounts
+    (51); -- EventSequence
+
+DECLARE @event_id int;
+DECLARE @column_id int;
+DECLARE event_col_cursor CURSOR LOCAL FAST_FORWARD FOR
+    SELECT e.id, c.id
+    FROM @events e
+    CROSS JOIN @columns c
+    -- Starting events have no Duration/EndTime/Reads/... columns
+    WHERE EXISTS (
+        SELECT 1
+        FROM sys.trace_event_bindings b
+        WHERE b.trace_event_id = e.id
+          AND b.trace_column_id = c.id
+    );
+
+OPEN event_col_cursor;
+FETCH NEXT FROM event_col_cursor INTO @event_id, @column_id;
+WHILE @@FETCH_STATUS = 0
+BEGIN
+    EXEC sp_trace_setevent @trace_id, @event_id, @column_id, @on;
+    FETCH NEXT FROM event_col_cursor INTO @event_id, @column_id;
+END
+CLOSE event_col_cursor;
+DEALLOCATE event_col_cursor;
+
+-- Exclude this app itself
+EXEC sp_trace_setfilter @trace_id, 10, 0, 7, N'%SimpleSQLProfiler%';
+
+-- User filters. sp_trace_setfilter takes sql_variant, which cannot hold
+-- nvarchar(max), so the patterns go through sized variables. A trace filter
+-- applies to every event class, so duration, reads and text (@P6-@P8) are
+-- left to TRACE_POLL_EVENTS, which applies them to requests only, as the XE
+-- session does; errors, deadlocks and blocking reports have none of them.
+DECLARE @database_like nvarchar(256) = @P2;
+DECLARE @login_like nvarchar(256) = @P3;
+DECLARE @host_like nvarchar(256) = @P4;
+DECLARE @program_like nvarchar(256) = @P5;
+
+IF @database_like IS NOT NULL EXEC sp_trace_setfilter @trace_id, 35, 0, 6, @database_like;
+IF @login_like IS NOT NULL EXEC sp_trace_setfilter @trace_id, 11, 0, 6, @login_like;
+IF @host_like IS NOT NULL EXEC sp_trace_setfilter @trace_id, 8, 0, 6, @host_like;
+IF @program_like IS NOT NULL EXEC sp_trace_setfilter @trace_id, 10, 0, 6, @program_like;
+
+EXEC sp_trace_setstatus @trace_id, 1;
+
+SELECT @trace_id AS trace_id, t.path AS trace_file
+FROM sys.traces t
+WHERE t.id = @trace_id;
+";
+
+const TRACE_STOP: &str = "
+IF EXISTS (SELECT 1 FROM sys.traces WHERE id = @P1 AND status = 1)
+BEGIN
+    EXEC sp_trace_setstatus @P1, 0;
+END
+";
+
+const TRACE_STOP_AND_CLOSE: &str = "
+IF EXISTS (SELECT 1 FROM sys.traces WHERE id = @P1)
+BEGIN
+    BEGIN TRY
+        EXEC sp_trace_setstatus @P1, 0;
+    END TRY
+    BEGIN CATCH
+    END CATCH;
+
+    BEGIN TRY
+        EXEC sp_trace_setstatus @P1, 2;
+    END TRY
+    BEGIN CATCH
+    END CATCH;
+END
+";
+
+/// Our trace's state and the file it is writing. The id alone is not enough
+/// to find it: ids are reused once the server restarts. Rollover files share
+/// the first file's name up to the extension. No row once the trace is
+/// closed, which SQL Server does itself at the stop time.
+const TRACE_STATUS: &str = "
+SELECT
+    CAST(CASE WHEN status = 1 THEN 1 ELSE 0 END AS bit) AS running,
+    path AS current_file,
+    ISNULL(event_count, 0) AS event_count
+FROM sys.traces
+WHERE id = @P1
+  AND path LIKE REPLACE(REPLACE(LEFT(@P2, LEN(@P2) - 4), N'[', N'[[]'), N'_', N'[_]') + N'%'
+";
+
+/// The trace columns the profiler maps, as `trace_row_to_event` reads them.
+const TRACE_EVENT_COLUMNS: &str = "
+    CAST(EventClass AS int) AS event_class,
+    CONVERT(varchar(27), StartTime, 126) AS start_time,
+    ISNULL(CONVERT(varchar(27), EndTime, 126), '') AS end_time,
+    CAST(ISNULL(EventSequence, 0) AS bigint) AS event_sequence,
+    CAST(ISNULL(Duration, 0) AS bigint) AS duration_us,
+    CAST(ISNULL(CPU, 0) AS bigint) AS cpu_ms,
+    CAST(ISNULL(Reads, 0) AS bigint) AS reads,
+    CAST(ISNULL(Writes, 0) AS bigint) AS writes,
+    CAST(ISNULL(RowCounts, 0) AS bigint) AS row_count,
+    CAST(ISNULL(TextData, N'') AS nvarchar(max)) AS text_data,
+    CAST(ISNULL(DatabaseName, N'') AS nvarchar(128)) AS database_name,
+    CAST(ISNULL(LoginName, N'') AS nvarchar(128)) AS login_name,
+    CAST(ISNULL(HostName, N'') AS nvarchar(128)) AS host_name,
+    CAST(ISNULL(ApplicationName, N'') AS nvarchar(128)) AS program_name,
+    CAST(ISNULL(SPID, 0) AS int) AS session_id,
+    CAST(ISNULL(ObjectName, N'') AS nvarchar(128)) AS object_name,
+    CAST(ISNULL(ClientProcessID, 0) AS int) AS client_process_id,
+    CAST(ISNULL(NTUserName, N'') AS nvarchar(128)) AS nt_user_name,
+    CAST(ISNULL(TransactionID, 0) AS bigint) AS transaction_id,
+    CAST(ISNULL(Error, 0) AS int) AS error_number,
+    CAST(ISNULL(Severity, 0) AS int) AS severity
+";
+
+/// Reads one trace file (`@P1`) from just after the last event seen. Events
+/// are written in EventSequence order, completions included, so the
+/// sequence alone is a watermark that skips nothing. `fn_trace_gettable`
+/// still scans the whole file, which is why polling reads one file at a time.
+/// The minimum duration (`@P4`, microseconds), minimum reads (`@P5`) and
+/// text pattern (`@P6`) filter requests only; NULL when not set.
+const TRACE_POLL_EVENTS: &str = "
+SELECT TOP (@P3) {event_columns}
+FROM sys.fn_trace_gettable(@P1, 1) t
+WHERE EventClass IN (10, 11, 12, 13, 16, 33, 41, 45, 137, 148, 162)
+  -- Informational messages such as 'Changed database context'
+  AND NOT (EventClass IN (33, 162) AND ISNULL(Severity, 0) <= 10)
+  AND ISNULL(ApplicationName, N'') NOT LIKE N'%SimpleSQLProfiler%'
+  AND EventSequence > @P2
+  AND (
+      EventClass NOT IN (10, 11, 12, 13, 41, 45)
+      OR (
+          (@P4 IS NULL OR Duration >= @P4)
+          AND (@P5 IS NULL OR Reads >= @P5)
+          AND (@P6 IS NULL OR CAST(TextData AS nvarchar(max)) COLLATE Latin1_General_CI_AS LIKE @P6)
+      )
+  )
+ORDER BY EventSequence ASC;
+";
+
+/// Reads a whole trace file (`@P1`) in capture order, for replaying a file
+/// the user chose to have the server read. Traces written by other tools may
+/// lack EventSequence; those fall back to start time order.
+const TRACE_READ_FILE: &str = "
+SELECT {event_columns}
+FROM sys.fn_trace_gettable(@P1, 1) t
+WHERE EventClass IN (10, 11, 12, 13, 16, 33, 41, 45, 137, 148, 162)
+  AND NOT (EventClass IN (33, 162) AND ISNULL(Severity, 0) <= 10)
+ORDER BY t.EventSequence, t.StartTime;
+";
+
+/// How long a poll may skip reading while the trace's event count stands
+/// still. SQL Trace buffers events before writing them, so counted events
+/// can reach the file a few seconds later.
+const IDLE_REREAD_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+struct ActiveTrace {
+    trace_id: i32,
+    /// The first file; rollover files are named after it.
+    trace_file: String,
+    /// The file being read. Moves to the next rollover file once it is
+    /// closed and fully read, so no file is read again after that.
+    reading_file: String,
+    /// The file SQL Server is writing, as of the last status check.
+    current_file: String,
+    max_file_mb: i64,
+    /// The event count when `current_file` was last read to the end.
+    drained_at: Option<(i64, Instant)>,
+}
+
+struct TraceStatus {
+    running: bool,
+    current_file: String,
+    event_count: i64,
+}
+
+/// Captures through a server-side SQL Trace written to files next to the
+/// error log (or wherever `CaptureFileOptions` says), read back with
+/// `sys.fn_trace_gettable`.
+pub struct SqlTraceSource {
+    config: ConnectionConfig,
+    options: CaptureOptions,
+    client: Option<SqlClient>,
+    trace: Option<ActiveTrace>,
+    last_event_sequence: i64,
+    behind: bool,
+}
+
+impl SqlTraceSource {
+    pub fn new(config: ConnectionConfig, options: CaptureOptions) -> Self {
+        Self {
+            config,
+            options,
+            client: None,
+            trace: None,
+            last_event_sequence: 0,
+            behind: false,
+        }
+    }
+}
+
+#[async_trait]
+impl CaptureSource for SqlTraceSource {
+    async fn start(&mut self) -> Result<(), String> {
+        let mut client = db::connect(&self.config)
+            .await
+            .map_err(|e| format!("Failed to start polling stream: {e}"))?;
+        let trace = start_trace(&mut client, &self.options).await?;
+        self.client = Some(client);
+        self.trace = Some(trace);
+        Ok(())
+    }
+
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        let (Some(client), Some(trace)) = (self.client.as_mut(), self.trace.as_mut()) else {
+            return Err("SQL Trace is not running".into());
+        };
+
+        // Checked before reading, so a read after the trace stopped is
+        // known to include everything it wrote.
+        let status = trace_status(client, trace).await?;
+        let running = status.as_ref().is_some_and(|s| s.running);
+        let event_count = status.as_ref().map(|s| s.event_count);
+        if let Some(status) = status.filter(|s| !s.current_file.is_empty()) {
+            trace.current_file = status.current_file;
+        }
+
+        let reading_current = trace.reading_file == trace.current_file;
+        let idle = matches!(
+            (trace.drained_at, event_count),
+            (Some((drained, at)), Some(count)) if drained == count && at.elapsed() < IDLE_REREAD_INTERVAL
+        );
+        if running && reading_current && idle {
+            self.behind = false;
+            return Ok(Some(Vec::new()));
+        }
+
+        let polled = poll_trace_events(client, &trace.reading_file, self.last_event_sequence, &self.options.filter).await;
+        let (events, read) = match polled {
+            Ok(events) => (events, true),
+            // Not written yet, or deleted by rollover before it was read.
+            Err(e) if is_transient_trace_file_error(&e) => (Vec::new(), false),
+            Err(e) => return Err(e),
+        };
+        if let Some(last) = events.last() {
+            self.last_event_sequence = last.event_sequence;
+        }
+
+        let full = events.len() >= capture::POLL_ROW_LIMIT as usize;
+        if !full {
+            if !reading_current {
+                trace.reading_file = next_rollover_file(&trace.trace_file, &trace.reading_file);
+            } else if read && running {
+                trace.drained_at = event_count.map(|count| (count, Instant::now()));
+            }
+        }
+        // More rows in this file, or closed files still to read.
+        self.behind = full || trace.reading_file != trace.current_file;
+
+        if !events.is_empty() || running || !reading_current {
+            return Ok(Some(events));
+        }
+
+        // Stopped by SQL Server and fully read: the stop time came, or the
+        // file filled up without rollover.
+        match self.options.stop_deadline()? {
+            Some(stop_at) if stop_at <= chrono::Utc::now() => Ok(None),
+            _ => Err(format!(
+                "SQL Server stopped the trace, most likely because its file reached {} MB. \
+                 Turn on rollover or raise the maximum file size.",
+                trace.max_file_mb
+            )),
+        }
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        let (Some(client), Some(trace)) = (self.client.as_mut(), self.trace.as_ref()) else {
+            return Ok(());
+        };
+        stop_trace(client, trace.trace_id).await
+    }
+
+    async fn reconnect(&mut self) -> Result<(), String> {
+        let Some(trace) = self.trace.as_ref() else {
+            return Err("SQL Trace is not running".into());
+        };
+        self.client = None;
+
+        let mut client = db::connect(&self.config).await?;
+        if !trace_status(&mut client, trace).await?.is_some_and(|status| status.running) {
+            return Err("The SQL Trace is no longer running on the server (was it restarted?)".into());
+        }
+        self.client = Some(client);
+        Ok(())
+    }
+
+    async fn cleanup(&mut self) -> Result<(), String> {
+        let client = self.client.take();
+        let Some(trace) = self.trace.take() else {
+            return Ok(());
+        };
+
+        if let Some(mut client) = client {
+            if stop_and_close_trace(&mut client, trace.trace_id).await.is_ok() {
+                return Ok(());
+            }
+        }
+
+        // The polling connection may be what failed; retry on a fresh one so
+        // the trace is not left running on the server.
+        let mut client = db::connect(&self.config).await?;
+        stop_and_close_trace(&mut client, trace.trace_id).await
+    }
+
+    fn behind(&self) -> bool {
+        self.behind
+    }
+}
+
+async fn start_trace(client: &mut SqlClient, options: &CaptureOptions) -> Result<ActiveTrace, String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(TRACE_CREATE_AND_START);
+    query.bind(options.statement_level);
+    options.filter.bind_to(&mut query);
+    options.files.bind_to(&mut query);
+    query.bind(options.stop_at_utc());
+
+    let stream = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to create/start SQL Trace: {e}"))?;
+
+    let rows = stream
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to read SQL Trace creation result: {e}"))?;
+
+    for result_set in rows {
+        for row in result_set {
+            let trace_id = row.get::<i32, _>("trace_id");
+            let trace_file = row.get::<&str, _>("trace_file");
+            if let (Some(id), Some(file)) = (trace_id, trace_file) {
+                if id > 0 && !file.is_empty() {
+                    let files = options.files.normalized();
+                    return Ok(ActiveTrace {
+                        trace_id: id,
+                        trace_file: file.to_string(),
+                        reading_file: file.to_string(),
+                        current_file: file.to_string(),
+                        max_file_mb: files.max_file_mb.unwrap_or(CaptureFileOptions::DEFAULT_MAX_FILE_MB),
+                        drained_at: None,
+                    });
+                }
+            }
+        }
+    }
+
+    Err("SQL Trace creation returned invalid trace metadata".into())
+}
+
+async fn stop_trace(client: &mut SqlClient, trace_id: i32) -> Result<(), String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(TRACE_STOP);
+    query.bind(trace_id);
+
+    query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to stop SQL Trace: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to confirm SQL Trace stop: {e}"))?;
+
+    Ok(())
+}
+
+async fn trace_status(client: &mut SqlClient, trace: &ActiveTrace) -> Result<Option<TraceStatus>, String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(TRACE_STATUS);
+    query.bind(trace.trace_id);
+    query.bind(trace.trace_file.as_str());
+
+    let row = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to check SQL Trace status: {e}"))?
+        .into_row()
+        .await
+        .map_err(|e| format!("Failed to read SQL Trace status: {e}"))?;
+
+    Ok(row.map(|r| TraceStatus {
+        running: r.get::<bool, _>("running").unwrap_or(false),
+        current_file: r.get::<&str, _>("current_file").unwrap_or("").to_string(),
+        event_count: r.get::<i64, _>("event_count").unwrap_or(0),
+    }))
+}
+
+/// The file SQL Server rolls over to after `file`: the first file
+/// `name.trc` is followed by `name_1.trc`, `name_2.trc` and so on.
+fn next_rollover_file(first_file: &str, file: &str) -> String {
+    let base = first_file.strip_suffix(".trc").unwrap_or(first_file);
+    let number = file
+        .strip_prefix(base)
+        .and_then(|rest| rest.strip_prefix('_'))
+        .and_then(|rest| rest.strip_suffix(".trc"))
+        .and_then(|n| n.parse::<u32>().ok())
+        .unwrap_or(0);
+    format!("{base}_{}.trc", number + 1)
+}
+
+pub(crate) async fn stop_and_close_trace(client: &mut SqlClient, trace_id: i32) -> Result<(), String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(TRACE_STOP_AND_CLOSE);
+    query.bind(trace_id);
+
+    query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to stop/close SQL Trace: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to confirm SQL Trace stop/close: {e}"))?;
+
+    Ok(())
+}
+
+async fn poll_trace_events(
+    client: &mut SqlClient,
+    trace_file: &str,
+    last_event_sequence: i64,
+    filter: &CaptureFilter,
+) -> Result<Vec<QueryEvent>, String> {
+    use tiberius::Query;
+
+    let filter = filter.normalized();
+    let mut query = Query::new(TRACE_POLL_EVENTS.replace("{event_columns}", TRACE_EVENT_COLUMNS));
+    query.bind(trace_file);
+    query.bind(last_event_sequence);
+    query.bind(capture::POLL_ROW_LIMIT);
+    query.bind(filter.min_duration_ms.map(|ms| ms * 1000));
+    query.bind(filter.min_logical_reads);
+    query.bind(filter.text_like);
+
+    let stream = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Trace poll query failed: {e}"))?;
+
+    let rows = stream
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to read trace poll results: {e}"))?;
+
+    Ok(rows
+        .first()
+        .map(|result_set| result_set.iter().filter_map(trace_row_to_event).collect())
+        .unwrap_or_default())
+}
+
+/// Reads `trace_file` through the server, sending its events to `tx`
+/// `batch_size` at a time as the rows arrive. Stops early, without an error,
+/// once the receiver is gone.
+pub(crate) async fn read_trace_file(
+    client: &mut SqlClient,
+    trace_file: &str,
+    batch_size: usize,
+    tx: &tokio::sync::mpsc::Sender<Result<Vec<QueryEvent>, String>>,
+) -> Result<(), String> {
+    use futures_util::TryStreamExt;
+    use tiberius::Query;
+
+    let mut query = Query::new(TRACE_READ_FILE.replace("{event_columns}", TRACE_EVENT_COLUMNS));
+    query.bind(trace_file);
+
+    let mut rows = query
+        .query(client)
+        .await
+        .map_err(|e| {
+            format!(
+                "The server failed to read '{trace_file}': {e}. It opens the file itself, \
+                 so the path must exist on the server or on a share it can reach."
+            )
+        })?
+        .into_row_stream();
+    let mut batch = Vec::with_capacity(batch_size);
+    while let Some(row) = rows
+        .try_next()
+        .await
+        .map_err(|e| format!("Failed to read trace file '{trace_file}': {e}"))?
+    {
+        batch.extend(trace_row_to_event(&row));
+        if batch.len() >= batch_size && tx.send(Ok(std::mem::take(&mut batch))).await.is_err() {
+            return Ok(());
+        }
+    }
+    if !batch.is_empty() {
+        let _ = tx.send(Ok(batch)).await;
+    }
+    Ok(())
+}
+
+/// Maps a row of `TRACE_EVENT_COLUMNS`; `None` for event classes the
+/// profiler doesn't show.
+fn trace_row_to_event(row: &tiberius::Row) -> Option<QueryEvent> {
+    let event_class: i32 = row.get::<i32, _>("event_class").unwrap_or(0);
+    let event_name = match event_class {
+        10 => "rpc_completed".to_string(),
+        11 => "rpc_starting".to_string(),
+        12 => "sql_batch_completed".to_string(),
+        13 => "sql_batch_starting".to_string(),
+        16 => "attention".to_string(),
+        33 => "exception".to_string(),
+        137 => "blocked_process_report".to_string(),
+        148 => "deadlock_graph".to_string(),
+        162 => "user_error_message".to_string(),
+        41 => "sql_statement_completed".to_string(),
+        45 => "sp_statement_completed".to_string(),
+        _ => return None,
+    };
+
+    let start_time: String = row.get::<&str, _>("start_time").unwrap_or("").to_string();
+    let end_time: String = row.get::<&str, _>("end_time").unwrap_or("").to_string();
+    let event_sequence: i64 = row.get::<i64, _>("event_sequence").unwrap_or(0);
+
+    let duration_us: i64 = row.get::<i64, _>("duration_us").unwrap_or(0);
+    // SQL Trace reports CPU in milliseconds.
+    let cpu_us: i64 = row.get::<i64, _>("cpu_ms").unwrap_or(0) * 1000;
+
+    let logical_reads: i64 = row.get::<i64, _>("reads").unwrap_or(0);
+    let writes: i64 = row.get::<i64, _>("writes").unwrap_or(0);
+    let row_count: i64 = row.get::<i64, _>("row_count").unwrap_or(0);
+
+    let text_data: String = row.get::<&str, _>("text_data").unwrap_or("").to_string();
+    let database_name: String = row.get::<&str, _>("database_name").unwrap_or("").to_string();
+    let login_name: String = row.get::<&str, _>("login_name").unwrap_or("").to_string();
+    let host_name: String = row.get::<&str, _>("host_name").unwrap_or("").to_string();
+    let program_name: String = row.get::<&str, _>("program_name").unwrap_or("").to_string();
+    let session_id: i32 = row.get::<i32, _>("session_id").unwrap_or(0);
+    let object_name: String = row.get::<&str, _>("object_name").unwrap_or("").to_string();
+    let client_process_id: i32 = row.get::<i32, _>("client_process_id").unwrap_or(0);
+    let nt_user_name: String = row.get::<&str, _>("nt_user_name").unwrap_or("").to_string();
+    let transaction_id: i64 = row.get::<i64, _>("transaction_id").unwrap_or(0);
+
+    let error_number: i32 = row.get::<i32, _>("error_number").unwrap_or(0);
+    let (error, event_status) = match event_class {
+        // For completions the Error column is the result: 1 = error, 2 = abort.
+        10 | 12 => (None, capture::completion_status(error_number as i64)),
+        33 | 162 => (
+            Some(QueryError {
+                number: error_number,
+                severity: row.get::<i32, _>("severity").unwrap_or(0),
+                message: text_data.clone(),
+            }),
+            String::new(),
+        ),
+        _ => (None, String::new()),
+    };
+
+    let (sql_text, current_statement) = match event_class {
+        10 | 11 | 41 | 45 => (text_data.clone(), text_data),
+        _ => (text_data, String::new()),
+    };
+
+    Some(QueryEvent {
+        id: String::new(),
+        session_id,
+        start_time,
+        end_time,
+        event_name,
+        database_name,
+        cpu_time: cpu_us,
+        elapsed_time: duration_us,
+        cpu_time_ms: micros_to_ms(cpu_us),
+        elapsed_time_ms: micros_to_ms(duration_us),
+        physical_reads: None,
+        writes,
+        logical_reads,
+        row_count,
+        sql_text,
+        current_statement,
+        fingerprint: String::new(),
+        query_hash: String::new(),
+        login_name,
+        host_name,
+        program_name,
+        object_name,
+        client_process_id,
+        nt_user_name,
+        transaction_id,
+        captured_at: String::new(),
+        event_status,
+        event_sequence,
+        parent_id: None,
+        error,
+        extra: serde_json::Map::new(),
+    })
+}
+
+fn is_transient_trace_file_error(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    lower.contains("code: 19049")
+        || (lower.contains("there are no more files") && lower.contains("fn_trace_gettable"))
+}
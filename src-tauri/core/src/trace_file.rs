@@ -0,0 +1,744 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::capture::{self, CaptureSource};
+use crate::db::{self, ConnectionConfig, SqlClient};
+use crate::profiler::{micros_to_ms, QueryError, QueryEvent};
+use crate::trace;
+
+// SQL Trace file layout (all integers little-endian):
+//
+//   header   TRC_HEADER_LEN bytes; starts with the 0xFEFF marker, the header
+//            length and the trace version, followed by the UTF-16 provider
+//            name "Microsoft SQL Server" and server/trace options.
+//   record   u16 TRACE_BEGIN_RECORD, u32 record length (including this
+//            prefix), u16 event class, then columns until the record length
+//            is consumed.
+//   column   u16 column id, u32 data length, data.
+//
+// Text columns are UTF-16LE, StartTime/EndTime are SYSTEMTIME structs and the
+// numeric columns are 4 or 8 byte integers depending on the column.
+const TRC_HEADER_LEN: usize = 1024;
+const TRC_UNICODE_MARKER: u16 = 0xFEFF;
+const TRC_PROVIDER_NAME: &str = "Microsoft SQL Server";
+const TRACE_BEGIN_RECORD: u16 = 0xFFFE;
+const RECORD_PREFIX_LEN: usize = 8;
+const COLUMN_PREFIX_LEN: usize = 6;
+const MAX_COLUMN_LEN: usize = 256 * 1024 * 1024;
+const MAX_RECORD_LEN: usize = RECORD_PREFIX_LEN + MAX_COLUMN_LEN;
+
+const OFFLINE_BATCH_SIZE: usize = 5000;
+
+const COL_TEXT_DATA: u16 = 1;
+const COL_TRANSACTION_ID: u16 = 4;
+const COL_NT_USER_NAME: u16 = 6;
+const COL_NT_DOMAIN_NAME: u16 = 7;
+const COL_HOST_NAME: u16 = 8;
+const COL_CLIENT_PROCESS_ID: u16 = 9;
+const COL_APPLICATION_NAME: u16 = 10;
+const COL_LOGIN_NAME: u16 = 11;
+const COL_SPID: u16 = 12;
+const COL_DURATION: u16 = 13;
+const COL_START_TIME: u16 = 14;
+const COL_END_TIME: u16 = 15;
+const COL_READS: u16 = 16;
+const COL_WRITES: u16 = 17;
+const COL_CPU: u16 = 18;
+const COL_SEVERITY: u16 = 20;
+const COL_SERVER_NAME: u16 = 26;
+const COL_ERROR: u16 = 31;
+const COL_OBJECT_NAME: u16 = 34;
+const COL_DATABASE_NAME: u16 = 35;
+const COL_DB_USER_NAME: u16 = 40;
+const COL_ROW_COUNTS: u16 = 48;
+const COL_EVENT_SEQUENCE: u16 = 51;
+const COL_SESSION_LOGIN_NAME: u16 = 64;
+
+const TEXT_COLUMNS: &[u16] = &[
+    COL_TEXT_DATA,
+    COL_NT_USER_NAME,
+    COL_NT_DOMAIN_NAME,
+    COL_HOST_NAME,
+    COL_APPLICATION_NAME,
+    COL_LOGIN_NAME,
+    COL_SERVER_NAME,
+    COL_OBJECT_NAME,
+    COL_DATABASE_NAME,
+    COL_DB_USER_NAME,
+    COL_SESSION_LOGIN_NAME,
+];
+
+const EVENT_RPC_COMPLETED: u16 = 10;
+const EVENT_RPC_STARTING: u16 = 11;
+const EVENT_SQL_BATCH_COMPLETED: u16 = 12;
+const EVENT_SQL_BATCH_STARTING: u16 = 13;
+const EVENT_ATTENTION: u16 = 16;
+const EVENT_EXCEPTION: u16 = 33;
+const EVENT_SQL_STMT_COMPLETED: u16 = 41;
+const EVENT_SP_STMT_COMPLETED: u16 = 45;
+const EVENT_BLOCKED_PROCESS_REPORT: u16 = 137;
+const EVENT_DEADLOCK_GRAPH: u16 = 148;
+const EVENT_USER_ERROR_MESSAGE: u16 = 162;
+
+#[derive(Debug, Clone)]
+enum TraceValue {
+    Text(String),
+    Int(i64),
+    DateTime(String),
+}
+
+#[derive(Debug, Clone)]
+struct TraceRecord {
+    event_class: u16,
+    columns: HashMap<u16, TraceValue>,
+}
+
+impl TraceRecord {
+    fn text(&self, column: u16) -> String {
+        match self.columns.get(&column) {
+            Some(TraceValue::Text(s)) => s.clone(),
+            _ => String::new(),
+        }
+    }
+
+    fn int(&self, column: u16) -> i64 {
+        match self.columns.get(&column) {
+            Some(TraceValue::Int(v)) => *v,
+            _ => 0,
+        }
+    }
+
+    fn datetime(&self, column: u16) -> String {
+        match self.columns.get(&column) {
+            Some(TraceValue::DateTime(s)) => s.clone(),
+            _ => String::new(),
+        }
+    }
+}
+
+/// Streams records out of a single `.trc` file.
+struct TraceFileReader<R = BufReader<File>> {
+    reader: R,
+    path: PathBuf,
+    offset: u64,
+}
+
+impl TraceFileReader {
+    fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Failed to open '{}': {e}", path.display()))?;
+        Self::new(BufReader::with_capacity(1024 * 1024, file), path)
+    }
+}
+
+impl<R: Read> TraceFileReader<R> {
+    fn new(mut reader: R, path: &Path) -> Result<Self, String> {
+        let mut header = vec![0_u8; TRC_HEADER_LEN];
+        reader
+            .read_exact(&mut header)
+            .map_err(|e| format!("'{}' is too short to be a SQL Trace file: {e}", path.display()))?;
+
+        let marker = u16::from_le_bytes([header[0], header[1]]);
+        if marker != TRC_UNICODE_MARKER || !header_contains_provider(&header) {
+            return Err(format!("'{}' is not a SQL Trace (.trc) file", path.display()));
+        }
+
+        Ok(Self {
+            reader,
+            path: path.to_path_buf(),
+            offset: TRC_HEADER_LEN as u64,
+        })
+    }
+
+    fn next_record(&mut self) -> Result<Option<TraceRecord>, String> {
+        let mut prefix = [0_u8; RECORD_PREFIX_LEN];
+        match self.reader.read_exact(&mut prefix) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(self.error_at(&format!("read failed: {e}"))),
+        }
+
+        let marker = u16::from_le_bytes([prefix[0], prefix[1]]);
+        if marker != TRACE_BEGIN_RECORD {
+            // Files are preallocated in pages; a zeroed tail marks the end of
+            // the data written so far.
+            if prefix.iter().all(|b| *b == 0) {
+                return Ok(None);
+            }
+            return Err(self.error_at("expected the start of a trace record"));
+        }
+
+        let record_len = u32::from_le_bytes([prefix[2], prefix[3], prefix[4], prefix[5]]) as usize;
+        let event_class = u16::from_le_bytes([prefix[6], prefix[7]]);
+        if !(RECORD_PREFIX_LEN..=MAX_RECORD_LEN).contains(&record_len) {
+            return Err(self.error_at(&format!("trace record length {record_len} is invalid")));
+        }
+
+        // Read through `take` so a corrupt length can't allocate more than
+        // the file actually holds.
+        let body_len = record_len - RECORD_PREFIX_LEN;
+        let mut body = Vec::new();
+        (&mut self.reader)
+            .take(body_len as u64)
+            .read_to_end(&mut body)
+            .map_err(|e| self.error_at(&format!("read failed: {e}")))?;
+        if body.len() < body_len {
+            return Err(self.error_at("truncated trace record"));
+        }
+        self.offset += record_len as u64;
+
+        let mut columns = HashMap::new();
+        let mut pos = 0;
+        while pos + COLUMN_PREFIX_LEN <= body.len() {
+            let column = u16::from_le_bytes([body[pos], body[pos + 1]]);
+            let len = u32::from_le_bytes([body[pos + 2], body[pos + 3], body[pos + 4], body[pos + 5]]) as usize;
+            pos += COLUMN_PREFIX_LEN;
+            if len > MAX_COLUMN_LEN || pos + len > body.len() {
+                return Err(self.error_at(&format!("column {column} overruns its record")));
+            }
+            if let Some(value) = decode_column(column, &body[pos..pos + len]) {
+                columns.insert(column, value);
+            }
+            pos += len;
+        }
+
+        Ok(Some(TraceRecord { event_class, columns }))
+    }
+
+    fn error_at(&self, message: &str) -> String {
+        format!("Invalid trace file '{}' at offset {}: {message}", self.path.display(), self.offset)
+    }
+}
+
+fn header_contains_provider(header: &[u8]) -> bool {
+    let needle: Vec<u8> = TRC_PROVIDER_NAME
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+    header.windows(needle.len()).any(|window| window == needle.as_slice())
+}
+
+/// Decodes the columns the profiler maps; binary and GUID columns are skipped.
+fn decode_column(column: u16, data: &[u8]) -> Option<TraceValue> {
+    if TEXT_COLUMNS.contains(&column) {
+        return Some(TraceValue::Text(decode_utf16(data)));
+    }
+
+    if column == COL_START_TIME || column == COL_END_TIME {
+        return decode_systemtime(data).map(TraceValue::DateTime);
+    }
+
+    match data.len() {
+        1 => Some(TraceValue::Int(data[0] as i64)),
+        2 => Some(TraceValue::Int(i16::from_le_bytes([data[0], data[1]]) as i64)),
+        4 => Some(TraceValue::Int(
+            i32::from_le_bytes([data[0], data[1], data[2], data[3]]) as i64,
+        )),
+        8 => Some(TraceValue::Int(i64::from_le_bytes([
+            data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7],
+        ]))),
+        _ => None,
+    }
+}
+
+fn decode_utf16(data: &[u8]) -> String {
+    let units: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    let text = String::from_utf16_lossy(&units);
+    text.trim_end_matches('\0').to_string()
+}
+
+/// Formats a Win32 SYSTEMTIME the same way the live poll formats StartTime
+/// (`CONVERT(varchar(27), ..., 126)`).
+fn decode_systemtime(data: &[u8]) -> Option<String> {
+    if data.len() != 16 {
+        return None;
+    }
+    let field = |i: usize| u16::from_le_bytes([data[i * 2], data[i * 2 + 1]]) as u32;
+    let (year, month, day) = (field(0) as i32, field(1), field(3));
+    let (hour, minute, second, millis) = (field(4), field(5), field(6), field(7));
+
+    let date = chrono::NaiveDate::from_ymd_opt(year, month, day)?;
+    let time = chrono::NaiveTime::from_hms_milli_opt(hour, minute, second, millis)?;
+    Some(
+        chrono::NaiveDateTime::new(date, time)
+            .format("%Y-%m-%dT%H:%M:%S%.3f")
+            .to_string(),
+    )
+}
+
+fn record_to_query_event(record: &TraceRecord) -> Option<QueryEvent> {
+    let event_name = match record.event_class {
+        EVENT_RPC_COMPLETED => "rpc_completed",
+        EVENT_RPC_STARTING => "rpc_starting",
+        EVENT_SQL_BATCH_COMPLETED => "sql_batch_completed",
+        EVENT_SQL_BATCH_STARTING => "sql_batch_starting",
+        EVENT_SQL_STMT_COMPLETED => "sql_statement_completed",
+        EVENT_SP_STMT_COMPLETED => "sp_statement_completed",
+        EVENT_ATTENTION => "attention",
+        EVENT_DEADLOCK_GRAPH => "deadlock_graph",
+        EVENT_BLOCKED_PROCESS_REPORT => "blocked_process_report",
+        EVENT_EXCEPTION | EVENT_USER_ERROR_MESSAGE if record.int(COL_SEVERITY) <= 10 => return None,
+        EVENT_EXCEPTION => "exception",
+        EVENT_USER_ERROR_MESSAGE => "user_error_message",
+        _ => return None,
+    };
+
+    let text_data = record.text(COL_TEXT_DATA);
+    let (sql_text, current_statement) = match record.event_class {
+        EVENT_SQL_BATCH_COMPLETED
+        | EVENT_SQL_BATCH_STARTING
+        | EVENT_ATTENTION
+        | EVENT_DEADLOCK_GRAPH
+        | EVENT_BLOCKED_PROCESS_REPORT
+        | EVENT_EXCEPTION
+        | EVENT_USER_ERROR_MESSAGE => (text_data, String::new()),
+        _ => (text_data.clone(), text_data),
+    };
+
+    let (error, event_status) = match record.event_class {
+        // For completions the Error column is the result: 1 = error, 2 = abort.
+        EVENT_RPC_COMPLETED | EVENT_SQL_BATCH_COMPLETED => (None, capture::completion_status(record.int(COL_ERROR))),
+        EVENT_EXCEPTION | EVENT_USER_ERROR_MESSAGE => (
+            Some(QueryError {
+                number: record.int(COL_ERROR) as i32,
+                severity: record.int(COL_SEVERITY) as i32,
+                message: record.text(COL_TEXT_DATA),
+            }),
+            String::new(),
+        ),
+        _ => (None, String::new()),
+    };
+
+    // CPU is recorded in milliseconds, Duration in microseconds.
+    let cpu_us = record.int(COL_CPU) * 1000;
+    let duration_us = record.int(COL_DURATION);
+
+    Some(QueryEvent {
+        id: String::new(),
+        session_id: record.int(COL_SPID) as i32,
+        start_time: record.datetime(COL_START_TIME),
+        end_time: record.datetime(COL_END_TIME),
+        event_name: event_name.to_string(),
+        database_name: record.text(COL_DATABASE_NAME),
+        cpu_time: cpu_us,
+        elapsed_time: duration_us,
+        cpu_time_ms: micros_to_ms(cpu_us),
+        elapsed_time_ms: micros_to_ms(duration_us),
+        physical_reads: None,
+        writes: record.int(COL_WRITES),
+        logical_reads: record.int(COL_READS),
+        row_count: record.int(COL_ROW_COUNTS),
+        sql_text,
+        current_statement,
+        fingerprint: String::new(),
+        query_hash: String::new(),
+        login_name: record.text(COL_LOGIN_NAME),
+        host_name: record.text(COL_HOST_NAME),
+        program_name: record.text(COL_APPLICATION_NAME),
+        object_name: record.text(COL_OBJECT_NAME),
+        client_process_id: record.int(COL_CLIENT_PROCESS_ID) as i32,
+        nt_user_name: record.text(COL_NT_USER_NAME),
+        transaction_id: record.int(COL_TRANSACTION_ID),
+        captured_at: String::new(),
+        event_status,
+        event_sequence: record.int(COL_EVENT_SEQUENCE),
+        parent_id: None,
+        error,
+        extra: serde_json::Map::new(),
+    })
+}
+
+/// Returns `path` followed by the rollover files SQL Server created after it
+/// (`name_1.trc`, `name_2.trc`, ...). Opening `name_3.trc` continues from 4.
+pub fn rollover_set(path: &Path) -> Vec<PathBuf> {
+    let mut files = vec![path.to_path_buf()];
+
+    let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else {
+        return files;
+    };
+    let extension = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("trc")
+        .to_string();
+
+    let (base, mut next) = match stem.rsplit_once('_') {
+        Some((base, suffix)) if !suffix.is_empty() && suffix.chars().all(|c| c.is_ascii_digit()) => {
+            match suffix.parse::<u32>() {
+                Ok(n) => (base.to_string(), n + 1),
+                Err(_) => (stem.to_string(), 1),
+            }
+        }
+        _ => (stem.to_string(), 1),
+    };
+
+    loop {
+        let candidate = path.with_file_name(format!("{base}_{next}.{extension}"));
+        if !candidate.is_file() {
+            break;
+        }
+        files.push(candidate);
+        next += 1;
+    }
+
+    files
+}
+
+/// Replays a `.trc` file set by parsing the files here, one batch per poll.
+pub struct TraceFileSource {
+    files: Vec<PathBuf>,
+    next_file: usize,
+    reader: Option<TraceFileReader>,
+}
+
+impl TraceFileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            files: rollover_set(&path),
+            next_file: 0,
+            reader: None,
+        }
+    }
+}
+
+#[async_trait]
+impl CaptureSource for TraceFileSource {
+    async fn start(&mut self) -> Result<(), String> {
+        // Open the first file up front so a wrong path fails the start
+        // command rather than the first poll.
+        let first = self
+            .files
+            .first()
+            .ok_or_else(|| "No trace file selected".to_string())?;
+        self.reader = Some(TraceFileReader::open(first)?);
+        self.next_file = 1;
+        Ok(())
+    }
+
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        let mut events = Vec::new();
+
+        while events.len() < OFFLINE_BATCH_SIZE {
+            let Some(reader) = self.reader.as_mut() else {
+                break;
+            };
+
+            match reader.next_record()? {
+                Some(record) => {
+                    if let Some(event) = record_to_query_event(&record) {
+                        events.push(event);
+                    }
+                }
+                None => {
+                    self.reader = match self.files.get(self.next_file) {
+                        Some(path) => Some(TraceFileReader::open(path)?),
+                        None => None,
+                    };
+                    self.next_file += 1;
+                }
+            }
+        }
+
+        if events.is_empty() && self.reader.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(events))
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn cleanup(&mut self) -> Result<(), String> {
+        self.reader = None;
+        Ok(())
+    }
+}
+
+/// Replays a `.trc` file set through the connected server with
+/// `sys.fn_trace_gettable`. Only used when the user asks for it, for paths
+/// the server can open (its own disk or a share). A background task streams
+/// the rows so a large file is never held in memory at once.
+pub struct ServerTraceFileSource {
+    files: Vec<PathBuf>,
+    config: ConnectionConfig,
+    batches: Option<mpsc::Receiver<Result<Vec<QueryEvent>, String>>>,
+    reader: Option<JoinHandle<()>>,
+    /// The first batch, read by `start` to surface a bad path early.
+    first: Option<Vec<QueryEvent>>,
+}
+
+impl ServerTraceFileSource {
+    pub fn new(path: impl Into<PathBuf>, config: ConnectionConfig) -> Self {
+        let path = path.into();
+        Self {
+            files: rollover_set(&path),
+            config,
+            batches: None,
+            reader: None,
+            first: None,
+        }
+    }
+}
+
+/// Reads `files` in order, stopping at the first error.
+async fn read_files_on_server(
+    mut client: SqlClient,
+    files: Vec<PathBuf>,
+    tx: mpsc::Sender<Result<Vec<QueryEvent>, String>>,
+) {
+    for path in files {
+        if tx.is_closed() {
+            return;
+        }
+        if let Err(e) = trace::read_trace_file(&mut client, &path.to_string_lossy(), OFFLINE_BATCH_SIZE, &tx).await {
+            let _ = tx.send(Err(e)).await;
+            return;
+        }
+    }
+}
+
+#[async_trait]
+impl CaptureSource for ServerTraceFileSource {
+    async fn start(&mut self) -> Result<(), String> {
+        if self.files.is_empty() {
+            return Err("No trace file selected".to_string());
+        }
+        let client = db::connect(&self.config).await?;
+        let (tx, mut rx) = mpsc::channel(2);
+        self.reader = Some(tokio::spawn(read_files_on_server(client, self.files.clone(), tx)));
+        self.first = rx.recv().await.transpose()?;
+        self.batches = Some(rx);
+        Ok(())
+    }
+
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        if let Some(first) = self.first.take() {
+            return Ok(Some(first));
+        }
+        match self.batches.as_mut() {
+            Some(batches) => batches.recv().await.transpose(),
+            None => Ok(None),
+        }
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn cleanup(&mut self) -> Result<(), String> {
+        self.batches = None;
+        self.first = None;
+        if let Some(reader) = self.reader.take() {
+            reader.abort();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("sqlprof-{name}-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn rollover_set_follows_numbered_files() {
+        let dir = temp_dir("rollover");
+        for name in ["capture.trc", "capture_1.trc", "capture_2.trc", "capture_4.trc", "other_1.trc"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let names = |files: Vec<PathBuf>| -> Vec<String> {
+            files
+                .iter()
+                .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+        // Stops at the first gap.
+        assert_eq!(
+            names(rollover_set(&dir.join("capture.trc"))),
+            ["capture.trc", "capture_1.trc", "capture_2.trc"]
+        );
+        assert_eq!(names(rollover_set(&dir.join("capture_1.trc"))), ["capture_1.trc", "capture_2.trc"]);
+        assert_eq!(names(rollover_set(&dir.join("missing.trc"))), ["missing.trc"]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    fn trace_header() -> Vec<u8> {
+        let mut header = TRC_UNICODE_MARKER.to_le_bytes().to_vec();
+        header.extend((TRC_HEADER_LEN as u16).to_le_bytes());
+        header.extend(TRC_PROVIDER_NAME.encode_utf16().flat_map(|unit| unit.to_le_bytes()));
+        header.resize(TRC_HEADER_LEN, 0);
+        header
+    }
+
+    fn column(id: u16, data: &[u8]) -> Vec<u8> {
+        let mut bytes = id.to_le_bytes().to_vec();
+        bytes.extend((data.len() as u32).to_le_bytes());
+        bytes.extend(data);
+        bytes
+    }
+
+    fn utf16(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect()
+    }
+
+    fn systemtime(fields: [u16; 8]) -> Vec<u8> {
+        fields.iter().flat_map(|field| field.to_le_bytes()).collect()
+    }
+
+    fn record(event_class: u16, columns: &[Vec<u8>]) -> Vec<u8> {
+        let body: Vec<u8> = columns.concat();
+        let mut bytes = TRACE_BEGIN_RECORD.to_le_bytes().to_vec();
+        bytes.extend(((RECORD_PREFIX_LEN + body.len()) as u32).to_le_bytes());
+        bytes.extend(event_class.to_le_bytes());
+        bytes.extend(body);
+        bytes
+    }
+
+    fn reader(bytes: Vec<u8>) -> TraceFileReader<std::io::Cursor<Vec<u8>>> {
+        TraceFileReader::new(std::io::Cursor::new(bytes), Path::new("test.trc")).unwrap()
+    }
+
+    #[test]
+    fn next_record_decodes_an_rpc_completion() {
+        let mut file = trace_header();
+        file.extend(record(
+            EVENT_RPC_COMPLETED,
+            &[
+                column(COL_TEXT_DATA, &utf16("exec dbo.GetOrders @id = 7")),
+                column(COL_SPID, &57_i32.to_le_bytes()),
+                column(COL_DATABASE_NAME, &utf16("Sales")),
+                column(COL_APPLICATION_NAME, &utf16("OrderService")),
+                column(COL_LOGIN_NAME, &utf16("app")),
+                column(COL_DURATION, &2_500_i64.to_le_bytes()),
+                column(COL_CPU, &2_i32.to_le_bytes()),
+                column(COL_READS, &120_i64.to_le_bytes()),
+                column(COL_WRITES, &3_i64.to_le_bytes()),
+                column(COL_ROW_COUNTS, &10_i64.to_le_bytes()),
+                column(COL_ERROR, &2_i32.to_le_bytes()),
+                column(COL_EVENT_SEQUENCE, &41_i64.to_le_bytes()),
+                column(COL_START_TIME, &systemtime([2024, 3, 5, 14, 9, 30, 15, 250])),
+                // Binary columns are skipped.
+                column(2, &[0xAB; 5]),
+            ],
+        ));
+        file.extend([0_u8; 64]);
+        let mut reader = reader(file);
+
+        let record = reader.next_record().unwrap().unwrap();
+        let event = record_to_query_event(&record).unwrap();
+        assert_eq!(event.event_name, "rpc_completed");
+        assert_eq!(event.session_id, 57);
+        assert_eq!(event.sql_text, "exec dbo.GetOrders @id = 7");
+        assert_eq!(event.current_statement, event.sql_text);
+        assert_eq!(event.database_name, "Sales");
+        assert_eq!(event.program_name, "OrderService");
+        assert_eq!(event.login_name, "app");
+        assert_eq!(event.start_time, "2024-03-14T09:30:15.250");
+        assert_eq!(event.elapsed_time, 2_500);
+        assert_eq!(event.cpu_time, 2_000);
+        assert_eq!(event.logical_reads, 120);
+        assert_eq!(event.writes, 3);
+        assert_eq!(event.row_count, 10);
+        assert_eq!(event.event_status, "cancelled");
+        assert_eq!(event.event_sequence, 41);
+        assert!(event.error.is_none());
+
+        // The zeroed preallocated tail ends the file.
+        assert!(reader.next_record().unwrap().is_none());
+    }
+
+    #[test]
+    fn errors_keep_their_number_and_informational_messages_are_dropped() {
+        let mut file = trace_header();
+        file.extend(record(
+            EVENT_USER_ERROR_MESSAGE,
+            &[
+                column(COL_TEXT_DATA, &utf16("Invalid object name 'dbo.Missing'.")),
+                column(COL_ERROR, &208_i32.to_le_bytes()),
+                column(COL_SEVERITY, &16_i32.to_le_bytes()),
+            ],
+        ));
+        file.extend(record(
+            EVENT_USER_ERROR_MESSAGE,
+            &[
+                column(COL_TEXT_DATA, &utf16("Changed database context to 'Sales'.")),
+                column(COL_ERROR, &5701_i32.to_le_bytes()),
+                column(COL_SEVERITY, &10_i32.to_le_bytes()),
+            ],
+        ));
+        let mut reader = reader(file);
+
+        let event = record_to_query_event(&reader.next_record().unwrap().unwrap()).unwrap();
+        assert_eq!(event.event_name, "user_error_message");
+        let error = event.error.unwrap();
+        assert_eq!((error.number, error.severity), (208, 16));
+        assert_eq!(error.message, "Invalid object name 'dbo.Missing'.");
+        assert!(event.current_statement.is_empty());
+
+        assert!(record_to_query_event(&reader.next_record().unwrap().unwrap()).is_none());
+        assert!(reader.next_record().unwrap().is_none());
+    }
+
+    #[test]
+    fn corrupt_record_lengths_are_rejected() {
+        let prefix = |len: u32| {
+            let mut bytes = trace_header();
+            bytes.extend(TRACE_BEGIN_RECORD.to_le_bytes());
+            bytes.extend(len.to_le_bytes());
+            bytes.extend(EVENT_RPC_COMPLETED.to_le_bytes());
+            bytes
+        };
+
+        let error = reader(prefix(u32::MAX)).next_record().unwrap_err();
+        assert!(error.contains("length"), "{error}");
+        assert!(reader(prefix(4)).next_record().is_err());
+
+        // Claims more bytes than the file holds.
+        let mut truncated = prefix(100);
+        truncated.extend([0_u8; 10]);
+        let error = reader(truncated).next_record().unwrap_err();
+        assert!(error.contains("truncated"), "{error}");
+
+        // A column that runs past the end of its record.
+        let mut overrun = trace_header();
+        let mut bad = record(EVENT_RPC_COMPLETED, &[column(COL_SPID, &57_i32.to_le_bytes())]);
+        bad[RECORD_PREFIX_LEN + 2] = 200;
+        overrun.extend(bad);
+        assert!(reader(overrun).next_record().is_err());
+    }
+
+    #[test]
+    fn files_without_the_trace_header_are_rejected() {
+        let mut header = trace_header();
+        header[0] = 0;
+        assert!(TraceFileReader::new(std::io::Cursor::new(header), Path::new("x.trc")).is_err());
+        assert!(TraceFileReader::new(std::io::Cursor::new(vec![0_u8; 10]), Path::new("x.trc")).is_err());
+    }
+
+    #[test]
+    fn systemtime_formats_like_the_live_poll() {
+        let mut data = Vec::new();
+        for field in [2024_u16, 3, 5, 14, 9, 30, 15, 250] {
+            data.extend(field.to_le_bytes());
+        }
+        assert_eq!(decode_systemtime(&data).as_deref(), Some("2024-03-14T09:30:15.250"));
+        assert_eq!(decode_systemtime(&data[..14]), None);
+    }
+}
@@ -0,0 +1,749 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader, Lines};
+use tokio::process::{Child, ChildStdout, Command};
+use tokio::task::JoinHandle;
+
+use crate::capture::{self, CaptureSource};
+use crate::db::{self, ConnectionConfig, SqlClient};
+use crate::profiler::{micros_to_ms, QueryError, QueryEvent};
+use crate::xevents::{self, BufferPosition};
+
+/// Decodes the files named in `SQLPROF_XEL_PATHS` (one per line) with
+/// `Read-SqlXEvent` from the SqlServer PowerShell module, which wraps
+/// Microsoft's XEL reader (XELite), and writes one JSON object per event:
+/// `{name, timestamp (UTC, ISO 8601), fields, actions}`. Binary values are
+/// base64, XML and other non-scalar values their text.
+const XEL_READER_SCRIPT: &str = r#"
+$ErrorActionPreference = 'Stop'
+[Console]::OutputEncoding = New-Object System.Text.UTF8Encoding $false
+if (-not (Get-Command Read-SqlXEvent -ErrorAction SilentlyContinue)) {
+    Import-Module SqlServer -ErrorAction SilentlyContinue
+}
+if (-not (Get-Command Read-SqlXEvent -ErrorAction SilentlyContinue)) {
+    [Console]::Error.WriteLine('Reading .xel files needs the SqlServer PowerShell module (Install-Module SqlServer -Scope CurrentUser), or a connection so the server can read them.')
+    exit 3
+}
+function ConvertTo-JsonValue($value) {
+    if ($null -eq $value) { return $null }
+    if ($value -is [byte[]]) { return [Convert]::ToBase64String($value) }
+    if ($value -is [System.Xml.XmlNode]) { return $value.OuterXml }
+    if ($value -is [string] -or $value -is [bool]) { return $value }
+    if ($value -is [ValueType] -and $value -is [IConvertible] -and $value -isnot [datetime] -and $value -isnot [char]) {
+        return $value
+    }
+    return [string]$value
+}
+foreach ($path in $env:SQLPROF_XEL_PATHS -split "`n") {
+    Read-SqlXEvent -FileName $path | ForEach-Object {
+        $fields = [ordered]@{}
+        foreach ($entry in $_.Fields.GetEnumerator()) { $fields[$entry.Key] = ConvertTo-JsonValue $entry.Value }
+        $actions = [ordered]@{}
+        foreach ($entry in $_.Actions.GetEnumerator()) { $actions[$entry.Key] = ConvertTo-JsonValue $entry.Value }
+        [ordered]@{
+            name = $_.Name
+            timestamp = $_.Timestamp.UtcDateTime.ToString('o')
+            fields = $fields
+            actions = $actions
+        } | ConvertTo-Json -Compress -Depth 3
+    }
+}
+"#;
+
+/// Shells tried in order for `XEL_READER_SCRIPT`.
+const POWERSHELL: &[&str] = &["pwsh", "powershell"];
+
+/// Reads events from an `event_file` target's files (`@P1`, wildcards
+/// allowed) after the buffer at `@P2`/`@P3`, or from the start when those
+/// are NULL. `fn_xe_file_target_read_file` resumes at buffer granularity:
+/// every event of the given buffer is skipped, so a page must end on a
+/// buffer boundary. Rows come back in file order.
+const XEL_READ_EVENTS: &str = "
+DECLARE @utc_offset_min int = DATEDIFF(minute, SYSUTCDATETIME(), SYSDATETIME());
+
+SELECT TOP (@P4)
+    e.file_name,
+    e.file_offset,{event_output}FROM (
+    SELECT
+        f.file_name,
+        f.file_offset,{event_columns}    FROM sys.fn_xe_file_target_read_file(@P1, NULL, @P2, @P3) AS f
+    CROSS APPLY (SELECT CAST(f.event_data AS xml) AS event_xml) AS x
+    CROSS APPLY x.event_xml.nodes('/event') AS q(n)
+) e;
+";
+
+const OFFLINE_BATCH_SIZE: usize = 5000;
+
+/// One line of `XEL_READER_SCRIPT` output.
+#[derive(Debug, Deserialize)]
+struct XelEvent {
+    name: String,
+    timestamp: String,
+    #[serde(default)]
+    fields: serde_json::Map<String, serde_json::Value>,
+    #[serde(default)]
+    actions: serde_json::Map<String, serde_json::Value>,
+}
+
+impl XelEvent {
+    fn field_i64(&self, name: &str) -> i64 {
+        self.fields.get(name).and_then(value_i64).unwrap_or(0)
+    }
+
+    fn field_text(&self, name: &str) -> String {
+        self.fields.get(name).map(value_text).unwrap_or_default()
+    }
+
+    fn action_i64(&self, name: &str) -> i64 {
+        self.actions.get(name).and_then(value_i64).unwrap_or(0)
+    }
+
+    fn action_text(&self, name: &str) -> String {
+        self.actions.get(name).map(value_text).unwrap_or_default()
+    }
+
+    /// The `result` of a completion as the Error column codes it (1 = error,
+    /// 2 = abort). XELite gives map fields as their text.
+    fn result_code(&self) -> i64 {
+        match self.fields.get("result") {
+            Some(serde_json::Value::String(text)) => match text.as_str() {
+                "Error" => 1,
+                "Abort" => 2,
+                _ => 0,
+            },
+            Some(value) => value_i64(value).unwrap_or(0),
+            None => 0,
+        }
+    }
+}
+
+fn value_i64(value: &serde_json::Value) -> Option<i64> {
+    match value {
+        serde_json::Value::Number(n) => n.as_i64().or_else(|| n.as_u64().map(|v| v as i64)),
+        serde_json::Value::Bool(b) => Some(*b as i64),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn value_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// `timestamp` less `minus_us`, in this machine's local time and the format
+/// the live poll uses.
+fn format_local_time(timestamp: &chrono::DateTime<chrono::Utc>, minus_us: i64) -> String {
+    (*timestamp - chrono::Duration::microseconds(minus_us))
+        .with_timezone(&chrono::Local)
+        .format("%Y-%m-%dT%H:%M:%S%.3f")
+        .to_string()
+}
+
+fn xe_event_to_query_event(event: &XelEvent) -> Option<QueryEvent> {
+    let (sql_text, current_statement) = match event.name.as_str() {
+        "rpc_starting" | "rpc_completed" | "sp_statement_completed" | "sql_statement_completed" => {
+            let statement = event.field_text("statement");
+            (statement.clone(), statement)
+        }
+        "sql_batch_starting" | "sql_batch_completed" => (event.field_text("batch_text"), String::new()),
+        "error_reported" if event.field_i64("severity") <= 10 => return None,
+        "error_reported" => (event.field_text("message"), String::new()),
+        "attention" => (String::new(), String::new()),
+        "xml_deadlock_report" | "database_xml_deadlock_report" => (event.field_text("xml_report"), String::new()),
+        "blocked_process_report" => (event.field_text("blocked_process"), String::new()),
+        _ => return None,
+    };
+
+    let timestamp = chrono::DateTime::parse_from_rfc3339(&event.timestamp)
+        .ok()?
+        .with_timezone(&chrono::Utc);
+    let duration_us = event.field_i64("duration");
+    let cpu_us = event.field_i64("cpu_time");
+    let database_name = match event.action_text("database_name") {
+        name if name.is_empty() => event.field_text("database_name"),
+        name => name,
+    };
+    let login_name = match event.action_text("server_principal_name") {
+        name if name.is_empty() => event.action_text("username"),
+        name => name,
+    };
+
+    // Only events with a duration end at their timestamp.
+    let end_time = if event.fields.contains_key("duration") {
+        format_local_time(&timestamp, 0)
+    } else {
+        String::new()
+    };
+
+    let (error, event_status) = match event.name.as_str() {
+        "rpc_completed" | "sql_batch_completed" => (None, capture::completion_status(event.result_code())),
+        "error_reported" => (
+            Some(QueryError {
+                number: event.field_i64("error_number") as i32,
+                severity: event.field_i64("severity") as i32,
+                message: sql_text.clone(),
+            }),
+            String::new(),
+        ),
+        _ => (None, String::new()),
+    };
+
+    Some(QueryEvent {
+        id: String::new(),
+        session_id: event.action_i64("session_id") as i32,
+        start_time: format_local_time(&timestamp, duration_us),
+        end_time,
+        event_name: match event.name.as_str() {
+            "xml_deadlock_report" | "database_xml_deadlock_report" => "deadlock_graph".to_string(),
+            name => name.to_string(),
+        },
+        database_name,
+        cpu_time: cpu_us,
+        elapsed_time: duration_us,
+        cpu_time_ms: micros_to_ms(cpu_us),
+        elapsed_time_ms: micros_to_ms(duration_us),
+        physical_reads: event.fields.get("physical_reads").and_then(value_i64),
+        writes: event.field_i64("writes"),
+        logical_reads: event.field_i64("logical_reads"),
+        row_count: event.field_i64("row_count"),
+        sql_text,
+        current_statement,
+        fingerprint: String::new(),
+        query_hash: String::new(),
+        login_name,
+        host_name: event.action_text("client_hostname"),
+        program_name: event.action_text("client_app_name"),
+        object_name: event.field_text("object_name"),
+        client_process_id: event.action_i64("client_pid") as i32,
+        nt_user_name: event.action_text("nt_username"),
+        transaction_id: event.action_i64("transaction_id"),
+        captured_at: String::new(),
+        event_status,
+        event_sequence: event.action_i64("event_sequence"),
+        parent_id: None,
+        error,
+        extra: serde_json::Map::new(),
+    })
+}
+
+/// Returns every file of the `event_file` target that `path` belongs to,
+/// oldest first. The target names its files `<name>_0_<start ticks>.xel`.
+pub fn xel_file_set(path: &Path) -> Vec<PathBuf> {
+    let fallback = vec![path.to_path_buf()];
+
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else {
+        return fallback;
+    };
+    let Some((prefix, ticks)) = stem.rsplit_once('_') else {
+        return fallback;
+    };
+    if ticks.is_empty() || !ticks.chars().all(|c| c.is_ascii_digit()) {
+        return fallback;
+    }
+
+    let Ok(entries) = std::fs::read_dir(if dir.as_os_str().is_empty() { Path::new(".") } else { dir }) else {
+        return fallback;
+    };
+
+    let mut files: Vec<(u64, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|candidate| {
+            let is_xel = candidate
+                .extension()
+                .and_then(|s| s.to_str())
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("xel"));
+            let candidate_stem = candidate.file_stem()?.to_str()?;
+            let (candidate_prefix, candidate_ticks) = candidate_stem.rsplit_once('_')?;
+            if !is_xel || candidate_prefix != prefix {
+                return None;
+            }
+            Some((candidate_ticks.parse::<u64>().ok()?, candidate))
+        })
+        .collect();
+
+    if files.is_empty() {
+        return fallback;
+    }
+    files.sort();
+    files.into_iter().map(|(_, p)| p).collect()
+}
+
+/// Replays the events of an XE `event_file` target without a server. The
+/// file format is undocumented, so the files are decoded by Microsoft's own
+/// reader through `XEL_READER_SCRIPT`; this needs PowerShell and the
+/// SqlServer module on this machine.
+pub struct XelFileSource {
+    files: Vec<PathBuf>,
+    reader: Option<Child>,
+    lines: Option<Lines<BufReader<ChildStdout>>>,
+    /// Collects the reader's stderr so a failure can be reported.
+    errors: Option<JoinHandle<String>>,
+    /// Read by `start` to check the files, delivered by the first poll.
+    pending: Option<Vec<QueryEvent>>,
+}
+
+impl XelFileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        Self {
+            files: xel_file_set(&path),
+            reader: None,
+            lines: None,
+            errors: None,
+            pending: None,
+        }
+    }
+
+    fn spawn_reader(&self) -> Result<Child, String> {
+        let paths = self
+            .files
+            .iter()
+            .map(|f| f.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("\n");
+        for shell in POWERSHELL {
+            let mut command = Command::new(shell);
+            command
+                .args(["-NoProfile", "-NonInteractive", "-Command", XEL_READER_SCRIPT])
+                .env("SQLPROF_XEL_PATHS", &paths)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            #[cfg(windows)]
+            command.creation_flags(0x0800_0000); // CREATE_NO_WINDOW
+            match command.spawn() {
+                Ok(child) => return Ok(child),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(format!("Failed to start PowerShell to read the .xel file: {e}")),
+            }
+        }
+        Err("Reading .xel files without a connection needs PowerShell with the SqlServer module. \
+             Connect and let the server read the file instead."
+            .into())
+    }
+
+    /// Reads up to a batch of events; `None` once the reader is done.
+    async fn read_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        let Some(lines) = self.lines.as_mut() else {
+            return Ok(None);
+        };
+
+        let mut events = Vec::new();
+        while events.len() < OFFLINE_BATCH_SIZE {
+            let line = lines
+                .next_line()
+                .await
+                .map_err(|e| format!("Failed to read the .xel reader's output: {e}"))?;
+            let Some(line) = line else {
+                self.lines = None;
+                self.finish_reader().await?;
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let event: XelEvent = serde_json::from_str(&line)
+                .map_err(|e| format!("The .xel reader wrote an unexpected line: {e}"))?;
+            events.extend(xe_event_to_query_event(&event));
+        }
+
+        if events.is_empty() && self.lines.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(events))
+    }
+
+    /// Waits for the reader to exit and turns a failure into its stderr.
+    async fn finish_reader(&mut self) -> Result<(), String> {
+        let Some(mut reader) = self.reader.take() else {
+            return Ok(());
+        };
+        let status = reader
+            .wait()
+            .await
+            .map_err(|e| format!("Failed to wait for the .xel reader: {e}"))?;
+        let stderr = match self.errors.take() {
+            Some(errors) => errors.await.unwrap_or_default(),
+            None => String::new(),
+        };
+        if status.success() {
+            return Ok(());
+        }
+        let detail = stderr.trim();
+        Err(if detail.is_empty() {
+            format!("The .xel reader failed ({status})")
+        } else {
+            format!("Failed to read the .xel file: {detail}")
+        })
+    }
+}
+
+#[async_trait]
+impl CaptureSource for XelFileSource {
+    async fn start(&mut self) -> Result<(), String> {
+        if let Some(missing) = self.files.iter().find(|f| !f.is_file()) {
+            return Err(format!("'{}' does not exist", missing.display()));
+        }
+
+        let mut reader = self.spawn_reader()?;
+        self.lines = reader.stdout.take().map(|stdout| BufReader::new(stdout).lines());
+        self.errors = reader.stderr.take().map(|mut stderr| {
+            tokio::spawn(async move {
+                let mut text = String::new();
+                let _ = stderr.read_to_string(&mut text).await;
+                text
+            })
+        });
+        self.reader = Some(reader);
+
+        // Read the first batch up front so a missing module or an unreadable
+        // file fails the start command rather than the first poll.
+        self.pending = self.read_batch().await?;
+        Ok(())
+    }
+
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        if let Some(events) = self.pending.take() {
+            return Ok(Some(events));
+        }
+        self.read_batch().await
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn cleanup(&mut self) -> Result<(), String> {
+        self.lines = None;
+        self.pending = None;
+        if let Some(mut reader) = self.reader.take() {
+            let _ = reader.kill().await;
+        }
+        if let Some(errors) = self.errors.take() {
+            errors.abort();
+        }
+        Ok(())
+    }
+}
+
+/// The pattern matching every file of the `event_file` target that `path`
+/// belongs to. The target names its files `<name>_0_<start ticks>.xel`;
+/// other names are read as they are.
+fn xel_file_pattern(path: &str) -> String {
+    let split = path.rfind(['\\', '/']).map_or(0, |i| i + 1);
+    let (dir, file) = path.split_at(split);
+    let Some(stem) = file.strip_suffix(".xel").or_else(|| file.strip_suffix(".XEL")) else {
+        return path.to_string();
+    };
+    match stem.rsplit_once('_') {
+        Some((prefix, ticks)) if !ticks.is_empty() && ticks.chars().all(|c| c.is_ascii_digit()) => {
+            format!("{dir}{prefix}_*.xel")
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// How many of `positions` (one per row, in read order) belong to buffers
+/// that were read completely: all of them when the read returned fewer than
+/// `limit` rows, otherwise all but the rows of the last buffer, whose other
+/// events were cut off. Zero when a single buffer holds `limit` rows or more.
+fn complete_rows(positions: &[BufferPosition], limit: usize) -> usize {
+    if positions.len() < limit {
+        return positions.len();
+    }
+    let Some(last) = positions.last() else {
+        return 0;
+    };
+    positions.iter().rposition(|p| p != last).map_or(0, |i| i + 1)
+}
+
+/// Replays the events of an XE `event_file` target through the connected
+/// server with `sys.fn_xe_file_target_read_file`, when the user asks for it.
+/// The path has to be one the server can open, e.g. when it runs on this
+/// machine or the file is on a share.
+pub struct ServerXelFileSource {
+    pattern: String,
+    config: ConnectionConfig,
+    client: Option<SqlClient>,
+    resume: Option<BufferPosition>,
+    /// Read by `start` to check the path, delivered by the first poll.
+    pending: Option<Vec<QueryEvent>>,
+    finished: bool,
+}
+
+impl ServerXelFileSource {
+    pub fn new(path: &str, config: ConnectionConfig) -> Self {
+        Self {
+            pattern: xel_file_pattern(path),
+            config,
+            client: None,
+            resume: None,
+            pending: None,
+            finished: false,
+        }
+    }
+
+    /// Reads the next page of whole buffers.
+    async fn read_page(&mut self) -> Result<Vec<QueryEvent>, String> {
+        let Some(client) = self.client.as_mut() else {
+            return Err("The XEL file is not open".into());
+        };
+
+        let mut limit = OFFLINE_BATCH_SIZE;
+        loop {
+            let rows = read_rows(client, &self.pattern, self.resume.as_ref(), limit).await?;
+            let positions: Vec<BufferPosition> = rows.iter().map(|(position, _)| position.clone()).collect();
+            let complete = complete_rows(&positions, limit);
+            if complete == 0 && !rows.is_empty() {
+                // One buffer holds more events than the page; read more.
+                limit *= 2;
+                continue;
+            }
+
+            self.finished = rows.len() < limit;
+            if let Some(position) = complete.checked_sub(1).map(|i| positions[i].clone()) {
+                self.resume = Some(position);
+            }
+            return Ok(rows.into_iter().take(complete).filter_map(|(_, event)| event).collect());
+        }
+    }
+}
+
+async fn read_rows(
+    client: &mut SqlClient,
+    pattern: &str,
+    resume: Option<&BufferPosition>,
+    limit: usize,
+) -> Result<Vec<(BufferPosition, Option<QueryEvent>)>, String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(xevents::with_event_columns(XEL_READ_EVENTS));
+    query.bind(pattern);
+    query.bind(resume.map(|(file, _)| file.as_str()));
+    query.bind(resume.map(|(_, offset)| *offset));
+    query.bind(i32::try_from(limit).unwrap_or(i32::MAX));
+
+    let rows = query
+        .query(client)
+        .await
+        .map_err(|e| {
+            format!(
+                "The server failed to read '{pattern}': {e}. It opens the file itself, \
+                 so the path must exist on the server or on a share it can reach."
+            )
+        })?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to read events from '{pattern}': {e}"))?;
+
+    Ok(rows
+        .last()
+        .map(|result_set| {
+            result_set
+                .iter()
+                .map(|row| {
+                    let position = (
+                        row.get::<&str, _>("file_name").unwrap_or("").to_string(),
+                        row.get::<i64, _>("file_offset").unwrap_or(0),
+                    );
+                    (position, xevents::xe_row_to_event(row))
+                })
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+#[async_trait]
+impl CaptureSource for ServerXelFileSource {
+    async fn start(&mut self) -> Result<(), String> {
+        // Read the first page up front so a wrong path fails the start
+        // command rather than the first poll.
+        self.client = Some(db::connect(&self.config).await?);
+        self.pending = Some(self.read_page().await?);
+        Ok(())
+    }
+
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        if let Some(events) = self.pending.take() {
+            return Ok(Some(events));
+        }
+        if self.finished {
+            return Ok(None);
+        }
+        self.read_page().await.map(Some)
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        Ok(())
+    }
+
+    async fn cleanup(&mut self) -> Result<(), String> {
+        self.client = None;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(line: &str) -> Option<QueryEvent> {
+        xe_event_to_query_event(&serde_json::from_str(line).unwrap())
+    }
+
+    fn local(utc: &str) -> String {
+        let utc = chrono::DateTime::parse_from_rfc3339(utc).unwrap();
+        utc.with_timezone(&chrono::Local).format("%Y-%m-%dT%H:%M:%S%.3f").to_string()
+    }
+
+    #[test]
+    fn rpc_completion_maps_like_the_live_poll() {
+        let event = event(
+            r#"{"name":"rpc_completed","timestamp":"2024-03-14T09:30:15.2500000Z",
+                "fields":{"cpu_time":2000,"duration":1500000,"physical_reads":4,"logical_reads":120,"writes":3,
+                          "result":"Abort","row_count":10,"object_name":"GetOrders",
+                          "statement":"exec dbo.GetOrders @id = 7","data_stream":"AAEC"},
+                "actions":{"session_id":57,"database_name":"Sales","client_app_name":"OrderService",
+                           "client_hostname":"APP01","server_principal_name":"app","event_sequence":"41",
+                           "transaction_id":9001}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(event.event_name, "rpc_completed");
+        assert_eq!(event.session_id, 57);
+        assert_eq!(event.sql_text, "exec dbo.GetOrders @id = 7");
+        assert_eq!(event.current_statement, event.sql_text);
+        assert_eq!(event.start_time, local("2024-03-14T09:30:13.750Z"));
+        assert_eq!(event.end_time, local("2024-03-14T09:30:15.250Z"));
+        assert_eq!((event.elapsed_time, event.cpu_time), (1_500_000, 2000));
+        assert_eq!((event.logical_reads, event.physical_reads, event.writes), (120, Some(4), 3));
+        assert_eq!(event.row_count, 10);
+        assert_eq!(event.event_status, "cancelled");
+        assert_eq!(event.database_name, "Sales");
+        assert_eq!(event.program_name, "OrderService");
+        assert_eq!(event.host_name, "APP01");
+        assert_eq!(event.login_name, "app");
+        assert_eq!(event.object_name, "GetOrders");
+        assert_eq!(event.event_sequence, 41);
+        assert_eq!(event.transaction_id, 9001);
+        assert!(event.error.is_none());
+    }
+
+    #[test]
+    fn batches_and_statements_keep_their_text_apart() {
+        let batch = event(
+            r#"{"name":"sql_batch_completed","timestamp":"2024-03-14T09:30:15Z",
+                "fields":{"batch_text":"select 1; select 2","duration":10,"result":"OK"},"actions":{}}"#,
+        )
+        .unwrap();
+        assert_eq!(batch.sql_text, "select 1; select 2");
+        assert!(batch.current_statement.is_empty());
+        assert!(batch.event_status.is_empty());
+
+        let statement = event(
+            r#"{"name":"sql_statement_completed","timestamp":"2024-03-14T09:30:15Z",
+                "fields":{"statement":"select 2"},"actions":{}}"#,
+        )
+        .unwrap();
+        assert_eq!(statement.current_statement, "select 2");
+        // Without a duration there is no end time.
+        assert!(statement.end_time.is_empty());
+
+        // A numeric result is the Error column code.
+        let failed = event(
+            r#"{"name":"sql_batch_completed","timestamp":"2024-03-14T09:30:15Z","fields":{"result":1}}"#,
+        )
+        .unwrap();
+        assert_eq!(failed.event_status, "failed");
+    }
+
+    #[test]
+    fn errors_and_deadlocks_map_and_other_events_are_dropped() {
+        let error = event(
+            r#"{"name":"error_reported","timestamp":"2024-03-14T09:30:15Z",
+                "fields":{"error_number":208,"severity":16,"message":"Invalid object name 'dbo.Missing'."},
+                "actions":{"session_id":57}}"#,
+        )
+        .unwrap()
+        .error
+        .unwrap();
+        assert_eq!((error.number, error.severity), (208, 16));
+        assert_eq!(error.message, "Invalid object name 'dbo.Missing'.");
+
+        assert!(event(
+            r#"{"name":"error_reported","timestamp":"2024-03-14T09:30:15Z",
+                "fields":{"error_number":5701,"severity":10,"message":"Changed database context to 'Sales'."}}"#
+        )
+        .is_none());
+
+        let deadlock = event(
+            r#"{"name":"xml_deadlock_report","timestamp":"2024-03-14T09:30:15Z",
+                "fields":{"xml_report":"<deadlock><victim-list/></deadlock>"}}"#,
+        )
+        .unwrap();
+        assert_eq!(deadlock.event_name, "deadlock_graph");
+        assert_eq!(deadlock.sql_text, "<deadlock><victim-list/></deadlock>");
+
+        assert!(event(r#"{"name":"login","timestamp":"2024-03-14T09:30:15Z"}"#).is_none());
+    }
+
+    #[test]
+    fn file_set_is_ordered_by_start_ticks() {
+        let dir = std::env::temp_dir().join(format!("sqlprof-xel-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["s_0_300.xel", "s_0_20.xel", "s_0_1000.xel", "other_0_5.xel", "s_0_7.txt"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let names: Vec<String> = xel_file_set(&dir.join("s_0_300.xel"))
+            .iter()
+            .map(|f| f.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names, ["s_0_20.xel", "s_0_300.xel", "s_0_1000.xel"]);
+        assert_eq!(xel_file_set(&dir.join("export.xel")), [dir.join("export.xel")]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn pattern_covers_the_rollover_set() {
+        assert_eq!(
+            xel_file_pattern(r"D:\Traces\capture_0_133531200000000000.xel"),
+            r"D:\Traces\capture_0_*.xel"
+        );
+        assert_eq!(xel_file_pattern("/var/opt/mssql/log/s_0_1.xel"), "/var/opt/mssql/log/s_0_*.xel");
+        assert_eq!(xel_file_pattern(r"D:\Traces\export.xel"), r"D:\Traces\export.xel");
+        assert_eq!(xel_file_pattern(r"D:\Traces\events_final.xel"), r"D:\Traces\events_final.xel");
+    }
+
+    fn positions(offsets: &[i64]) -> Vec<BufferPosition> {
+        offsets.iter().map(|offset| ("a.xel".to_string(), *offset)).collect()
+    }
+
+    #[test]
+    fn short_read_is_complete() {
+        assert_eq!(complete_rows(&positions(&[0, 0, 512, 512]), 10), 4);
+        assert_eq!(complete_rows(&[], 10), 0);
+    }
+
+    #[test]
+    fn full_read_drops_the_cut_off_buffer() {
+        assert_eq!(complete_rows(&positions(&[0, 0, 512, 512]), 4), 2);
+        let mut rows = positions(&[0, 512]);
+        rows.push(("b.xel".to_string(), 512));
+        assert_eq!(complete_rows(&rows, 3), 2);
+    }
+
+    #[test]
+    fn buffer_larger_than_the_page_reads_nothing() {
+        assert_eq!(complete_rows(&positions(&[512, 512, 512]), 3), 0);
+    }
+}
@@ -0,0 +1,739 @@
+use async_trait::async_trait;
+
+use crate::capture::{self, CaptureOptions, CaptureSource, PolledEvent, Watermark};
+use crate::db::{self, ConnectionConfig, SqlClient};
+use crate::profiler::{micros_to_ms, QueryError, QueryEvent};
+
+const XE_CREATE_AND_START: &str = "
+DECLARE @engine_edition int = CONVERT(int, SERVERPROPERTY('EngineEdition'));
+DECLARE @database_scope bit = CASE WHEN @engine_edition = 5 THEN 1 ELSE 0 END;
+DECLARE @scope nvarchar(10) = CASE WHEN @database_scope = 1 THEN N'DATABASE' ELSE N'SERVER' END;
+DECLARE @session_name sysname =
+    N'SimpleSQLProfiler_' + REPLACE(CONVERT(nvarchar(36), NEWID()), N'-', N'');
+DECLARE @file_pattern nvarchar(260) = NULL;
+DECLARE @target nvarchar(max);
+
+IF @P1 = N'event_file'
+BEGIN
+    IF @engine_edition IN (5, 8)
+    BEGIN
+        RAISERROR('The event_file target requires Azure Blob Storage on Azure SQL; use the ring buffer target instead.', 16, 1);
+        RETURN;
+    END
+
+    -- File options (@P10-@P13). event_file always rolls over; the file
+    -- count only changes how many files it keeps (5 by default).
+    DECLARE @directory nvarchar(260) = @P10;
+    IF @directory IS NULL
+    BEGIN
+        DECLARE @errorlog nvarchar(260) = CONVERT(nvarchar(260), SERVERPROPERTY('ErrorLogFileName'));
+        SET @directory = LEFT(@errorlog, LEN(@errorlog) - CHARINDEX('\\', REVERSE(@errorlog)) + 1);
+    END
+    ELSE IF RIGHT(@directory, 1) NOT IN (N'\\', N'/')
+    BEGIN
+        SET @directory = @directory + CASE WHEN CHARINDEX(N'/', @directory) > 0 THEN N'/' ELSE N'\\' END;
+    END
+    SET @target =
+        N'package0.event_file(SET filename = N''' + REPLACE(@directory + @session_name + N'.xel', N'''', N'''''')
+        + N''', max_file_size = ' + CONVERT(nvarchar(20), @P11)
+        + CASE WHEN @P12 = 1 AND @P13 IS NOT NULL THEN N', max_rollover_files = ' + CONVERT(nvarchar(20), @P13) ELSE N'' END
+        + N')';
+    SET @file_pattern = @directory + @session_name + N'*.xel';
+END
+ELSE
+BEGIN
+    SET @target = N'package0.ring_buffer(SET max_memory = 8192)';
+END
+
+DECLARE @actions nvarchar(max) = N'
+    ACTION(
+        package0.event_sequence,
+        sqlserver.client_app_name,
+        sqlserver.client_hostname,
+        sqlserver.client_pid,
+        sqlserver.database_name,
+        sqlserver.nt_username,
+        sqlserver.server_principal_name,
+        sqlserver.session_id,
+        sqlserver.transaction_id)';
+-- Exclude this app itself
+DECLARE @exclude_app nvarchar(max) =
+    N'NOT sqlserver.like_i_sql_unicode_string(sqlserver.client_app_name, N''%SimpleSQLProfiler%'')';
+
+-- User filters (@P3-@P9, NULL when not set)
+DECLARE @filter nvarchar(max) = N'';
+IF @P3 IS NOT NULL
+    SET @filter += N' AND sqlserver.like_i_sql_unicode_string(sqlserver.database_name, N''' + REPLACE(@P3, N'''', N'''''') + N''')';
+IF @P4 IS NOT NULL
+    SET @filter += N' AND sqlserver.like_i_sql_unicode_string(sqlserver.server_principal_name, N''' + REPLACE(@P4, N'''', N'''''') + N''')';
+IF @P5 IS NOT NULL
+    SET @filter += N' AND sqlserver.like_i_sql_unicode_string(sqlserver.client_hostname, N''' + REPLACE(@P5, N'''', N'''''') + N''')';
+IF @P6 IS NOT NULL
+    SET @filter += N' AND sqlserver.like_i_sql_unicode_string(sqlserver.client_app_name, N''' + REPLACE(@P6, N'''', N'''''') + N''')';
+
+DECLARE @measure_filter nvarchar(max) = N'';
+IF @P7 IS NOT NULL
+    SET @measure_filter += N' AND duration >= ' + CONVERT(nvarchar(20), @P7);
+IF @P8 IS NOT NULL
+    SET @measure_filter += N' AND logical_reads >= ' + CONVERT(nvarchar(20), @P8);
+
+DECLARE @statement_filter nvarchar(max) = CASE WHEN @P9 IS NULL THEN N''
+    ELSE N' AND sqlserver.like_i_sql_unicode_string(statement, N''' + REPLACE(@P9, N'''', N'''''') + N''')' END;
+DECLARE @batch_filter nvarchar(max) = CASE WHEN @P9 IS NULL THEN N''
+    ELSE N' AND sqlserver.like_i_sql_unicode_string(batch_text, N''' + REPLACE(@P9, N'''', N'''''') + N''')' END;
+
+DECLARE @event_options nvarchar(max) = @actions + N'
+    WHERE (' + @exclude_app + @filter + N')';
+DECLARE @rpc_options nvarchar(max) = @actions + N'
+    WHERE (' + @exclude_app + @filter + @statement_filter + N')';
+DECLARE @batch_options nvarchar(max) = @actions + N'
+    WHERE (' + @exclude_app + @filter + @batch_filter + N')';
+DECLARE @rpc_completed_options nvarchar(max) = @actions + N'
+    WHERE (' + @exclude_app + @filter + @statement_filter + @measure_filter + N')';
+DECLARE @batch_completed_options nvarchar(max) = @actions + N'
+    WHERE (' + @exclude_app + @filter + @batch_filter + @measure_filter + N')';
+-- Informational messages such as 'Changed database context' are not errors
+DECLARE @error_options nvarchar(max) = @actions + N'
+    WHERE (severity > 10 AND ' + @exclude_app + @filter + N')';
+
+-- Starting events have no duration or reads, so those filters would let every
+-- one through with no completion to follow.
+DECLARE @starting_events nvarchar(max) = N'';
+IF @P7 IS NULL AND @P8 IS NULL
+BEGIN
+    SET @starting_events = N'
+ADD EVENT sqlserver.rpc_starting(' + @rpc_options + N'),
+ADD EVENT sqlserver.sql_batch_starting(' + @batch_options + N'),';
+END
+
+-- Statement-level capture (opt-in)
+DECLARE @statement_events nvarchar(max) = N'';
+IF @P2 = 1
+BEGIN
+    SET @statement_events = N',
+ADD EVENT sqlserver.sp_statement_completed(' + @rpc_completed_options + N'),
+ADD EVENT sqlserver.sql_statement_completed(' + @rpc_completed_options + N')';
+END
+
+-- Deadlock and blocked process reports come from a system session, so they
+-- only get the sequence. Blocked process reports are not available database scoped.
+DECLARE @deadlock_event nvarchar(128) =
+    CASE WHEN @database_scope = 1 THEN N'sqlserver.database_xml_deadlock_report' ELSE N'sqlserver.xml_deadlock_report' END;
+DECLARE @blocked_process_event nvarchar(max) =
+    CASE WHEN @database_scope = 1 THEN N''
+    ELSE N',
+ADD EVENT sqlserver.blocked_process_report(ACTION(package0.event_sequence))' END;
+
+DECLARE @sql nvarchar(max) =
+    N'CREATE EVENT SESSION ' + QUOTENAME(@session_name) + N' ON ' + @scope + @starting_events + N'
+ADD EVENT sqlserver.rpc_completed(' + @rpc_completed_options + N'),
+ADD EVENT sqlserver.sql_batch_completed(' + @batch_completed_options + N'),
+ADD EVENT sqlserver.error_reported(' + @error_options + N'),
+ADD EVENT sqlserver.attention(' + @event_options + N'),
+ADD EVENT ' + @deadlock_event + N'(ACTION(package0.event_sequence))' + @blocked_process_event + @statement_events + N'
+ADD TARGET ' + @target + N'
+WITH (
+    MAX_MEMORY = 16 MB,
+    EVENT_RETENTION_MODE = ALLOW_SINGLE_EVENT_LOSS,
+    MAX_DISPATCH_LATENCY = 1 SECONDS
+);';
+EXEC sys.sp_executesql @sql;
+
+SET @sql = N'ALTER EVENT SESSION ' + QUOTENAME(@session_name) + N' ON ' + @scope + N' STATE = START;';
+EXEC sys.sp_executesql @sql;
+
+SELECT
+    @session_name AS session_name,
+    @database_scope AS database_scope,
+    @file_pattern AS file_pattern;
+";
+
+const XE_STOP: &str = "
+DECLARE @scope nvarchar(10) = CASE WHEN @P2 = 1 THEN N'DATABASE' ELSE N'SERVER' END;
+DECLARE @running_view nvarchar(128) =
+    CASE WHEN @P2 = 1 THEN N'sys.dm_xe_database_sessions' ELSE N'sys.dm_xe_sessions' END;
+
+DECLARE @sql nvarchar(max) = N'
+IF EXISTS (SELECT 1 FROM ' + @running_view + N' WHERE name = @name)
+BEGIN
+    ALTER EVENT SESSION ' + QUOTENAME(@P1) + N' ON ' + @scope + N' STATE = STOP;
+END';
+
+EXEC sys.sp_executesql @sql, N'@name sysname', @name = @P1;
+";
+
+/// Whether our session is still running after a reconnect; a server restart
+/// leaves it defined but stopped.
+const XE_IS_RUNNING: &str = "
+DECLARE @running_view nvarchar(128) =
+    CASE WHEN @P2 = 1 THEN N'sys.dm_xe_database_sessions' ELSE N'sys.dm_xe_sessions' END;
+DECLARE @running bit;
+
+DECLARE @sql nvarchar(max) = N'
+SET @running = CASE WHEN EXISTS (SELECT 1 FROM ' + @running_view + N' WHERE name = @name) THEN 1 ELSE 0 END';
+
+EXEC sys.sp_executesql @sql, N'@name sysname, @running bit OUTPUT', @name = @P1, @running = @running OUTPUT;
+SELECT @running AS running;
+";
+
+const XE_STOP_AND_DROP: &str = "
+DECLARE @scope nvarchar(10) = CASE WHEN @P2 = 1 THEN N'DATABASE' ELSE N'SERVER' END;
+DECLARE @sessions_view nvarchar(128) =
+    CASE WHEN @P2 = 1 THEN N'sys.database_event_sessions' ELSE N'sys.server_event_sessions' END;
+
+DECLARE @sql nvarchar(max) = N'
+IF EXISTS (SELECT 1 FROM ' + @sessions_view + N' WHERE name = @name)
+BEGIN
+    BEGIN TRY
+        ALTER EVENT SESSION ' + QUOTENAME(@P1) + N' ON ' + @scope + N' STATE = STOP;
+    END TRY
+    BEGIN CATCH
+    END CATCH;
+
+    DROP EVENT SESSION ' + QUOTENAME(@P1) + N' ON ' + @scope + N';
+END';
+
+EXEC sys.sp_executesql @sql, N'@name sysname', @name = @P1;
+";
+
+/// The columns `xe_row_to_event` reads, shredded from an `<event>` node `n`
+/// by `XE_EVENT_COLUMNS` and formatted by `XE_EVENT_OUTPUT` from the
+/// subquery `e` around it. Times are converted from UTC to server time
+/// (`@utc_offset_min`).
+const XE_EVENT_COLUMNS: &str = "
+        n.value('@name', 'nvarchar(60)') AS event_name,
+        n.value('@timestamp', 'datetime2(7)') AS event_time_utc,
+        ISNULL(n.value('(action[@name=\"event_sequence\"]/value)[1]', 'bigint'), 0) AS event_sequence,
+        ISNULL(n.value('(data[@name=\"duration\"]/value)[1]', 'bigint'), 0) AS duration_us,
+        n.exist('data[@name=\"duration\"]') AS has_duration,
+        ISNULL(n.value('(data[@name=\"cpu_time\"]/value)[1]', 'bigint'), 0) AS cpu_us,
+        ISNULL(n.value('(data[@name=\"logical_reads\"]/value)[1]', 'bigint'), 0) AS reads,
+        n.value('(data[@name=\"physical_reads\"]/value)[1]', 'bigint') AS physical_reads,
+        ISNULL(n.value('(data[@name=\"writes\"]/value)[1]', 'bigint'), 0) AS writes,
+        ISNULL(n.value('(data[@name=\"row_count\"]/value)[1]', 'bigint'), 0) AS row_count,
+        ISNULL(
+            COALESCE(
+                n.value('(data[@name=\"statement\"]/value)[1]', 'nvarchar(max)'),
+                n.value('(data[@name=\"batch_text\"]/value)[1]', 'nvarchar(max)'),
+                n.value('(data[@name=\"message\"]/value)[1]', 'nvarchar(max)'),
+                NULLIF(CAST(n.query('(data[@name=\"xml_report\"]/value/deadlock)[1]') AS nvarchar(max)), N''),
+                NULLIF(
+                    CAST(n.query('(data[@name=\"blocked_process\"]/value/blocked-process-report)[1]') AS nvarchar(max)),
+                    N''
+                )
+            ),
+            N''
+        ) AS text_data,
+        ISNULL(n.value('(action[@name=\"database_name\"]/value)[1]', 'nvarchar(128)'), N'') AS database_name,
+        ISNULL(n.value('(action[@name=\"server_principal_name\"]/value)[1]', 'nvarchar(128)'), N'') AS login_name,
+        ISNULL(n.value('(action[@name=\"client_hostname\"]/value)[1]', 'nvarchar(128)'), N'') AS host_name,
+        ISNULL(n.value('(action[@name=\"client_app_name\"]/value)[1]', 'nvarchar(128)'), N'') AS program_name,
+        ISNULL(n.value('(action[@name=\"session_id\"]/value)[1]', 'int'), 0) AS session_id,
+        ISNULL(n.value('(data[@name=\"object_name\"]/value)[1]', 'nvarchar(128)'), N'') AS object_name,
+        ISNULL(n.value('(action[@name=\"client_pid\"]/value)[1]', 'int'), 0) AS client_process_id,
+        ISNULL(n.value('(action[@name=\"nt_username\"]/value)[1]', 'nvarchar(128)'), N'') AS nt_user_name,
+        ISNULL(n.value('(action[@name=\"transaction_id\"]/value)[1]', 'bigint'), 0) AS transaction_id,
+        -- error_reported carries the error number; completions carry their result (1 = error, 2 = abort)
+        ISNULL(
+            COALESCE(
+                n.value('(data[@name=\"error_number\"]/value)[1]', 'int'),
+                n.value('(data[@name=\"result\"]/value)[1]', 'int')
+            ),
+            0
+        ) AS error_number,
+        ISNULL(n.value('(data[@name=\"severity\"]/value)[1]', 'int'), 0) AS severity
+";
+
+const XE_EVENT_OUTPUT: &str = "
+    e.event_name,
+    CONVERT(
+        varchar(27),
+        DATEADD(millisecond, -(e.duration_us / 1000), DATEADD(minute, @utc_offset_min, e.event_time_utc)),
+        126
+    ) AS start_time,
+    -- Only events with a duration end at their timestamp
+    CASE
+        WHEN e.has_duration = 1
+        THEN CONVERT(varchar(27), DATEADD(minute, @utc_offset_min, e.event_time_utc), 126)
+        ELSE ''
+    END AS end_time,
+    e.event_sequence,
+    e.duration_us,
+    e.cpu_us,
+    e.reads,
+    e.physical_reads,
+    e.writes,
+    e.row_count,
+    e.text_data,
+    e.database_name,
+    e.login_name,
+    e.host_name,
+    e.program_name,
+    e.session_id,
+    e.object_name,
+    e.client_process_id,
+    e.nt_user_name,
+    e.transaction_id,
+    e.error_number,
+    e.severity
+";
+
+/// Shreds the collected event XML into the same column shape as the SQL
+/// Trace poll. `{event_source}` is replaced with one of the `XE_SOURCE_*`
+/// snippets, which fill `@events` from the session target.
+const XE_POLL_EVENTS: &str = "
+DECLARE @events TABLE(position int IDENTITY PRIMARY KEY, event_xml xml, file_name nvarchar(260), file_offset bigint);
+DECLARE @utc_offset_min int = DATEDIFF(minute, SYSUTCDATETIME(), SYSDATETIME());
+
+{event_source}
+
+SELECT TOP (@P4)
+    w.watermark_time,{event_output}FROM (
+    SELECT{event_columns}    FROM @events ev
+    CROSS APPLY ev.event_xml.nodes('/event') AS q(n)
+) e
+CROSS APPLY (SELECT CONVERT(varchar(27), e.event_time_utc, 126) AS watermark_time) w
+WHERE e.event_name IN (
+        N'rpc_starting', N'rpc_completed', N'sql_batch_starting', N'sql_batch_completed',
+        N'sp_statement_completed', N'sql_statement_completed', N'error_reported', N'attention',
+        N'xml_deadlock_report', N'database_xml_deadlock_report', N'blocked_process_report'
+    )
+  AND e.program_name NOT LIKE N'%SimpleSQLProfiler%'
+  AND (
+      w.watermark_time > @P2
+      OR (w.watermark_time = @P2 AND e.event_sequence > @P3)
+  )
+ORDER BY
+    w.watermark_time ASC,
+    e.event_sequence ASC;
+";
+
+const XE_SOURCE_RING_BUFFER: &str = "
+INSERT INTO @events(event_xml)
+SELECT q.n.query('.')
+FROM (
+    SELECT CAST(t.target_data AS xml) AS target_xml
+    FROM sys.dm_xe_sessions s
+    JOIN sys.dm_xe_session_targets t ON t.event_session_address = s.address
+    WHERE s.name = @P1
+      AND t.target_name = N'ring_buffer'
+) rb
+CROSS APPLY rb.target_xml.nodes('/RingBufferTarget/event') AS q(n);
+";
+
+const XE_SOURCE_DATABASE_RING_BUFFER: &str = "
+INSERT INTO @events(event_xml)
+SELECT q.n.query('.')
+FROM (
+    SELECT CAST(t.target_data AS xml) AS target_xml
+    FROM sys.dm_xe_database_sessions s
+    JOIN sys.dm_xe_database_session_targets t ON t.event_session_address = s.address
+    WHERE s.name = @P1
+      AND t.target_name = N'ring_buffer'
+) rb
+CROSS APPLY rb.target_xml.nodes('/RingBufferTarget/event') AS q(n);
+";
+
+/// Reads the buffers written after the one at `@P5`/`@P6` (every file when
+/// NULL) and reports the last buffer read, where the next poll resumes.
+const XE_SOURCE_EVENT_FILE: &str = "
+INSERT INTO @events(event_xml, file_name, file_offset)
+SELECT CAST(f.event_data AS xml), f.file_name, f.file_offset
+FROM sys.fn_xe_file_target_read_file(@P1, NULL, @P5, @P6) AS f;
+
+SELECT TOP (1) file_name, file_offset
+FROM @events
+ORDER BY position DESC;
+";
+
+/// Where a read of an `event_file` target stopped: the file and the offset
+/// of its last buffer. `fn_xe_file_target_read_file` resumes after it.
+pub(crate) type BufferPosition = (String, i64);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XeTarget {
+    RingBuffer,
+    EventFile,
+}
+
+impl XeTarget {
+    fn as_sql_name(self) -> &'static str {
+        match self {
+            XeTarget::RingBuffer => "ring_buffer",
+            XeTarget::EventFile => "event_file",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ActiveXeSession {
+    session_name: String,
+    database_scope: bool,
+    target: XeTarget,
+    file_pattern: Option<String>,
+}
+
+/// Captures through an Extended Events session. On Azure SQL Database the
+/// session is database scoped; everywhere else it is server scoped.
+pub struct XeSource {
+    config: ConnectionConfig,
+    target: XeTarget,
+    options: CaptureOptions,
+    client: Option<SqlClient>,
+    session: Option<ActiveXeSession>,
+    watermark: Watermark,
+    /// The last `event_file` buffer read by a poll that kept up.
+    resume: Option<BufferPosition>,
+    behind: bool,
+}
+
+impl XeSource {
+    pub fn new(config: ConnectionConfig, target: XeTarget, options: CaptureOptions) -> Self {
+        Self {
+            config,
+            target,
+            options,
+            client: None,
+            session: None,
+            watermark: Watermark::default(),
+            resume: None,
+            behind: false,
+        }
+    }
+}
+
+#[async_trait]
+impl CaptureSource for XeSource {
+    async fn start(&mut self) -> Result<(), String> {
+        let mut client = db::connect(&self.config)
+            .await
+            .map_err(|e| format!("Failed to start polling stream: {e}"))?;
+        let session = start_session(&mut client, self.target, &self.options).await?;
+        self.client = Some(client);
+        self.session = Some(session);
+        Ok(())
+    }
+
+    async fn poll_batch(&mut self) -> Result<Option<Vec<QueryEvent>>, String> {
+        let (Some(client), Some(session)) = (self.client.as_mut(), self.session.as_ref()) else {
+            return Err("Extended Events session is not running".into());
+        };
+
+        let (events, last_read) = poll_session_events(
+            client,
+            session,
+            &self.watermark.last_timestamp,
+            self.watermark.last_event_sequence,
+            self.resume.as_ref(),
+        )
+        .await?;
+
+        self.behind = events.len() >= capture::POLL_ROW_LIMIT as usize;
+        // A poll cut short by the row limit may have left events in the
+        // buffers it read, so only a complete one moves the resume point.
+        // Re-read events are dropped by the watermark.
+        if !self.behind && last_read.is_some() {
+            self.resume = last_read;
+        }
+        Ok(Some(self.watermark.admit_all(events)))
+    }
+
+    async fn stop(&mut self) -> Result<(), String> {
+        let (Some(client), Some(session)) = (self.client.as_mut(), self.session.as_ref()) else {
+            return Ok(());
+        };
+        stop_session(client, session).await
+    }
+
+    async fn reconnect(&mut self) -> Result<(), String> {
+        let Some(session) = self.session.as_ref() else {
+            return Err("Extended Events session is not running".into());
+        };
+        self.client = None;
+
+        let mut client = db::connect(&self.config).await?;
+        if !session_is_running(&mut client, session).await? {
+            return Err("The Extended Events session is no longer running on the server (was it restarted?)".into());
+        }
+        self.client = Some(client);
+        Ok(())
+    }
+
+    async fn cleanup(&mut self) -> Result<(), String> {
+        let client = self.client.take();
+        let Some(session) = self.session.take() else {
+            return Ok(());
+        };
+
+        if let Some(mut client) = client {
+            if stop_and_drop_session(&mut client, &session.session_name, session.database_scope).await.is_ok() {
+                return Ok(());
+            }
+        }
+
+        // The polling connection may be what failed; retry on a fresh one so
+        // the session is not left running on the server.
+        let mut client = db::connect(&self.config).await?;
+        stop_and_drop_session(&mut client, &session.session_name, session.database_scope).await
+    }
+
+    fn behind(&self) -> bool {
+        self.behind
+    }
+}
+
+async fn start_session(
+    client: &mut SqlClient,
+    target: XeTarget,
+    options: &CaptureOptions,
+) -> Result<ActiveXeSession, String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(XE_CREATE_AND_START);
+    query.bind(target.as_sql_name());
+    query.bind(options.statement_level);
+    options.filter.bind_to(&mut query);
+    options.files.bind_to(&mut query);
+
+    let rows = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to create/start Extended Events session: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to read Extended Events session creation result: {e}"))?;
+
+    for result_set in rows {
+        for row in result_set {
+            let session_name = row.get::<&str, _>("session_name");
+            let database_scope = row.get::<bool, _>("database_scope").unwrap_or(false);
+            let file_pattern = row.get::<&str, _>("file_pattern").map(str::to_string);
+            if let Some(name) = session_name {
+                if name.is_empty() {
+                    continue;
+                }
+                if target == XeTarget::EventFile && file_pattern.is_none() {
+                    continue;
+                }
+                return Ok(ActiveXeSession {
+                    session_name: name.to_string(),
+                    database_scope,
+                    target,
+                    file_pattern,
+                });
+            }
+        }
+    }
+
+    Err("Extended Events session creation returned invalid session metadata".into())
+}
+
+async fn stop_session(client: &mut SqlClient, session: &ActiveXeSession) -> Result<(), String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(XE_STOP);
+    query.bind(session.session_name.as_str());
+    query.bind(session.database_scope);
+
+    query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to stop Extended Events session: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to confirm Extended Events session stop: {e}"))?;
+
+    Ok(())
+}
+
+async fn session_is_running(client: &mut SqlClient, session: &ActiveXeSession) -> Result<bool, String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(XE_IS_RUNNING);
+    query.bind(session.session_name.as_str());
+    query.bind(session.database_scope);
+
+    let row = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to check Extended Events session status: {e}"))?
+        .into_row()
+        .await
+        .map_err(|e| format!("Failed to read Extended Events session status: {e}"))?;
+
+    Ok(row.and_then(|r| r.get::<bool, _>("running")).unwrap_or(false))
+}
+
+pub(crate) async fn stop_and_drop_session(
+    client: &mut SqlClient,
+    session_name: &str,
+    database_scope: bool,
+) -> Result<(), String> {
+    use tiberius::Query;
+
+    let mut query = Query::new(XE_STOP_AND_DROP);
+    query.bind(session_name);
+    query.bind(database_scope);
+
+    query
+        .query(client)
+        .await
+        .map_err(|e| format!("Failed to stop/drop Extended Events session: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to confirm Extended Events session stop/drop: {e}"))?;
+
+    Ok(())
+}
+
+async fn poll_session_events(
+    client: &mut SqlClient,
+    session: &ActiveXeSession,
+    last_timestamp: &str,
+    last_event_sequence: i64,
+    resume: Option<&BufferPosition>,
+) -> Result<(Vec<PolledEvent>, Option<BufferPosition>), String> {
+    use tiberius::Query;
+
+    let (event_source, source_param) = match session.target {
+        XeTarget::RingBuffer if session.database_scope => {
+            (XE_SOURCE_DATABASE_RING_BUFFER, session.session_name.as_str())
+        }
+        XeTarget::RingBuffer => (XE_SOURCE_RING_BUFFER, session.session_name.as_str()),
+        XeTarget::EventFile => (
+            XE_SOURCE_EVENT_FILE,
+            session.file_pattern.as_deref().unwrap_or_default(),
+        ),
+    };
+
+    let sql = with_event_columns(XE_POLL_EVENTS).replace("{event_source}", event_source);
+    let mut query = Query::new(sql);
+    query.bind(source_param);
+    query.bind(last_timestamp);
+    query.bind(last_event_sequence);
+    query.bind(capture::POLL_ROW_LIMIT);
+    query.bind(resume.map(|(file, _)| file.as_str()));
+    query.bind(resume.map(|(_, offset)| *offset));
+
+    let result_sets = query
+        .query(client)
+        .await
+        .map_err(|e| format!("Extended Events poll query failed: {e}"))?
+        .into_results()
+        .await
+        .map_err(|e| format!("Failed to read Extended Events poll results: {e}"))?;
+
+    // Result sets are told apart by their columns: the event file snippet
+    // adds one with the last buffer read.
+    let mut events = Vec::new();
+    let mut last_read = None;
+    for row in result_sets.iter().flatten() {
+        if row.columns().iter().any(|column| column.name() == "file_offset") {
+            last_read = row
+                .get::<&str, _>("file_name")
+                .map(|file_name| (file_name.to_string(), row.get::<i64, _>("file_offset").unwrap_or(0)));
+        } else if let Some(event) = xe_row_to_event(row) {
+            events.push(PolledEvent {
+                event,
+                watermark_time: row.get::<&str, _>("watermark_time").unwrap_or("").to_string(),
+            });
+        }
+    }
+    Ok((events, last_read))
+}
+
+/// `template` with `{event_columns}` and `{event_output}` filled in.
+pub(crate) fn with_event_columns(template: &str) -> String {
+    template
+        .replace("{event_columns}", XE_EVENT_COLUMNS)
+        .replace("{event_output}", XE_EVENT_OUTPUT)
+}
+
+/// Maps a row of `XE_EVENT_OUTPUT`; `None` for events the profiler doesn't
+/// show.
+pub(crate) fn xe_row_to_event(row: &tiberius::Row) -> Option<QueryEvent> {
+    let mut event_name: String = row.get::<&str, _>("event_name").unwrap_or("").to_string();
+    if event_name.ends_with("xml_deadlock_report") {
+        event_name = "deadlock_graph".into();
+    }
+    if !matches!(
+        event_name.as_str(),
+        "rpc_starting"
+            | "rpc_completed"
+            | "sql_batch_starting"
+            | "sql_batch_completed"
+            | "sp_statement_completed"
+            | "sql_statement_completed"
+            | "error_reported"
+            | "attention"
+            | "deadlock_graph"
+            | "blocked_process_report"
+    ) {
+        return None;
+    }
+
+    let start_time: String = row.get::<&str, _>("start_time").unwrap_or("").to_string();
+    let event_sequence: i64 = row.get::<i64, _>("event_sequence").unwrap_or(0);
+
+    let duration_us: i64 = row.get::<i64, _>("duration_us").unwrap_or(0);
+    let cpu_us: i64 = row.get::<i64, _>("cpu_us").unwrap_or(0);
+
+    let text_data: String = row.get::<&str, _>("text_data").unwrap_or("").to_string();
+    let error_number: i32 = row.get::<i32, _>("error_number").unwrap_or(0);
+    let (error, event_status) = match event_name.as_str() {
+        "rpc_completed" | "sql_batch_completed" => (None, capture::completion_status(error_number as i64)),
+        "error_reported" => (
+            Some(QueryError {
+                number: error_number,
+                severity: row.get::<i32, _>("severity").unwrap_or(0),
+                message: text_data.clone(),
+            }),
+            String::new(),
+        ),
+        _ => (None, String::new()),
+    };
+
+    let (sql_text, current_statement) = match event_name.as_str() {
+        "sql_batch_starting"
+        | "sql_batch_completed"
+        | "error_reported"
+        | "attention"
+        | "deadlock_graph"
+        | "blocked_process_report" => (text_data, String::new()),
+        _ => (text_data.clone(), text_data),
+    };
+
+    Some(QueryEvent {
+        id: String::new(),
+        session_id: row.get::<i32, _>("session_id").unwrap_or(0),
+        start_time,
+        end_time: row.get::<&str, _>("end_time").unwrap_or("").to_string(),
+        event_name,
+        database_name: row.get::<&str, _>("database_name").unwrap_or("").to_string(),
+        cpu_time: cpu_us,
+        elapsed_time: duration_us,
+        cpu_time_ms: micros_to_ms(cpu_us),
+        elapsed_time_ms: micros_to_ms(duration_us),
+        physical_reads: row.get::<i64, _>("physical_reads"),
+        writes: row.get::<i64, _>("writes").unwrap_or(0),
+        logical_reads: row.get::<i64, _>("reads").unwrap_or(0),
+        row_count: row.get::<i64, _>("row_count").unwrap_or(0),
+        sql_text,
+        current_statement,
+        fingerprint: String::new(),
+        query_hash: String::new(),
+        login_name: row.get::<&str, _>("login_name").unwrap_or("").to_string(),
+        host_name: row.get::<&str, _>("host_name").unwrap_or("").to_string(),
+        program_name: row.get::<&str, _>("program_name").unwrap_or("").to_string(),
+        object_name: row.get::<&str, _>("object_name").unwrap_or("").to_string(),
+        client_process_id: row.get::<i32, _>("client_process_id").unwrap_or(0),
+        nt_user_name: row.get::<&str, _>("nt_user_name").unwrap_or("").to_string(),
+        transaction_id: row.get::<i64, _>("transaction_id").unwrap_or(0),
+        captured_at: String::new(),
+        event_status,
+        event_sequence,
+        parent_id: None,
+        error,
+        extra: serde_json::Map::new(),
+    })
+}
@@ -1,16 +1,62 @@
-mod db;
-mod profiler;
 mod settings;
 
-use db::ConnectionConfig;
-use profiler::{ProfilerCommand, QueryResultData, spawn_profiler_task};
-use tauri::Manager;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use sqlprof_core::blocking::BlockedProcessReport;
+use sqlprof_core::capture::{CaptureFilter, CaptureOptions, CaptureSourceKind};
+use sqlprof_core::db::ConnectionConfig;
+use sqlprof_core::deadlock::DeadlockGraph;
+use sqlprof_core::export::{self, ExportFormat};
+use sqlprof_core::import;
+use sqlprof_core::live::LiveBatch;
+use sqlprof_core::orphans::OrphanReport;
+use sqlprof_core::profiler::{self, EventSink, ProfilerCommand, ProfilerStatus, QueryResultData};
+use sqlprof_core::sessions::{EventWindow, SessionData, SessionInfo, SessionStore};
+use sqlprof_core::stats::{QueryAggregate, QueryStatsOrder, QueryStatsSnapshot};
+use tauri::{Emitter, Manager};
 use tokio::sync::{mpsc, oneshot};
 
+const SESSIONS_FILE: &str = "sessions.db";
+
 struct AppState {
     tx: mpsc::Sender<ProfilerCommand>,
 }
 
+/// Forwards what the profiler captures to the webview.
+struct WebviewSink(tauri::AppHandle);
+
+impl EventSink for WebviewSink {
+    fn status(&self, status: &ProfilerStatus) {
+        let _ = self.0.emit("profiler-status", status);
+    }
+
+    fn query_events(&self, batch: &LiveBatch) {
+        let _ = self.0.emit("query-events", batch);
+    }
+
+    fn deadlock(&self, graph: &DeadlockGraph) {
+        let _ = self.0.emit("deadlock", graph);
+    }
+
+    fn blocked_process(&self, report: &BlockedProcessReport) {
+        let _ = self.0.emit("blocked-process", report);
+    }
+
+    fn query_stats(&self, snapshot: &QueryStatsSnapshot) {
+        let _ = self.0.emit("query-stats", snapshot);
+    }
+}
+
+fn sessions_path(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve data dir: {e}"))?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create data dir: {e}"))?;
+    Ok(dir.join(SESSIONS_FILE))
+}
+
 #[tauri::command]
 async fn connect_to_server(
     app: tauri::AppHandle,
@@ -65,11 +111,17 @@ async fn disconnect_from_server(
 #[tauri::command]
 async fn start_capture(
     state: tauri::State<'_, AppState>,
+    source: Option<CaptureSourceKind>,
+    options: Option<CaptureOptions>,
 ) -> Result<(), String> {
     let (reply_tx, reply_rx) = oneshot::channel();
     state
         .tx
-        .send(ProfilerCommand::StartCapture { reply: reply_tx })
+        .send(ProfilerCommand::StartCapture {
+            source: source.unwrap_or_default(),
+            options: options.unwrap_or_default(),
+            reply: reply_tx,
+        })
         .await
         .map_err(|e| format!("Internal error: {e}"))?;
 
@@ -114,6 +166,282 @@ async fn execute_query(
         .map_err(|e| format!("Internal error: {e}"))?
 }
 
+#[tauri::command]
+async fn get_blocked_process_threshold(
+    state: tauri::State<'_, AppState>,
+) -> Result<i32, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::GetBlockedProcessThreshold { reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn set_blocked_process_threshold(
+    state: tauri::State<'_, AppState>,
+    seconds: i32,
+) -> Result<(), String> {
+    if !(0..=86400).contains(&seconds) {
+        return Err("The blocked process threshold must be between 0 and 86400 seconds".into());
+    }
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::SetBlockedProcessThreshold {
+            seconds,
+            reply: reply_tx,
+        })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn find_orphaned_captures(
+    state: tauri::State<'_, AppState>,
+) -> Result<OrphanReport, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::FindOrphanedCaptures { reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn clean_up_orphaned_captures(
+    state: tauri::State<'_, AppState>,
+    trace_ids: Vec<i32>,
+    xe_sessions: Vec<String>,
+) -> Result<OrphanReport, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::CleanUpOrphanedCaptures {
+            trace_ids,
+            xe_sessions,
+            reply: reply_tx,
+        })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn get_query_stats(
+    state: tauri::State<'_, AppState>,
+    order: Option<QueryStatsOrder>,
+    limit: Option<usize>,
+) -> Result<Vec<QueryAggregate>, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::GetQueryStats {
+            order: order.unwrap_or_default(),
+            limit: limit.unwrap_or(100),
+            reply: reply_tx,
+        })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))
+}
+
+#[tauri::command]
+async fn clear_query_stats(
+    state: tauri::State<'_, AppState>,
+) -> Result<(), String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::ClearQueryStats { reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))
+}
+
+#[tauri::command]
+async fn list_sessions(
+    state: tauri::State<'_, AppState>,
+) -> Result<Vec<SessionInfo>, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::ListSessions { reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn open_session(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    limit: u64,
+) -> Result<SessionData, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::OpenSession { id, limit, reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn rename_session(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    name: String,
+) -> Result<(), String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Session name cannot be empty".into());
+    }
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::RenameSession {
+            id,
+            name,
+            reply: reply_tx,
+        })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn delete_session(
+    state: tauri::State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::DeleteSession { id, reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn set_live_event_limit(
+    state: tauri::State<'_, AppState>,
+    limit: usize,
+) -> Result<(), String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::SetLiveEventLimit { limit, reply: reply_tx })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))
+}
+
+#[tauri::command]
+async fn fetch_session_events(
+    state: tauri::State<'_, AppState>,
+    id: String,
+    before: Option<String>,
+    after: Option<String>,
+    limit: u64,
+) -> Result<EventWindow, String> {
+    let (reply_tx, reply_rx) = oneshot::channel();
+    state
+        .tx
+        .send(ProfilerCommand::FetchSessionEvents {
+            id,
+            before,
+            after,
+            limit,
+            reply: reply_tx,
+        })
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?;
+
+    reply_rx
+        .await
+        .map_err(|e| format!("Internal error: {e}"))?
+}
+
+/// Runs on a blocking thread with its own handle on the store: large
+/// sessions take a while and must not hold up the capture.
+#[tauri::command]
+async fn export_session(
+    app: tauri::AppHandle,
+    id: String,
+    path: String,
+    format: ExportFormat,
+    filter: Option<CaptureFilter>,
+    event_ids: Option<Vec<String>>,
+) -> Result<u64, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let store = SessionStore::open_handle(&sessions_path(&app)?)?;
+        export::export_session(
+            &store,
+            &id,
+            std::path::Path::new(&path),
+            format,
+            &filter.unwrap_or_default(),
+            event_ids.as_deref(),
+        )
+    })
+    .await
+    .map_err(|e| format!("Internal error: {e}"))?
+}
+
+#[tauri::command]
+async fn import_session(
+    app: tauri::AppHandle,
+    path: String,
+) -> Result<SessionInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut store = SessionStore::open_handle(&sessions_path(&app)?)?;
+        import::import_session(&mut store, std::path::Path::new(&path))
+    })
+    .await
+    .map_err(|e| format!("Internal error: {e}"))?
+}
+
 #[tauri::command]
 async fn load_connection(
     app: tauri::AppHandle,
@@ -131,7 +459,9 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .setup(|app| {
-            let tx = spawn_profiler_task(app.handle().clone());
+            let (tx, rx) = mpsc::channel(32);
+            let sink = Arc::new(WebviewSink(app.handle().clone()));
+            tauri::async_runtime::spawn(profiler::profiler_loop(rx, sink, sessions_path(app.handle())?));
             app.manage(AppState { tx });
             Ok(())
         })
@@ -141,6 +471,20 @@ pub fn run() {
             start_capture,
             stop_capture,
             execute_query,
+            get_blocked_process_threshold,
+            set_blocked_process_threshold,
+            find_orphaned_captures,
+            clean_up_orphaned_captures,
+            get_query_stats,
+            clear_query_stats,
+            list_sessions,
+            open_session,
+            rename_session,
+            delete_session,
+            set_live_event_limit,
+            fetch_session_events,
+            export_session,
+            import_session,
             load_connection,
         ])
         .run(tauri::generate_context!())
@@ -7,15 +7,43 @@ import { Show, createEffect, createMemo, createSignal, onCleanup, onMount } from
 import { createStore, produce } from "solid-js/store";
 import AboutDialog from "./components/AboutDialog.tsx";
 import AdvancedFilterDialog from "./components/AdvancedFilterDialog.tsx";
+import CaptureFilesDialog from "./components/CaptureFilesDialog.tsx";
+import CaptureFilterDialog from "./components/CaptureFilterDialog.tsx";
 import ConnectionForm from "./components/ConnectionForm.tsx";
 import ContextMenu from "./components/ContextMenu.tsx";
+import BlockingDialog from "./components/BlockingDialog.tsx";
+import DeadlockDialog from "./components/DeadlockDialog.tsx";
+import HistoryBar from "./components/HistoryBar.tsx";
+import OpenFileDialog from "./components/OpenFileDialog.tsx";
+import OrphanedCapturesDialog from "./components/OrphanedCapturesDialog.tsx";
 import QueryDetail from "./components/QueryDetail.tsx";
 import QueryFeed from "./components/QueryFeed.tsx";
+import SessionsDialog from "./components/SessionsDialog.tsx";
 import TitleBar from "./components/TitleBar.tsx";
 import Toolbar from "./components/Toolbar.tsx";
+import TopQueriesDialog from "./components/TopQueriesDialog.tsx";
 import UpdateDialog from "./components/UpdateDialog.tsx";
-import { evaluateFilter, type AdvancedFilterCondition } from "./lib/advancedFilters.ts";
-import type { ConnectionConfig, ProfilerStatus, QueryEvent } from "./lib/types.ts";
+import { createFilterCondition, evaluateFilter, migrateStoredFilters, type AdvancedFilterCondition } from "./lib/advancedFilters.ts";
+import { buildBlockingChains, countBlockedSessions } from "./lib/blocking.ts";
+import {
+  DEFAULT_CAPTURE_FILE_OPTIONS,
+  EMPTY_CAPTURE_FILTER,
+  type BlockedProcessReport,
+  type CaptureFileOptions,
+  type CaptureFilter,
+  type CaptureOptions,
+  type CaptureSourceKind,
+  type ConnectionConfig,
+  type DeadlockGraph,
+  type EventWindow,
+  type LiveBatch,
+  type LiveCaptureKind,
+  type OrphanReport,
+  type ProfilerStatus,
+  type QueryEvent,
+  type QueryStatsSnapshot,
+  type SessionData,
+} from "./lib/types.ts";
 
 type UpdateMessageTone = "info" | "success" | "error";
 
@@ -31,7 +59,9 @@ interface UpdaterErrorDetails {
   tone: UpdateMessageTone;
 }
 
-const MAX_QUERY_BUFFER = 5000;
+const DEFAULT_LIVE_EVENT_LIMIT = 5000;
+const MAX_DEADLOCKS = 200;
+const MAX_BLOCKED_REPORTS = 500;
 
 const MISSING_UPDATER_CONFIG_MESSAGE =
   "Updater is not configured yet. Set plugins.updater.endpoints and plugins.updater.pubkey in src-tauri/tauri.conf.json.";
@@ -45,12 +75,15 @@ export default function App() {
     connected: false,
     capturing: false,
     error: null,
+    reconnect_attempt: null,
+    behind: false,
   });
   const [queries, setQueries] = createStore<QueryEvent[]>([]);
   const [selectedId, setSelectedId] = createSignal<string | null>(null);
   const [filterText, setFilterText] = createSignal("");
   const [showConnection, setShowConnection] = createSignal(true);
   const [showAbout, setShowAbout] = createSignal(false);
+  const [orphanReport, setOrphanReport] = createSignal<OrphanReport | null>(null);
   const [appVersion, setAppVersion] = createSignal<string | null>(null);
   const [autoScroll, setAutoScroll] = createSignal<"on" | "off" | "smart">(
     (() => {
@@ -60,6 +93,39 @@ export default function App() {
       return "smart";
     })()
   );
+  const [captureSource, setCaptureSource] = createSignal<LiveCaptureKind>(
+    (() => {
+      const val = localStorage.getItem("capture-source");
+      if (val === "sql_trace" || val === "xe_ring_buffer" || val === "xe_event_file") return val;
+      return "sql_trace";
+    })()
+  );
+  const [statementLevel, setStatementLevel] = createSignal(localStorage.getItem("statement-level") === "true");
+  const [captureFilter, setCaptureFilter] = createSignal<CaptureFilter>(
+    (() => {
+      try {
+        const stored = localStorage.getItem("capture-filter");
+        return stored ? { ...EMPTY_CAPTURE_FILTER, ...JSON.parse(stored) } : EMPTY_CAPTURE_FILTER;
+      } catch {
+        return EMPTY_CAPTURE_FILTER;
+      }
+    })()
+  );
+  const [showCaptureFilter, setShowCaptureFilter] = createSignal(false);
+  const [captureFiles, setCaptureFiles] = createSignal<CaptureFileOptions>(
+    (() => {
+      try {
+        const stored = localStorage.getItem("capture-files");
+        return stored ? { ...DEFAULT_CAPTURE_FILE_OPTIONS, ...JSON.parse(stored) } : DEFAULT_CAPTURE_FILE_OPTIONS;
+      } catch {
+        return DEFAULT_CAPTURE_FILE_OPTIONS;
+      }
+    })()
+  );
+  // Not persisted: a stop time from an earlier run has usually passed.
+  const [captureStopAt, setCaptureStopAt] = createSignal<string | null>(null);
+  const [showCaptureFiles, setShowCaptureFiles] = createSignal(false);
+  const [expandedParents, setExpandedParents] = createSignal<Set<string>>(new Set());
   const [deduplicateRepeats, setDeduplicateRepeats] = createSignal(localStorage.getItem("deduplicate-repeats") !== "false");
   const [updateStatus, setUpdateStatus] = createSignal<UpdateStatus>({
     checking: false,
@@ -71,33 +137,95 @@ export default function App() {
     (() => {
       try {
         const stored = localStorage.getItem("advanced-filters");
-        return stored ? JSON.parse(stored) : [];
+        if (!stored) return [];
+        // Filters saved before "advanced-filters-version" 2 used ms under the µs keys.
+        const filters: AdvancedFilterCondition[] = JSON.parse(stored);
+        return localStorage.getItem("advanced-filters-version") === "2" ? filters : migrateStoredFilters(filters);
       } catch {
         return [];
       }
     })()
   );
   const [showAdvancedFilter, setShowAdvancedFilter] = createSignal(false);
+  const [showOpenFile, setShowOpenFile] = createSignal(false);
+  const [deadlocks, setDeadlocks] = createSignal<DeadlockGraph[]>([]);
+  const [showDeadlocks, setShowDeadlocks] = createSignal(false);
+  const [blockedReports, setBlockedReports] = createSignal<BlockedProcessReport[]>([]);
+  const [blockingThreshold, setBlockingThreshold] = createSignal<number | null>(null);
+  const [blockingThresholdError, setBlockingThresholdError] = createSignal<string | null>(null);
+  const [showBlocking, setShowBlocking] = createSignal(false);
+  const [queryStats, setQueryStats] = createSignal<QueryStatsSnapshot | null>(null);
+  const [showTopQueries, setShowTopQueries] = createSignal(false);
+  const [showSessions, setShowSessions] = createSignal(false);
+  const [openedSessionId, setOpenedSessionId] = createSignal<string | null>(null);
+  const [liveEventLimit, setLiveEventLimit] = createSignal(
+    Number(localStorage.getItem("live-event-limit")) || DEFAULT_LIVE_EVENT_LIMIT,
+  );
+  // Events of the session before the ones in the feed, and which session
+  // to page through them in.
+  const [olderEventCount, setOlderEventCount] = createSignal(0);
+  const [feedSessionId, setFeedSessionId] = createSignal<string | null>(null);
+  const [historyWindow, setHistoryWindow] = createSignal<EventWindow | null>(null);
+  const [historyLoading, setHistoryLoading] = createSignal(false);
+  const [historyError, setHistoryError] = createSignal<string | null>(null);
 
   createEffect(() => {
     localStorage.setItem("advanced-filters", JSON.stringify(advancedFilters()));
+    localStorage.setItem("advanced-filters-version", "2");
   });
 
   createEffect(() => {
     localStorage.setItem("auto-scroll", String(autoScroll()));
   });
 
+  createEffect(() => {
+    localStorage.setItem("capture-source", captureSource());
+  });
+
+  createEffect(() => {
+    localStorage.setItem("statement-level", String(statementLevel()));
+  });
+
+  createEffect(() => {
+    localStorage.setItem("capture-filter", JSON.stringify(captureFilter()));
+  });
+
+  createEffect(() => {
+    localStorage.setItem("capture-files", JSON.stringify(captureFiles()));
+  });
+
+  const captureFilterCount = () => Object.values(captureFilter()).filter((v) => v !== null).length;
+  const captureFilesCustomized = () => {
+    const files = captureFiles();
+    return (
+      files.directory !== null ||
+      files.max_file_mb !== null ||
+      files.rollover !== DEFAULT_CAPTURE_FILE_OPTIONS.rollover ||
+      files.max_files !== DEFAULT_CAPTURE_FILE_OPTIONS.max_files ||
+      captureStopAt() !== null
+    );
+  };
+
   createEffect(() => {
     localStorage.setItem("deduplicate-repeats", String(deduplicateRepeats()));
   });
 
-  const selectedQuery = () => queries.find((q) => q.id === selectedId()) ?? null;
+  createEffect(() => {
+    const limit = liveEventLimit();
+    localStorage.setItem("live-event-limit", String(limit));
+    invoke("set_live_event_limit", { limit }).catch((e) => console.error("Failed to set live event limit:", e));
+  });
+
+  // While paging through history the feed shows that window instead.
+  const shownQueries = () => historyWindow()?.events ?? queries;
+
+  const selectedQuery = () => shownQueries().find((q) => q.id === selectedId()) ?? null;
 
   const filteredQueries = createMemo(() => {
     const filter = filterText().toLowerCase();
     const advFilters = advancedFilters();
 
-    let result = queries.filter((q) => {
+    let result = shownQueries().filter((q) => {
       // Basic text search (OR across common fields)
       if (filter) {
         const matchesText =
@@ -120,22 +248,79 @@ export default function App() {
     });
 
     if (deduplicateRepeats()) {
-      result = result.filter(
-        (q, i, arr) => i === 0 || q.sql_text !== arr[i - 1].sql_text
-      );
+      // Statements often repeat their parent's text; only dedupe top-level rows.
+      let previousSql: string | null = null;
+      result = result.filter((q) => {
+        if (q.parent_id) return true;
+        const repeated = q.sql_text === previousSql;
+        previousSql = q.sql_text;
+        return !repeated;
+      });
     }
 
-    return result;
+    // Nest statements right under their batch or RPC; they stay hidden until
+    // the parent is expanded. Statements whose parent is gone stay top-level.
+    const visibleIds = new Set(result.map((q) => q.id));
+    const children = new Map<string, QueryEvent[]>();
+    for (const q of result) {
+      if (q.parent_id && visibleIds.has(q.parent_id)) {
+        const list = children.get(q.parent_id);
+        if (list) list.push(q);
+        else children.set(q.parent_id, [q]);
+      }
+    }
+    if (children.size === 0) return result;
+
+    const expanded = expandedParents();
+    const nested: QueryEvent[] = [];
+    for (const q of result) {
+      if (q.parent_id && visibleIds.has(q.parent_id)) continue;
+      nested.push(q);
+      const list = children.get(q.id);
+      if (list && expanded.has(q.id)) nested.push(...list);
+    }
+    return nested;
   });
 
+  const statementCounts = createMemo(() => {
+    const counts = new Map<string, number>();
+    for (const q of shownQueries()) {
+      if (q.parent_id) counts.set(q.parent_id, (counts.get(q.parent_id) ?? 0) + 1);
+    }
+    return counts;
+  });
+
+  const blockingChains = createMemo(() => buildBlockingChains(blockedReports(), queries));
+
+  // The threshold is server-wide, so read it whenever a connection comes up.
+  createEffect(() => {
+    if (status().connected) void refreshBlockingThreshold();
+    else setBlockingThreshold(null);
+  });
+
+  function toggleStatements(id: string) {
+    setExpandedParents((prev) => {
+      const next = new Set(prev);
+      if (next.has(id)) next.delete(id);
+      else next.add(id);
+      return next;
+    });
+  }
+
   onMount(() => {
     let unlistenQuery: (() => void) | null = null;
     let unlistenStatus: (() => void) | null = null;
+    let unlistenDeadlock: (() => void) | null = null;
+    let unlistenBlocked: (() => void) | null = null;
+    let unlistenStats: (() => void) | null = null;
     let updateTimeout: number | undefined;
 
     onCleanup(() => {
       unlistenQuery?.();
       unlistenStatus?.();
+      unlistenDeadlock?.();
+      unlistenBlocked?.();
+      unlistenStats?.();
       if (updateTimeout !== undefined) {
         clearTimeout(updateTimeout);
       }
@@ -149,26 +334,49 @@ export default function App() {
         setAppVersion(null);
       }
 
-      unlistenQuery = await listen<QueryEvent>("query-event", (event) => {
-        const query = event.payload;
+      unlistenQuery = await listen<LiveBatch>("query-events", (event) => {
+        const batch = event.payload;
+        setOlderEventCount(batch.dropped);
+        setFeedSessionId(batch.session_id);
         setQueries(
           produce((draft) => {
-            draft.push(query);
-            if (draft.length > MAX_QUERY_BUFFER) {
-              draft.splice(0, draft.length - MAX_QUERY_BUFFER);
+            // Completions reuse the id of their starting event; update the
+            // running row in place instead of adding a new one.
+            const indexById = new Map(draft.map((q, i) => [q.id, i]));
+            for (const query of batch.events) {
+              const index = indexById.get(query.id);
+              if (index !== undefined) {
+                Object.assign(draft[index], query);
+              } else {
+                indexById.set(query.id, draft.length);
+                draft.push(query);
+              }
+            }
+            if (draft.length > batch.limit) {
+              draft.splice(0, draft.length - batch.limit);
             }
           }),
         );
       });
 
+      unlistenDeadlock = await listen<DeadlockGraph>("deadlock", (event) => {
+        setDeadlocks((list) => [...list.slice(-(MAX_DEADLOCKS - 1)), event.payload]);
+      });
+
+      unlistenBlocked = await listen<BlockedProcessReport>("blocked-process", (event) => {
+        setBlockedReports((list) => [...list.slice(-(MAX_BLOCKED_REPORTS - 1)), event.payload]);
+      });
+
+      unlistenStats = await listen<QueryStatsSnapshot>("query-stats", (event) => {
+        setQueryStats(event.payload);
+      });
+
       unlistenStatus = await listen<ProfilerStatus>(
         "profiler-status",
         (event) => {
           setStatus(event.payload);
-          if (event.payload.connected) {
+          if (event.payload.connected || event.payload.capturing) {
             setShowConnection(false);
-          } else if (event.payload.capturing) {
-            void handleStopCapture();
           }
         }
       );
@@ -185,13 +393,47 @@ export default function App() {
       await invoke("connect_to_server", { config, rememberPassword });
     } catch (e) {
       setStatus((s) => ({ ...s, error: String(e) }));
+      return;
+    }
+    void checkOrphanedCaptures();
+  }
+
+  // Best effort: a login without the permissions to list traces can still capture.
+  async function checkOrphanedCaptures() {
+    try {
+      const report = await invoke<OrphanReport>("find_orphaned_captures");
+      if (report.traces.length > 0 || report.xe_sessions.length > 0 || report.files.some((f) => !f.in_use)) {
+        setOrphanReport(report);
+      }
+    } catch {
+      // Nothing to offer.
     }
   }
 
+  async function refreshBlockingThreshold() {
+    try {
+      setBlockingThreshold(await invoke<number>("get_blocked_process_threshold"));
+      setBlockingThresholdError(null);
+    } catch (e) {
+      setBlockingThreshold(null);
+      setBlockingThresholdError(String(e));
+    }
+  }
+
+  async function handleSetBlockingThreshold(seconds: number) {
+    try {
+      await invoke("set_blocked_process_threshold", { seconds });
+    } catch (e) {
+      setBlockingThresholdError(String(e));
+      return;
+    }
+    await refreshBlockingThreshold();
+  }
+
   async function handleDisconnect() {
     try {
       await invoke("disconnect_from_server");
-      setStatus({ connected: false, capturing: false, error: null });
+      setStatus({ connected: false, capturing: false, error: null, reconnect_attempt: null, behind: false });
       setShowConnection(true);
     } catch (e) {
       setStatus((s) => ({ ...s, error: String(e) }));
@@ -200,13 +442,82 @@ export default function App() {
 
   async function handleStartCapture() {
     try {
-      await invoke("start_capture");
+      const source: CaptureSourceKind = { kind: captureSource() };
+      const options: CaptureOptions = {
+        statement_level: statementLevel(),
+        filter: captureFilter(),
+        files: captureFiles(),
+        stop_at: captureStopAt(),
+      };
+      await invoke("start_capture", { source, options });
+      setOpenedSessionId(null);
+      resetHistory();
     } catch (e) {
       setStatus((s) => ({ ...s, error: String(e) }));
       setShowConnection(true);
     }
   }
 
+  async function handleOpenFile(path: string, onServer: boolean) {
+    setShowOpenFile(false);
+    try {
+      setStatus((s) => ({ ...s, error: null }));
+      const source: CaptureSourceKind = path.toLowerCase().endsWith(".xel")
+        ? { kind: "xel_file", path, on_server: onServer }
+        : { kind: "trace_file", path, on_server: onServer };
+      await invoke("start_capture", { source });
+      setOpenedSessionId(null);
+      resetHistory();
+    } catch (e) {
+      setStatus((s) => ({ ...s, error: String(e) }));
+      // Errors are shown on the connection form until connected.
+      if (!status().connected) setShowConnection(true);
+    }
+  }
+
+  // Errors are shown by the sessions dialog.
+  async function handleOpenSession(id: string) {
+    const data = await invoke<SessionData>("open_session", { id, limit: liveEventLimit() });
+    setQueries(data.window.events);
+    resetHistory();
+    setOlderEventCount(data.window.offset);
+    setFeedSessionId(data.session.id);
+    setSelectedId(null);
+    setExpandedParents(new Set<string>());
+    setDeadlocks(data.deadlocks.slice(-MAX_DEADLOCKS));
+    setBlockedReports(data.blocked_reports.slice(-MAX_BLOCKED_REPORTS));
+    setOpenedSessionId(data.session.id);
+    setShowSessions(false);
+    setShowConnection(false);
+  }
+
+  function resetHistory() {
+    setHistoryWindow(null);
+    setHistoryError(null);
+    setOlderEventCount(0);
+    setFeedSessionId(null);
+  }
+
+  // Pages are a feed's worth of events next to the page (or feed) shown,
+  // by event id. Paging newer into the feed goes back to the feed.
+  async function showHistory(anchor: { before: string } | { after: string }) {
+    const id = feedSessionId();
+    if (!id) return;
+    setHistoryLoading(true);
+    try {
+      const page = await invoke<EventWindow>("fetch_session_events", { id, ...anchor, limit: liveEventLimit() });
+      const feedStart = queries[0]?.id;
+      const reachedFeed = "after" in anchor && (page.events.length === 0 || page.events.some((q) => q.id === feedStart));
+      setHistoryWindow(reachedFeed ? null : page);
+      setHistoryError(null);
+      setSelectedId(null);
+    } catch (e) {
+      setHistoryError(String(e));
+    } finally {
+      setHistoryLoading(false);
+    }
+  }
+
   async function handleStopCapture() {
     try {
       await invoke("stop_capture");
@@ -340,7 +651,23 @@ export default function App() {
 
   function handleClear() {
     setQueries([]);
+    resetHistory();
     setSelectedId(null);
+    setExpandedParents(new Set<string>());
+    setDeadlocks([]);
+    setBlockedReports([]);
+    setOpenedSessionId(null);
+    setQueryStats(null);
+    invoke("clear_query_stats").catch((e) => console.error("Failed to clear query stats:", e));
+  }
+
+  function handleFilterByHash(queryHash: string) {
+    const condition = { ...createFilterCondition("query_hash"), operator: "equals" as const, value: queryHash };
+    setAdvancedFilters((filters) => [
+      ...filters.filter((f) => f.column !== "query_hash"),
+      condition,
+    ]);
+    setShowTopQueries(false);
   }
 
   return (
@@ -359,6 +686,11 @@ export default function App() {
           <ConnectionForm
             onConnect={handleConnect}
             onClose={() => status().connected && setShowConnection(false)}
+            onOpenFile={() => {
+              setShowConnection(false);
+              setShowOpenFile(true);
+            }}
+            onOpenSessions={() => setShowSessions(true)}
             error={!status().connected ? status().error : null}
             connected={status().connected}
           />
@@ -383,6 +715,84 @@ export default function App() {
           />
         )}
 
+        {showCaptureFilter() && (
+          <CaptureFilterDialog
+            filter={captureFilter()}
+            onApply={setCaptureFilter}
+            onClose={() => setShowCaptureFilter(false)}
+          />
+        )}
+
+        {showCaptureFiles() && (
+          <CaptureFilesDialog
+            files={captureFiles()}
+            stopAt={captureStopAt()}
+            onApply={(files, stopAt) => {
+              setCaptureFiles(files);
+              setCaptureStopAt(stopAt);
+            }}
+            onClose={() => setShowCaptureFiles(false)}
+          />
+        )}
+
+        {showDeadlocks() && (
+          <DeadlockDialog
+            deadlocks={deadlocks()}
+            onClose={() => setShowDeadlocks(false)}
+          />
+        )}
+
+        {showBlocking() && (
+          <BlockingDialog
+            chains={blockingChains()}
+            reportCount={blockedReports().length}
+            unparsedReports={blockedReports().filter((report) => report.parse_error)}
+            connected={status().connected}
+            threshold={blockingThreshold()}
+            thresholdError={blockingThresholdError()}
+            onSetThreshold={handleSetBlockingThreshold}
+            onClose={() => setShowBlocking(false)}
+          />
+        )}
+
+        {showTopQueries() && (
+          <TopQueriesDialog
+            snapshot={queryStats()}
+            onFilterByHash={handleFilterByHash}
+            onClose={() => setShowTopQueries(false)}
+          />
+        )}
+
+        {showSessions() && (
+          <SessionsDialog
+            capturing={status().capturing}
+            openedSessionId={openedSessionId()}
+            visibleEventIds={filteredQueries().map((q) => q.id)}
+            onOpen={handleOpenSession}
+            onClose={() => setShowSessions(false)}
+          />
+        )}
+
+        <Show when={orphanReport()} keyed>
+          {(report) => (
+            <OrphanedCapturesDialog
+              report={report}
+              onClose={() => setOrphanReport(null)}
+            />
+          )}
+        </Show>
+
+        {showOpenFile() && (
+          <OpenFileDialog
+            connected={status().connected}
+            onOpen={(path, onServer) => void handleOpenFile(path, onServer)}
+            onCancel={() => {
+              setShowOpenFile(false);
+              if (!status().connected && !status().capturing) setShowConnection(true);
+            }}
+          />
+        )}
+
         <Show when={updateAvailable()} keyed>
           {(update) => (
             <UpdateDialog
@@ -399,37 +809,82 @@ export default function App() {
           connected={status().connected}
           capturing={status().capturing}
           queryCount={queries.length}
+          deadlockCount={deadlocks().length}
+          blockedCount={countBlockedSessions(blockingChains())}
+          distinctQueryCount={queryStats()?.distinct_queries ?? 0}
+          captureSource={captureSource()}
+          statementLevel={statementLevel()}
+          captureFilterCount={captureFilterCount()}
+          captureFilesCustomized={captureFilesCustomized()}
           filterText={filterText()}
           advancedFilterCount={advancedFilters().length}
           autoScroll={autoScroll()}
           deduplicateRepeats={deduplicateRepeats()}
-          error={status().connected ? status().error : null}
+          error={status().connected || status().capturing ? status().error : null}
+          reconnectAttempt={status().capturing ? status().reconnect_attempt : null}
+          behind={status().capturing && status().behind}
           onStartCapture={handleStartCapture}
+          onCaptureSourceChange={(value) => setCaptureSource(value as LiveCaptureKind)}
+          onToggleStatementLevel={() => setStatementLevel((s) => !s)}
+          onOpenCaptureFilter={() => setShowCaptureFilter(true)}
+          onOpenCaptureFiles={() => setShowCaptureFiles(true)}
+          onOpenFile={() => setShowOpenFile(true)}
+          onOpenSessions={() => setShowSessions(true)}
           onStopCapture={handleStopCapture}
           onClear={handleClear}
           onFilterChange={setFilterText}
           onOpenAdvancedFilter={() => setShowAdvancedFilter(true)}
+          onOpenDeadlocks={() => setShowDeadlocks(true)}
+          onOpenBlocking={() => setShowBlocking(true)}
+          onOpenTopQueries={() => setShowTopQueries(true)}
           onToggleAutoScroll={() => setAutoScroll((s) => s === "smart" ? "on" : s === "on" ? "off" : "smart")}
           onToggleDeduplicateRepeats={() => setDeduplicateRepeats((s) => !s)}
+          liveEventLimit={liveEventLimit()}
+          onLiveEventLimitChange={setLiveEventLimit}
         />
 
         {/* Main Content Area */}
         <div class="flex-1 flex flex-row min-h-0 relative">
           {/* List and Details */}
           <div class="flex-1 flex flex-col min-h-0 relative">
+            <Show when={olderEventCount() > 0}>
+              <HistoryBar
+                olderCount={olderEventCount()}
+                browsable={feedSessionId() !== null}
+                window={historyWindow()}
+                live={status().capturing}
+                loading={historyLoading()}
+                error={historyError()}
+                onOlder={() => {
+                  const first = (historyWindow()?.events ?? queries)[0];
+                  if (first) void showHistory({ before: first.id });
+                }}
+                onNewer={() => {
+                  const events = historyWindow()?.events ?? [];
+                  const last = events[events.length - 1];
+                  if (last) void showHistory({ after: last.id });
+                }}
+                onLatest={() => setHistoryWindow(null)}
+              />
+            </Show>
+
             <QueryFeed
               queries={filteredQueries()}
+              statementCounts={statementCounts()}
+              expandedParents={expandedParents()}
               selectedId={selectedId()}
               autoScroll={autoScroll()}
               connected={status().connected}
               capturing={status().capturing}
               onSelect={setSelectedId}
+              onToggleStatements={toggleStatements}
             />
 
             <Show when={selectedQuery()} keyed>
               {(query) => (
                 <QueryDetail
                   query={query}
+                  parent={query.parent_id ? shownQueries().find((q) => q.id === query.parent_id) ?? null : null}
                   onClose={() => setSelectedId(null)}
                 />
               )}
@@ -0,0 +1,195 @@
+import { For, Show, createSignal, onCleanup, onMount } from "solid-js";
+import type { BlockingChainNode } from "../lib/blocking.ts";
+import type { BlockedProcessReport } from "../lib/types.ts";
+
+interface Props {
+  chains: BlockingChainNode[];
+  reportCount: number;
+  // Reports whose XML could not be parsed, kept so it can still be copied.
+  unparsedReports: BlockedProcessReport[];
+  connected: boolean;
+  threshold: number | null;
+  thresholdError: string | null;
+  onSetThreshold: (seconds: number) => Promise<void>;
+  onClose: () => void;
+}
+
+export default function BlockingDialog(props: Props) {
+  const [seconds, setSeconds] = createSignal(String(props.threshold || 5));
+  const [saving, setSaving] = createSignal(false);
+  const [copiedId, setCopiedId] = createSignal<string | null>(null);
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  async function handleApply() {
+    const value = parseInt(seconds(), 10);
+    if (Number.isNaN(value) || value < 0) return;
+    setSaving(true);
+    try {
+      await props.onSetThreshold(value);
+    } finally {
+      setSaving(false);
+    }
+  }
+
+  async function handleCopyXml(report: BlockedProcessReport) {
+    await navigator.clipboard.writeText(report.xml);
+    setCopiedId(report.id);
+    setTimeout(() => setCopiedId(null), 2000);
+  }
+
+  function cleanSql(sql: string): string {
+    return sql.replace(/\s+/g, " ").trim();
+  }
+
+  function ChainNode(nodeProps: { node: BlockingChainNode; depth: number }) {
+    const node = nodeProps.node;
+    const process = node.process;
+    const sql = () => cleanSql(node.query?.current_statement || node.query?.sql_text || process?.input_buffer || "");
+    return (
+      <>
+        <div
+          class="flex items-start gap-2 py-1.5 border-b border-slate-800/60 text-xs"
+          style={{ "padding-left": `${nodeProps.depth * 20 + 8}px` }}
+        >
+          <i
+            class={`fa-solid ${nodeProps.depth === 0 ? "fa-circle-stop text-red-400" : "fa-turn-up fa-rotate-90 text-slate-600"} text-[10px] mt-1`}
+          />
+          <div class="flex-1 min-w-0">
+            <div class="flex items-center gap-2">
+              <span class="font-semibold text-slate-100 tabular-nums">#{node.sessionId}</span>
+              <Show when={nodeProps.depth === 0}>
+                <span class="px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-red-500/15 text-red-400">Head blocker</span>
+              </Show>
+              <Show when={process}>
+                <span class="text-slate-400 truncate">
+                  {process!.login_name}<span class="text-slate-500">@</span>{process!.host_name}
+                </span>
+                <span class="text-slate-500">{process!.database_name}</span>
+                <Show when={nodeProps.depth > 0}>
+                  <span class="text-amber-400 tabular-nums">
+                    waiting {(process!.wait_time_ms / 1000).toFixed(1)}s for {process!.lock_mode} on {process!.wait_resource}
+                  </span>
+                </Show>
+                <Show when={nodeProps.depth === 0 && process!.status}>
+                  <span class="text-slate-500">{process!.status}, {process!.transaction_count} open tran</span>
+                </Show>
+              </Show>
+            </div>
+            <div class="font-mono text-[11px] text-slate-300 truncate" title={sql()}>
+              {sql() || "-"}
+            </div>
+          </div>
+        </div>
+        <For each={node.blocked}>{(child) => <ChainNode node={child} depth={nodeProps.depth + 1} />}</For>
+      </>
+    );
+  }
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-4xl h-[70vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="flex items-center gap-3 px-5 py-3 border-b border-slate-800">
+          <div class="w-8 h-8 rounded-full bg-amber-500/10 flex items-center justify-center shrink-0">
+            <i class="fa-solid fa-hourglass-half text-amber-400 text-xs" />
+          </div>
+          <h2 class="text-sm font-semibold text-slate-100 flex-1">
+            Blocking <span class="text-slate-500 font-normal">({props.reportCount} reports)</span>
+          </h2>
+          <button
+            type="button"
+            onClick={props.onClose}
+            class="text-slate-500 hover:text-slate-200 w-8 h-8 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+            title="Close"
+          >
+            <i class="fa-solid fa-xmark" />
+          </button>
+        </div>
+
+        <Show when={props.connected}>
+          <div class="flex items-center gap-3 px-5 py-2.5 border-b border-slate-800 bg-slate-800/40 text-xs">
+            <Show
+              when={props.threshold !== null}
+              fallback={
+                <span class="text-slate-400 flex-1">
+                  Blocked process threshold: unknown{props.thresholdError ? ` (${props.thresholdError})` : ""}
+                </span>
+              }
+            >
+              <span class={`flex-1 ${props.threshold === 0 ? "text-amber-400" : "text-slate-400"}`}>
+                {props.threshold === 0
+                  ? "The blocked process threshold is 0, so the server produces no blocked process reports."
+                  : `Blocked process reports are produced for waits over ${props.threshold} s.`}
+              </span>
+            </Show>
+            <input
+              type="number"
+              min="0"
+              max="86400"
+              value={seconds()}
+              onInput={(e) => setSeconds(e.currentTarget.value)}
+              class="w-16 px-2 py-1 bg-slate-800 border border-slate-700 rounded text-xs text-slate-200 focus:outline-none focus:border-blue-500"
+            />
+            <span class="text-slate-500">s</span>
+            <button
+              type="button"
+              disabled={saving()}
+              onClick={() => void handleApply()}
+              class="px-3 py-1 bg-blue-600 enabled:hover:bg-blue-500 disabled:opacity-50 text-white font-medium rounded transition-colors"
+              title="Changes the server-wide 'blocked process threshold (s)' setting (requires ALTER SETTINGS)"
+            >
+              Set threshold
+            </button>
+          </div>
+        </Show>
+
+        <div class="flex-1 overflow-auto">
+          <Show
+            when={props.chains.length > 0}
+            fallback={
+              <div class="h-full flex items-center justify-center text-xs text-slate-500">
+                No blocking is currently reported.
+              </div>
+            }
+          >
+            <For each={props.chains}>{(node) => <ChainNode node={node} depth={0} />}</For>
+          </Show>
+        </div>
+
+        <Show when={props.unparsedReports.length > 0}>
+          <div class="max-h-[30%] overflow-auto border-t border-slate-800 bg-slate-800/40">
+            <div class="px-5 pt-2.5 pb-1 text-[10px] text-slate-500 uppercase font-bold">
+              Reports that could not be read ({props.unparsedReports.length})
+            </div>
+            <For each={props.unparsedReports}>
+              {(report) => (
+                <div class="flex items-center gap-3 px-5 py-1.5 text-xs border-b border-slate-800/60">
+                  <span class="text-slate-500 tabular-nums">{report.event_time || "-"}</span>
+                  <span class="flex-1 text-red-400 truncate" title={report.parse_error ?? ""}>
+                    {report.parse_error}
+                  </span>
+                  <button
+                    type="button"
+                    onClick={() => void handleCopyXml(report)}
+                    class="px-2.5 py-1 bg-slate-800 hover:bg-slate-700 text-slate-300 text-[11px] font-medium rounded transition-colors"
+                  >
+                    <i class={`fa-solid ${copiedId() === report.id ? "fa-check text-emerald-400" : "fa-copy"} mr-1.5`} />
+                    {copiedId() === report.id ? "Copied" : "Copy XML"}
+                  </button>
+                </div>
+              )}
+            </For>
+          </div>
+        </Show>
+      </div>
+    </div>
+  );
+}
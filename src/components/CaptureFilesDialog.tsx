@@ -0,0 +1,166 @@
+import { Show, onCleanup, onMount } from "solid-js";
+import { createStore } from "solid-js/store";
+import { DEFAULT_CAPTURE_FILE_OPTIONS, type CaptureFileOptions } from "../lib/types.ts";
+
+interface Props {
+  onClose: () => void;
+  files: CaptureFileOptions;
+  stopAt: string | null;
+  onApply: (files: CaptureFileOptions, stopAt: string | null) => void;
+}
+
+/** An RFC 3339 timestamp as a `datetime-local` input value, in local time. */
+function toLocalInput(isoStr: string | null): string {
+  if (!isoStr) return "";
+  const date = new Date(isoStr);
+  if (Number.isNaN(date.getTime())) return "";
+  return new Date(date.getTime() - date.getTimezoneOffset() * 60_000).toISOString().slice(0, 16);
+}
+
+export default function CaptureFilesDialog(props: Props) {
+  const [local, setLocal] = createStore<CaptureFileOptions>({ ...props.files });
+  const [stopAt, setStopAt] = createStore({ value: toLocalInput(props.stopAt) });
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  const handleApply = () => {
+    const number = (value: number | null) => (value !== null && value > 0 ? value : null);
+    const stop = stopAt.value ? new Date(stopAt.value) : null;
+    props.onApply(
+      {
+        directory: local.directory?.trim() || null,
+        max_file_mb: number(local.max_file_mb),
+        rollover: local.rollover,
+        max_files: local.rollover ? number(local.max_files) : null,
+      },
+      stop && !Number.isNaN(stop.getTime()) ? stop.toISOString() : null,
+    );
+    props.onClose();
+  };
+
+  const parseNumber = (value: string) => {
+    const parsed = parseInt(value, 10);
+    return Number.isNaN(parsed) ? null : parsed;
+  };
+
+  const inputClass =
+    "w-full bg-slate-900 border border-slate-700 rounded px-3 py-1.5 text-sm text-slate-200 placeholder-slate-600 focus:outline-none focus:border-blue-500 h-[38px]";
+  const labelClass = "block text-[10px] text-slate-500 uppercase font-bold mb-1";
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-xl max-h-[85vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="p-4 border-b border-slate-800 flex items-center justify-between shrink-0">
+          <h2 class="text-xl font-bold text-slate-100 flex items-center gap-2">
+            <i class="fa-solid fa-hard-drive text-blue-400" />
+            Capture Files
+          </h2>
+          <button
+            onClick={props.onClose}
+            class="text-slate-500 hover:text-slate-300 transition-colors"
+          >
+            <i class="fa-solid fa-xmark text-xl" />
+          </button>
+        </div>
+
+        <div class="flex-1 overflow-y-auto p-5 space-y-4">
+          <p class="text-[11px] text-slate-500">
+            Where SQL Trace and the event file target write on the server. By default, files of up to 50 MB next to
+            the error log, keeping the last 20. Live polling re-reads the file being written, so smaller files cost
+            the server less; without rollover, SQL Trace stops collecting once its file is full.
+          </p>
+
+          <div class="grid grid-cols-2 gap-3">
+            <div class="col-span-2">
+              <label class={labelClass}>Directory on the server</label>
+              <input
+                type="text"
+                value={local.directory ?? ""}
+                onInput={(e) => setLocal("directory", e.currentTarget.value)}
+                placeholder="Error log directory, e.g. D:\Traces"
+                class={inputClass}
+              />
+            </div>
+            <div>
+              <label class={labelClass}>Max file size (MB)</label>
+              <input
+                type="number"
+                min="2"
+                value={local.max_file_mb ?? ""}
+                onInput={(e) => setLocal("max_file_mb", parseNumber(e.currentTarget.value))}
+                placeholder="50"
+                class={inputClass}
+              />
+            </div>
+            <div>
+              <label class={labelClass}>Stop at</label>
+              <input
+                type="datetime-local"
+                value={stopAt.value}
+                onInput={(e) => setStopAt("value", e.currentTarget.value)}
+                class={inputClass}
+              />
+            </div>
+            <label class="col-span-2 flex items-center gap-2 text-sm text-slate-300 cursor-pointer">
+              <input
+                type="checkbox"
+                checked={local.rollover}
+                onChange={(e) => setLocal("rollover", e.currentTarget.checked)}
+              />
+              Start a new file when one is full
+            </label>
+            <Show when={local.rollover}>
+              <div>
+                <label class={labelClass}>Files to keep</label>
+                <input
+                  type="number"
+                  min="2"
+                  value={local.max_files ?? ""}
+                  onInput={(e) => setLocal("max_files", parseNumber(e.currentTarget.value))}
+                  placeholder="All"
+                  class={inputClass}
+                />
+              </div>
+            </Show>
+          </div>
+        </div>
+
+        <div class="p-4 border-t border-slate-800 flex items-center justify-between shrink-0 bg-slate-900">
+          <button
+            onClick={() => {
+              setLocal({ ...DEFAULT_CAPTURE_FILE_OPTIONS });
+              setStopAt("value", "");
+            }}
+            class="px-4 py-2 bg-slate-800/50 hover:bg-red-900/20 text-slate-400 hover:text-red-400 border border-slate-700 hover:border-red-900/30 text-sm font-medium rounded transition-all flex items-center gap-2"
+          >
+            <i class="fa-solid fa-rotate-left text-[10px]" />
+            Defaults
+          </button>
+
+          <div class="flex gap-3">
+            <button
+              onClick={props.onClose}
+              class="px-5 py-2 bg-slate-800 hover:bg-slate-700 text-slate-200 text-sm font-medium rounded transition-colors"
+            >
+              Cancel
+            </button>
+            <button
+              onClick={handleApply}
+              class="px-6 py-2 bg-blue-600 hover:bg-blue-500 text-white text-sm font-medium rounded shadow-lg shadow-blue-900/20 transition-colors"
+            >
+              Apply
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -0,0 +1,145 @@
+import { For, onCleanup, onMount } from "solid-js";
+import { createStore } from "solid-js/store";
+import { EMPTY_CAPTURE_FILTER, type CaptureFilter } from "../lib/types.ts";
+
+interface Props {
+  onClose: () => void;
+  filter: CaptureFilter;
+  onApply: (filter: CaptureFilter) => void;
+}
+
+type TextKey = "database_name" | "login_name" | "host_name" | "program_name" | "text_like";
+type NumberKey = "min_duration_ms" | "min_logical_reads";
+
+const TEXT_FIELDS: { key: TextKey; label: string; placeholder: string }[] = [
+  { key: "database_name", label: "Database", placeholder: "e.g. Sales" },
+  { key: "login_name", label: "Login", placeholder: "e.g. app_user" },
+  { key: "host_name", label: "Host", placeholder: "e.g. WEB%" },
+  { key: "program_name", label: "Application", placeholder: "e.g. %Entity Framework%" },
+  { key: "text_like", label: "SQL text", placeholder: "e.g. %Orders%" },
+];
+
+const NUMBER_FIELDS: { key: NumberKey; label: string }[] = [
+  { key: "min_duration_ms", label: "Min duration (ms)" },
+  { key: "min_logical_reads", label: "Min logical reads" },
+];
+
+export default function CaptureFilterDialog(props: Props) {
+  const [local, setLocal] = createStore<CaptureFilter>({ ...props.filter });
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  const handleApply = () => {
+    const text = (value: string | null) => value?.trim() || null;
+    const number = (value: number | null) => (value !== null && value > 0 ? value : null);
+    props.onApply({
+      database_name: text(local.database_name),
+      login_name: text(local.login_name),
+      host_name: text(local.host_name),
+      program_name: text(local.program_name),
+      min_duration_ms: number(local.min_duration_ms),
+      min_logical_reads: number(local.min_logical_reads),
+      text_like: text(local.text_like),
+    });
+    props.onClose();
+  };
+
+  const inputClass =
+    "w-full bg-slate-900 border border-slate-700 rounded px-3 py-1.5 text-sm text-slate-200 placeholder-slate-600 focus:outline-none focus:border-blue-500 h-[38px]";
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-xl max-h-[85vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="p-4 border-b border-slate-800 flex items-center justify-between shrink-0">
+          <h2 class="text-xl font-bold text-slate-100 flex items-center gap-2">
+            <i class="fa-solid fa-server text-blue-400" />
+            Server Filters
+          </h2>
+          <button
+            onClick={props.onClose}
+            class="text-slate-500 hover:text-slate-300 transition-colors"
+          >
+            <i class="fa-solid fa-xmark text-xl" />
+          </button>
+        </div>
+
+        <div class="flex-1 overflow-y-auto p-5 space-y-4">
+          <p class="text-[11px] text-slate-500">
+            Applied by SQL Server when the capture starts, so non-matching events are never collected. Text values
+            are LIKE patterns (use <span class="font-mono">%</span> as a wildcard). Duration, reads and SQL text
+            filter queries only; errors, deadlocks and blocking reports are kept. With SQL Trace those three are
+            applied as events are read, since a trace filter would drop every other event too. With a minimum
+            duration or reads, running queries are not shown.
+          </p>
+
+          <div class="grid grid-cols-2 gap-3">
+            <For each={TEXT_FIELDS}>
+              {(field) => (
+                <div class={field.key === "text_like" ? "col-span-2" : undefined}>
+                  <label class="block text-[10px] text-slate-500 uppercase font-bold mb-1">{field.label}</label>
+                  <input
+                    type="text"
+                    value={local[field.key] ?? ""}
+                    onInput={(e) => setLocal(field.key, e.currentTarget.value)}
+                    placeholder={field.placeholder}
+                    class={inputClass}
+                  />
+                </div>
+              )}
+            </For>
+            <For each={NUMBER_FIELDS}>
+              {(field) => (
+                <div>
+                  <label class="block text-[10px] text-slate-500 uppercase font-bold mb-1">{field.label}</label>
+                  <input
+                    type="number"
+                    min="0"
+                    value={local[field.key] ?? ""}
+                    onInput={(e) => {
+                      const value = parseInt(e.currentTarget.value, 10);
+                      setLocal(field.key, Number.isNaN(value) ? null : value);
+                    }}
+                    class={inputClass}
+                  />
+                </div>
+              )}
+            </For>
+          </div>
+        </div>
+
+        <div class="p-4 border-t border-slate-800 flex items-center justify-between shrink-0 bg-slate-900">
+          <button
+            onClick={() => setLocal({ ...EMPTY_CAPTURE_FILTER })}
+            class="px-4 py-2 bg-slate-800/50 hover:bg-red-900/20 text-slate-400 hover:text-red-400 border border-slate-700 hover:border-red-900/30 text-sm font-medium rounded transition-all flex items-center gap-2"
+          >
+            <i class="fa-solid fa-eraser text-[10px]" />
+            Clear All
+          </button>
+
+          <div class="flex gap-3">
+            <button
+              onClick={props.onClose}
+              class="px-5 py-2 bg-slate-800 hover:bg-slate-700 text-slate-200 text-sm font-medium rounded transition-colors"
+            >
+              Cancel
+            </button>
+            <button
+              onClick={handleApply}
+              class="px-6 py-2 bg-blue-600 hover:bg-blue-500 text-white text-sm font-medium rounded shadow-lg shadow-blue-900/20 transition-colors"
+            >
+              Apply Filters
+            </button>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -6,6 +6,8 @@ import Dropdown from "./Dropdown.tsx";
 interface Props {
   onConnect: (config: ConnectionConfig, rememberPassword: boolean) => void;
   onClose: () => void;
+  onOpenFile: () => void;
+  onOpenSessions: () => void;
   error: string | null;
   connected: boolean;
 }
@@ -198,6 +200,23 @@ export default function ConnectionForm(props: Props) {
         >
           {connecting() ? "Connecting..." : "Connect"}
         </button>
+
+        <button
+          type="button"
+          onClick={props.onOpenFile}
+          class="mt-3 w-full py-2 text-xs text-slate-400 hover:text-slate-200 transition-colors flex items-center justify-center gap-2"
+        >
+          <i class="fa-solid fa-folder-open text-[10px]" />
+          Open a capture file instead
+        </button>
+        <button
+          type="button"
+          onClick={props.onOpenSessions}
+          class="w-full py-2 text-xs text-slate-400 hover:text-slate-200 transition-colors flex items-center justify-center gap-2"
+        >
+          <i class="fa-solid fa-clock-rotate-left text-[10px]" />
+          Open a saved session
+        </button>
       </form>
     </div>
   );
@@ -0,0 +1,211 @@
+import { For, Show, createSignal, onCleanup, onMount } from "solid-js";
+import type { DeadlockGraph, DeadlockLockHolder, DeadlockProcess } from "../lib/types.ts";
+
+interface Props {
+  deadlocks: DeadlockGraph[];
+  onClose: () => void;
+}
+
+export default function DeadlockDialog(props: Props) {
+  const [selectedId, setSelectedId] = createSignal<string | null>(
+    props.deadlocks[props.deadlocks.length - 1]?.id ?? null
+  );
+  const [copied, setCopied] = createSignal(false);
+
+  const selected = () => props.deadlocks.find((d) => d.id === selectedId()) ?? null;
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  async function handleCopyXml(xml: string) {
+    await navigator.clipboard.writeText(xml);
+    setCopied(true);
+    setTimeout(() => setCopied(false), 2000);
+  }
+
+  function sessionLabel(graph: DeadlockGraph, processId: string): string {
+    const process = graph.processes.find((p) => p.id === processId);
+    return process ? `#${process.session_id}` : processId;
+  }
+
+  function formatTime(isoStr: string): string {
+    const match = isoStr.match(/(?:T|\s)(\d{2}:\d{2}:\d{2}(?:\.\d{1,3})?)/);
+    return match?.[1] ?? (isoStr || "-");
+  }
+
+  function HolderList(holderProps: { graph: DeadlockGraph; label: string; holders: DeadlockLockHolder[] }) {
+    return (
+      <Show when={holderProps.holders.length > 0}>
+        <div class="flex items-center gap-1.5 flex-wrap">
+          <span class="text-[10px] text-slate-500 uppercase tracking-wider w-14">{holderProps.label}</span>
+          <For each={holderProps.holders}>
+            {(holder) => (
+              <span class="px-1.5 py-0.5 rounded bg-slate-800 border border-slate-700 text-[11px] text-slate-300 tabular-nums">
+                {sessionLabel(holderProps.graph, holder.process_id)} <span class="text-slate-500">{holder.mode}</span>
+              </span>
+            )}
+          </For>
+        </div>
+      </Show>
+    );
+  }
+
+  function ProcessCard(cardProps: { process: DeadlockProcess }) {
+    const p = cardProps.process;
+    return (
+      <div
+        class={`rounded-lg border p-3 ${p.is_victim ? "border-red-900/60 bg-red-950/20" : "border-slate-700/50 bg-slate-900/50"}`}
+      >
+        <div class="flex items-center gap-2 mb-2">
+          <span class="text-xs font-semibold text-slate-100 tabular-nums">Session #{p.session_id}</span>
+          <Show when={p.is_victim}>
+            <span class="px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-red-500/15 text-red-400">Victim</span>
+          </Show>
+          <span class="text-[11px] text-slate-400 truncate">
+            {p.login_name}<span class="text-slate-500">@</span>{p.host_name}
+          </span>
+          <span class="text-[11px] text-slate-500 truncate ml-auto" title={p.program_name}>{p.program_name}</span>
+        </div>
+        <div class="grid grid-cols-[110px_1fr] gap-x-3 gap-y-0.5 text-[11px] mb-2">
+          <span class="text-slate-500">Database</span>
+          <span class="text-slate-300">{p.database_name || "-"}</span>
+          <span class="text-slate-500">Isolation</span>
+          <span class="text-slate-300">{p.isolation_level || "-"}</span>
+          <span class="text-slate-500">Waiting for</span>
+          <span class="text-slate-300 font-mono">
+            {p.lock_mode} {p.wait_resource} <span class="text-slate-500">({p.wait_time_ms.toLocaleString()} ms)</span>
+          </span>
+          <Show when={p.transaction_name}>
+            <span class="text-slate-500">Transaction</span>
+            <span class="text-slate-300">{p.transaction_name}</span>
+          </Show>
+        </div>
+        <For each={p.frames.filter((f) => f.statement)}>
+          {(frame) => (
+            <div class="mb-1.5">
+              <div class="text-[10px] text-slate-500 mb-0.5">
+                {frame.procedure || "adhoc"}{frame.line ? `, line ${frame.line}` : ""}
+              </div>
+              <pre class="text-[11px] font-mono text-slate-200 whitespace-pre-wrap break-all bg-slate-950/40 rounded p-2">
+                {frame.statement}
+              </pre>
+            </div>
+          )}
+        </For>
+        <Show when={p.input_buffer}>
+          <div class="text-[10px] text-slate-500 mb-0.5">Input buffer</div>
+          <pre class="text-[11px] font-mono text-slate-300 whitespace-pre-wrap break-all bg-slate-950/40 rounded p-2 max-h-[120px] overflow-auto">
+            {p.input_buffer}
+          </pre>
+        </Show>
+      </div>
+    );
+  }
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-5xl h-[80vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="flex items-center gap-3 px-5 py-3 border-b border-slate-800">
+          <div class="w-8 h-8 rounded-full bg-red-500/10 flex items-center justify-center shrink-0">
+            <i class="fa-solid fa-lock text-red-400 text-xs" />
+          </div>
+          <h2 class="text-sm font-semibold text-slate-100 flex-1">
+            Deadlocks <span class="text-slate-500 font-normal">({props.deadlocks.length})</span>
+          </h2>
+          <button
+            type="button"
+            onClick={props.onClose}
+            class="text-slate-500 hover:text-slate-200 w-8 h-8 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+            title="Close"
+          >
+            <i class="fa-solid fa-xmark" />
+          </button>
+        </div>
+
+        <div class="flex flex-1 min-h-0">
+          <div class="w-[200px] border-r border-slate-800 overflow-auto shrink-0">
+            <For each={[...props.deadlocks].reverse()}>
+              {(graph) => (
+                <button
+                  type="button"
+                  onClick={() => setSelectedId(graph.id)}
+                  class={`w-full text-left px-3 py-2 border-b border-slate-800/60 text-xs transition-colors ${selectedId() === graph.id ? "bg-blue-600/15 text-slate-100" : "text-slate-300 hover:bg-slate-800/50"}`}
+                >
+                  <div class="tabular-nums">{formatTime(graph.event_time)}</div>
+                  <div class="text-[10px] text-slate-500">
+                    {graph.processes.length} sessions, victim{" "}
+                    {graph.victim_ids.map((id) => sessionLabel(graph, id)).join(", ") || "-"}
+                  </div>
+                </button>
+              )}
+            </For>
+          </div>
+
+          <div class="flex-1 overflow-auto p-4">
+            <Show
+              when={selected()}
+              keyed
+              fallback={<div class="text-xs text-slate-500">Select a deadlock to see its details.</div>}
+            >
+              {(graph) => (
+                <div class="flex flex-col gap-5">
+                  <Show when={graph.processes.length === 0}>
+                    <div class="text-xs text-amber-400">The deadlock report could not be parsed; the raw XML is still available.</div>
+                  </Show>
+
+                  <div>
+                    <div class="text-[10px] text-slate-500 uppercase tracking-wider mb-1.5 font-medium">Processes</div>
+                    <div class="flex flex-col gap-2">
+                      <For each={graph.processes}>{(process) => <ProcessCard process={process} />}</For>
+                    </div>
+                  </div>
+
+                  <Show when={graph.resources.length > 0}>
+                    <div>
+                      <div class="text-[10px] text-slate-500 uppercase tracking-wider mb-1.5 font-medium">Resources</div>
+                      <div class="flex flex-col gap-2">
+                        <For each={graph.resources}>
+                          {(resource) => (
+                            <div class="rounded-lg border border-slate-700/50 bg-slate-900/50 p-3 flex flex-col gap-1.5">
+                              <div class="text-xs text-slate-200">
+                                <span class="font-mono text-slate-400 mr-2">{resource.kind}</span>
+                                {resource.object_name}
+                                <Show when={resource.index_name}>
+                                  <span class="text-slate-500"> / {resource.index_name}</span>
+                                </Show>
+                              </div>
+                              <HolderList graph={graph} label="Owners" holders={resource.owners} />
+                              <HolderList graph={graph} label="Waiters" holders={resource.waiters} />
+                            </div>
+                          )}
+                        </For>
+                      </div>
+                    </div>
+                  </Show>
+
+                  <div>
+                    <button
+                      type="button"
+                      onClick={() => void handleCopyXml(graph.xml)}
+                      class="px-3 py-1.5 bg-slate-800 hover:bg-slate-700 text-slate-300 text-xs font-medium rounded transition-colors"
+                    >
+                      <i class={`fa-solid ${copied() ? "fa-check text-emerald-400" : "fa-copy"} mr-1.5`} />
+                      {copied() ? "Copied" : "Copy deadlock XML"}
+                    </button>
+                  </div>
+                </div>
+              )}
+            </Show>
+          </div>
+        </div>
+      </div>
+    </div>
+  );
+}
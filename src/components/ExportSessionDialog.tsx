@@ -0,0 +1,207 @@
+import { invoke } from "@tauri-apps/api/core";
+import { For, Show, createSignal, onCleanup, onMount } from "solid-js";
+import { EMPTY_CAPTURE_FILTER, type CaptureFilter, type ExportFormat, type SessionInfo } from "../lib/types.ts";
+
+interface Props {
+  session: SessionInfo;
+  // Ids of the rows the feed shows, when this session is the one loaded.
+  visibleEventIds: string[] | null;
+  onClose: () => void;
+}
+
+const FORMATS: { value: ExportFormat; label: string; extension: string }[] = [
+  { value: "csv", label: "CSV", extension: ".csv" },
+  { value: "ndjson", label: "JSON Lines", extension: ".ndjson" },
+  { value: "parquet", label: "Parquet", extension: ".parquet" },
+];
+
+function withExtension(path: string, format: ExportFormat): string {
+  const extension = FORMATS.find((f) => f.value === format)!.extension;
+  const known = FORMATS.find((f) => path.toLowerCase().endsWith(f.extension));
+  return known ? path.slice(0, -known.extension.length) + extension : path + extension;
+}
+
+function defaultPath(session: SessionInfo, format: ExportFormat): string {
+  const dir = localStorage.getItem("last-export-dir") ?? "";
+  const name = session.name.replace(/[\\/:*?"<>|]+/g, "-").trim();
+  return withExtension(dir ? `${dir}${dir.match(/[\\/]$/) ? "" : "/"}${name}` : name, format);
+}
+
+export default function ExportSessionDialog(props: Props) {
+  const [format, setFormat] = createSignal<ExportFormat>(
+    (localStorage.getItem("last-export-format") as ExportFormat | null) ?? "csv"
+  );
+  const [path, setPath] = createSignal(defaultPath(props.session, format()));
+  const [filter, setFilter] = createSignal<CaptureFilter>({ ...EMPTY_CAPTURE_FILTER });
+  const [onlyVisible, setOnlyVisible] = createSignal(false);
+  const [exporting, setExporting] = createSignal(false);
+  const [result, setResult] = createSignal<string | null>(null);
+  const [error, setError] = createSignal<string | null>(null);
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape" && !exporting()) {
+        e.stopImmediatePropagation();
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown, true);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown, true));
+  });
+
+  function handleFormatChange(value: ExportFormat) {
+    setFormat(value);
+    setPath((p) => withExtension(p, value));
+  }
+
+  function updateText(key: "database_name" | "login_name" | "text_like", value: string) {
+    setFilter((f) => ({ ...f, [key]: value.trim() ? value : null }));
+  }
+
+  async function handleSubmit(e: Event) {
+    e.preventDefault();
+    const target = path().trim().replace(/^"(.*)"$/, "$1");
+    if (!target) return;
+
+    setExporting(true);
+    setError(null);
+    setResult(null);
+    try {
+      const count = await invoke<number>("export_session", {
+        id: props.session.id,
+        path: target,
+        format: format(),
+        filter: filter(),
+        eventIds: onlyVisible() ? props.visibleEventIds : null,
+      });
+      localStorage.setItem("last-export-dir", target.replace(/[\\/][^\\/]*$/, ""));
+      localStorage.setItem("last-export-format", format());
+      setResult(`Exported ${count.toLocaleString()} events to ${target}`);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setExporting(false);
+    }
+  }
+
+  return (
+    <div class="absolute inset-0 z-[70] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <form
+        onSubmit={handleSubmit}
+        class="w-full max-w-lg bg-slate-900 border border-slate-800 rounded-xl shadow-2xl p-6"
+      >
+        <div class="flex gap-4 items-start mb-5">
+          <div class="flex-shrink-0 flex items-center justify-center w-10 h-10 rounded-full bg-blue-500/10 text-blue-400 mt-1">
+            <i class="fa-solid fa-file-export text-lg" />
+          </div>
+          <div class="flex-1 min-w-0">
+            <h2 class="text-xl font-bold text-slate-100 mb-1">Export Session</h2>
+            <p class="text-slate-400 text-sm truncate" title={props.session.name}>
+              {props.session.name} · {props.session.event_count.toLocaleString()} events
+            </p>
+          </div>
+        </div>
+
+        <label class="label-base">Format</label>
+        <div class="flex gap-2 mb-4">
+          <For each={FORMATS}>
+            {(option) => (
+              <button
+                type="button"
+                onClick={() => handleFormatChange(option.value)}
+                class={`flex-1 py-1.5 text-xs font-medium rounded border transition-colors ${format() === option.value
+                  ? "bg-blue-600/20 text-blue-400 border-blue-500/40"
+                  : "bg-slate-800 text-slate-400 border-slate-700 hover:text-slate-300"
+                  }`}
+              >
+                {option.label}
+              </button>
+            )}
+          </For>
+        </div>
+
+        <label class="label-base">File path</label>
+        <input
+          type="text"
+          value={path()}
+          onInput={(e) => setPath(e.currentTarget.value)}
+          class="input-base mb-4"
+        />
+
+        <label class="label-base">Only events matching (LIKE patterns, optional)</label>
+        <div class="grid grid-cols-2 gap-2 mb-2">
+          <input
+            type="text"
+            placeholder="Database"
+            value={filter().database_name ?? ""}
+            onInput={(e) => updateText("database_name", e.currentTarget.value)}
+            class="input-base"
+          />
+          <input
+            type="text"
+            placeholder="Login"
+            value={filter().login_name ?? ""}
+            onInput={(e) => updateText("login_name", e.currentTarget.value)}
+            class="input-base"
+          />
+          <input
+            type="number"
+            min="0"
+            placeholder="Min duration (ms)"
+            value={filter().min_duration_ms ?? ""}
+            onInput={(e) => {
+              const value = e.currentTarget.value;
+              setFilter((f) => ({ ...f, min_duration_ms: value === "" ? null : Number(value) }));
+            }}
+            class="input-base"
+          />
+          <input
+            type="text"
+            placeholder="Text, e.g. %Orders%"
+            value={filter().text_like ?? ""}
+            onInput={(e) => updateText("text_like", e.currentTarget.value)}
+            class="input-base"
+          />
+        </div>
+
+        <Show when={props.visibleEventIds}>
+          {(ids) => (
+            <label class="flex items-center gap-2 text-xs text-slate-300 mt-3 cursor-pointer">
+              <input
+                type="checkbox"
+                checked={onlyVisible()}
+                onChange={(e) => setOnlyVisible(e.currentTarget.checked)}
+              />
+              Only the {ids().length.toLocaleString()} events shown in the feed
+            </label>
+          )}
+        </Show>
+
+        <Show when={error()}>
+          <div class="mt-4 text-xs text-red-400 break-words">{error()}</div>
+        </Show>
+        <Show when={result()}>
+          <div class="mt-4 text-xs text-emerald-400 break-words">{result()}</div>
+        </Show>
+
+        <div class="flex gap-3 justify-end mt-6">
+          <button
+            type="button"
+            onClick={props.onClose}
+            disabled={exporting()}
+            class="px-5 py-2 bg-slate-800 enabled:hover:bg-slate-700 disabled:opacity-40 text-slate-200 text-sm font-medium rounded transition-colors"
+          >
+            {result() ? "Close" : "Cancel"}
+          </button>
+          <button
+            type="submit"
+            disabled={exporting() || !path().trim()}
+            class="px-5 py-2 bg-blue-600 enabled:hover:bg-blue-500 disabled:bg-slate-700 disabled:text-slate-500 text-white text-sm font-medium rounded shadow-lg shadow-blue-900/20 transition-colors"
+          >
+            {exporting() ? "Exporting..." : "Export"}
+          </button>
+        </div>
+      </form>
+    </div>
+  );
+}
@@ -0,0 +1,57 @@
+import type { EventWindow } from "../lib/types.ts";
+
+interface Props {
+  // Events before the ones in the feed.
+  olderCount: number;
+  // Whether the older events can be paged through; file replays aren't recorded.
+  browsable: boolean;
+  window: EventWindow | null;
+  live: boolean;
+  loading: boolean;
+  error: string | null;
+  onOlder: () => void;
+  onNewer: () => void;
+  onLatest: () => void;
+}
+
+export default function HistoryBar(props: Props) {
+  const btnClass =
+    "flex items-center gap-1.5 px-2.5 py-1 text-[11px] font-medium rounded bg-slate-700 enabled:hover:bg-slate-600 text-slate-200 transition-colors disabled:opacity-40 disabled:cursor-not-allowed";
+
+  return (
+    <div class="flex items-center gap-2 px-3 py-1.5 bg-slate-800/40 border-b border-slate-700 text-[11px] text-slate-400">
+      <i class="fa-solid fa-layer-group text-slate-500" />
+      <div class="flex-1">
+        {props.window
+          ? `Showing events ${(props.window.offset + 1).toLocaleString()}–${(props.window.offset + props.window.events.length).toLocaleString()} of ${props.window.total.toLocaleString()} recorded.${props.live ? " The live feed keeps running meanwhile." : ""}`
+          : `${props.olderCount.toLocaleString()} older events are not shown${props.browsable ? "; they are kept in the session." : "."}`}
+        {props.error && <span class="ml-2 text-red-400">{props.error}</span>}
+      </div>
+
+      {props.browsable && (
+        <>
+          <button
+            class={btnClass}
+            disabled={props.loading || props.window?.offset === 0}
+            onClick={props.onOlder}
+          >
+            <i class="fa-solid fa-chevron-up text-[9px]" />
+            Older
+          </button>
+          {props.window && (
+            <>
+              <button class={btnClass} disabled={props.loading} onClick={props.onNewer}>
+                <i class="fa-solid fa-chevron-down text-[9px]" />
+                Newer
+              </button>
+              <button class={btnClass} disabled={props.loading} onClick={props.onLatest}>
+                <i class="fa-solid fa-forward-fast text-[9px]" />
+                {props.live ? "Back to live" : "Latest"}
+              </button>
+            </>
+          )}
+        </>
+      )}
+    </div>
+  );
+}
@@ -0,0 +1,97 @@
+import { invoke } from "@tauri-apps/api/core";
+import { Show, createSignal, onCleanup, onMount } from "solid-js";
+import type { SessionInfo } from "../lib/types.ts";
+
+interface Props {
+  onImported: (session: SessionInfo) => void;
+  onCancel: () => void;
+}
+
+export default function ImportSessionDialog(props: Props) {
+  const [path, setPath] = createSignal(localStorage.getItem("last-import-file") ?? "");
+  const [importing, setImporting] = createSignal(false);
+  const [error, setError] = createSignal<string | null>(null);
+  let inputRef!: HTMLInputElement;
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape" && !importing()) {
+        e.stopImmediatePropagation();
+        props.onCancel();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown, true);
+    inputRef.focus();
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown, true));
+  });
+
+  async function handleSubmit(e: Event) {
+    e.preventDefault();
+    const value = path().trim().replace(/^"(.*)"$/, "$1");
+    if (!value) return;
+    localStorage.setItem("last-import-file", value);
+
+    setImporting(true);
+    setError(null);
+    try {
+      props.onImported(await invoke<SessionInfo>("import_session", { path: value }));
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setImporting(false);
+    }
+  }
+
+  return (
+    <div class="absolute inset-0 z-[70] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <form
+        onSubmit={handleSubmit}
+        class="w-full max-w-lg bg-slate-900 border border-slate-800 rounded-xl shadow-2xl p-6"
+      >
+        <div class="flex gap-4 items-start mb-5">
+          <div class="flex-shrink-0 flex items-center justify-center w-10 h-10 rounded-full bg-blue-500/10 text-blue-400 mt-1">
+            <i class="fa-solid fa-file-import text-lg" />
+          </div>
+          <div class="flex-1">
+            <h2 class="text-xl font-bold text-slate-100 mb-1">Import Session</h2>
+            <p class="text-slate-400 text-sm">
+              Load a CSV or JSON Lines file exported by the profiler as a read-only session. Columns the profiler doesn't know are kept and shown with each event.
+            </p>
+          </div>
+        </div>
+
+        <label class="label-base">File path</label>
+        <input
+          ref={inputRef}
+          type="text"
+          value={path()}
+          onInput={(e) => setPath(e.currentTarget.value)}
+          placeholder={"C:\\Exports\\capture.csv"}
+          class="input-base"
+        />
+
+        <Show when={error()}>
+          <div class="mt-4 text-xs text-red-400 break-words">{error()}</div>
+        </Show>
+
+        <div class="flex gap-3 justify-end mt-6">
+          <button
+            type="button"
+            onClick={props.onCancel}
+            disabled={importing()}
+            class="px-5 py-2 bg-slate-800 enabled:hover:bg-slate-700 disabled:opacity-40 text-slate-200 text-sm font-medium rounded transition-colors"
+          >
+            Cancel
+          </button>
+          <button
+            type="submit"
+            disabled={importing() || path().trim().length === 0}
+            class="px-5 py-2 bg-blue-600 enabled:hover:bg-blue-500 disabled:bg-slate-700 disabled:text-slate-500 text-white text-sm font-medium rounded shadow-lg shadow-blue-900/20 transition-colors"
+          >
+            {importing() ? "Importing..." : "Import"}
+          </button>
+        </div>
+      </form>
+    </div>
+  );
+}
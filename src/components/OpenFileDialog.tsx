@@ -0,0 +1,95 @@
+import { createSignal, onCleanup, onMount } from "solid-js";
+
+interface Props {
+  connected: boolean;
+  onOpen: (path: string, onServer: boolean) => void;
+  onCancel: () => void;
+}
+
+export default function OpenFileDialog(props: Props) {
+  const [path, setPath] = createSignal(localStorage.getItem("last-capture-file") ?? "");
+  const [onServer, setOnServer] = createSignal(false);
+  let inputRef!: HTMLInputElement;
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        props.onCancel();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    inputRef.focus();
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  function handleSubmit(e: Event) {
+    e.preventDefault();
+    const value = path().trim().replace(/^"(.*)"$/, "$1");
+    if (!value) return;
+    localStorage.setItem("last-capture-file", value);
+    props.onOpen(value, props.connected && onServer());
+  }
+
+  return (
+    <div class="absolute inset-0 z-[70] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <form
+        onSubmit={handleSubmit}
+        class="w-full max-w-lg bg-slate-900 border border-slate-800 rounded-xl shadow-2xl p-6"
+      >
+        <div class="flex gap-4 items-start mb-5">
+          <div class="flex-shrink-0 flex items-center justify-center w-10 h-10 rounded-full bg-blue-500/10 text-blue-400 mt-1">
+            <i class="fa-solid fa-folder-open text-lg" />
+          </div>
+          <div class="flex-1">
+            <h2 class="text-xl font-bold text-slate-100 mb-1">Open Capture File</h2>
+            <p class="text-slate-400 text-sm">
+              Load a SQL Trace (.trc) or Extended Events (.xel) file into the feed. Rollover files next to it are loaded too.
+              Files are read on this machine; .xel files need PowerShell with the SqlServer module for that.
+            </p>
+          </div>
+        </div>
+
+        <label class="label-base">File path</label>
+        <input
+          ref={inputRef}
+          type="text"
+          value={path()}
+          onInput={(e) => setPath(e.currentTarget.value)}
+          placeholder={"C:\\Traces\\capture.trc"}
+          class="input-base"
+        />
+
+        <label
+          class="flex items-center gap-2 mt-3 cursor-pointer"
+          classList={{ "opacity-50 cursor-not-allowed": !props.connected }}
+        >
+          <input
+            type="checkbox"
+            checked={props.connected && onServer()}
+            disabled={!props.connected}
+            onChange={(e) => setOnServer(e.currentTarget.checked)}
+            class="custom-checkbox"
+          />
+          <span class="label-sub">Read on the connected server (the path must be one it can open)</span>
+        </label>
+
+        <div class="flex gap-3 justify-end mt-6">
+          <button
+            type="button"
+            onClick={props.onCancel}
+            class="px-5 py-2 bg-slate-800 hover:bg-slate-700 text-slate-200 text-sm font-medium rounded transition-colors"
+          >
+            Cancel
+          </button>
+          <button
+            type="submit"
+            disabled={path().trim().length === 0}
+            class="px-5 py-2 bg-blue-600 enabled:hover:bg-blue-500 disabled:bg-slate-700 disabled:text-slate-500 text-white text-sm font-medium rounded shadow-lg shadow-blue-900/20 transition-colors"
+          >
+            Open
+          </button>
+        </div>
+      </form>
+    </div>
+  );
+}
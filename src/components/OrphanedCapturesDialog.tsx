@@ -0,0 +1,205 @@
+import { invoke } from "@tauri-apps/api/core";
+import { For, Show, createSignal, onCleanup, onMount } from "solid-js";
+import type { OrphanReport } from "../lib/types.ts";
+
+interface Props {
+  report: OrphanReport;
+  onClose: () => void;
+}
+
+function formatDateTime(isoStr: string | null): string {
+  if (!isoStr) return "-";
+  const date = new Date(isoStr);
+  return Number.isNaN(date.getTime()) ? isoStr : date.toLocaleString();
+}
+
+function formatBytes(bytes: number): string {
+  if (bytes < 1024 * 1024) return `${Math.ceil(bytes / 1024)} KB`;
+  if (bytes < 1024 * 1024 * 1024) return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  return `${(bytes / (1024 * 1024 * 1024)).toFixed(2)} GB`;
+}
+
+export default function OrphanedCapturesDialog(props: Props) {
+  const [report, setReport] = createSignal(props.report);
+  // With other profilers connected a listed trace may be in use, so nothing
+  // is selected for them by default.
+  const selectByDefault = props.report.other_clients === 0;
+  const [traceIds, setTraceIds] = createSignal(
+    new Set(selectByDefault ? props.report.traces.map((t) => t.id) : []),
+  );
+  const [sessionNames, setSessionNames] = createSignal(
+    new Set(selectByDefault ? props.report.xe_sessions.map((s) => s.name) : []),
+  );
+  const [cleaning, setCleaning] = createSignal(false);
+  const [error, setError] = createSignal<string | null>(null);
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape" && !cleaning()) {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  function toggle<T>(set: Set<T>, value: T): Set<T> {
+    const next = new Set(set);
+    if (!next.delete(value)) next.add(value);
+    return next;
+  }
+
+  const leftoverBytes = () => report().files.filter((f) => !f.in_use).reduce((sum, f) => sum + f.size_bytes, 0);
+  const selectedCount = () => traceIds().size + sessionNames().size;
+
+  async function handleCleanUp() {
+    setCleaning(true);
+    setError(null);
+    try {
+      const next = await invoke<OrphanReport>("clean_up_orphaned_captures", {
+        traceIds: [...traceIds()],
+        xeSessions: [...sessionNames()],
+      });
+      setReport(next);
+      setTraceIds(new Set<number>());
+      setSessionNames(new Set<string>());
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setCleaning(false);
+    }
+  }
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-3xl max-h-[75vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="flex items-center gap-3 px-5 py-3 border-b border-slate-800">
+          <div class="w-8 h-8 rounded-full bg-amber-500/10 flex items-center justify-center shrink-0">
+            <i class="fa-solid fa-broom text-amber-400 text-xs" />
+          </div>
+          <div class="flex-1">
+            <h2 class="text-sm font-semibold text-slate-100">Leftover Captures on This Server</h2>
+            <p class="text-[11px] text-slate-500">
+              Traces and Extended Events sessions from earlier captures that were never cleaned up, e.g. after a crash.
+            </p>
+          </div>
+          <button
+            type="button"
+            onClick={props.onClose}
+            disabled={cleaning()}
+            class="text-slate-500 hover:text-slate-200 w-8 h-8 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+            title="Close"
+          >
+            <i class="fa-solid fa-xmark" />
+          </button>
+        </div>
+
+        <Show when={report().other_clients > 0}>
+          <div class="px-5 py-2 text-xs text-amber-400 border-b border-slate-800">
+            {report().other_clients} other profiler connection(s) are open on this server. Some of these may belong to a capture
+            that is still running; only remove the ones you know are abandoned.
+          </div>
+        </Show>
+        <Show when={error()}>
+          <div class="px-5 py-2 text-xs text-red-400 border-b border-slate-800">{error()}</div>
+        </Show>
+
+        <div class="flex-1 overflow-auto text-xs">
+          <Show when={report().traces.length === 0 && report().xe_sessions.length === 0}>
+            <div class="px-5 py-4 text-slate-400">
+              <i class="fa-solid fa-check text-emerald-400 mr-2" />
+              No profiler traces or sessions are left on the server.
+            </div>
+          </Show>
+
+          <For each={report().traces}>
+            {(trace) => (
+              <label class="flex items-center gap-3 px-5 py-2 border-b border-slate-800/60 hover:bg-slate-800/40 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={traceIds().has(trace.id)}
+                  onChange={() => setTraceIds((s) => toggle(s, trace.id))}
+                />
+                <div class="flex-1 min-w-0">
+                  <div class="text-slate-200 truncate" title={trace.path}>
+                    SQL Trace #{trace.id} <span class="text-slate-500 font-mono">{trace.path}</span>
+                  </div>
+                  <div class="text-[10px] text-slate-500">
+                    {trace.running ? "running" : "stopped"} · started {formatDateTime(trace.start_time)}
+                    {" "}· last event {formatDateTime(trace.last_event_time)} · {trace.event_count.toLocaleString()} events
+                  </div>
+                </div>
+              </label>
+            )}
+          </For>
+
+          <For each={report().xe_sessions}>
+            {(session) => (
+              <label class="flex items-center gap-3 px-5 py-2 border-b border-slate-800/60 hover:bg-slate-800/40 cursor-pointer">
+                <input
+                  type="checkbox"
+                  checked={sessionNames().has(session.name)}
+                  onChange={() => setSessionNames((s) => toggle(s, session.name))}
+                />
+                <div class="flex-1 min-w-0">
+                  <div class="text-slate-200 truncate">
+                    Extended Events session <span class="text-slate-500 font-mono">{session.name}</span>
+                  </div>
+                  <div class="text-[10px] text-slate-500">
+                    {session.running ? "running" : "stopped"} · {session.database_scope ? "database" : "server"} scoped
+                  </div>
+                </div>
+              </label>
+            )}
+          </For>
+
+          <Show when={report().files.length > 0}>
+            <div class="px-5 pt-4 pb-1 text-[11px] font-semibold text-slate-400 uppercase tracking-wide">
+              Files in the log directory
+            </div>
+            <div class="px-5 pb-2 text-[11px] text-slate-500">
+              SQL Server can't delete files; remove the ones not in use from the server's log directory.
+              <Show when={leftoverBytes() > 0}> {formatBytes(leftoverBytes())} can be freed.</Show>
+            </div>
+            <For each={report().files}>
+              {(file) => (
+                <div class="flex items-center gap-3 px-5 py-1.5 border-b border-slate-800/60">
+                  <span class="flex-1 min-w-0 font-mono text-slate-300 truncate select-text" title={file.path}>{file.path}</span>
+                  <Show when={file.in_use}>
+                    <span class="px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-slate-700 text-slate-400">In use</span>
+                  </Show>
+                  <span class="text-slate-500 tabular-nums">{formatBytes(file.size_bytes)}</span>
+                  <span class="text-slate-500 w-40 text-right">{formatDateTime(file.modified)}</span>
+                </div>
+              )}
+            </For>
+          </Show>
+          <Show when={report().files_error}>
+            <div class="px-5 py-2 text-[11px] text-slate-500">Trace files could not be listed: {report().files_error}</div>
+          </Show>
+        </div>
+
+        <div class="flex gap-3 justify-end px-5 py-3 border-t border-slate-800">
+          <button
+            type="button"
+            onClick={props.onClose}
+            disabled={cleaning()}
+            class="px-5 py-2 bg-slate-800 enabled:hover:bg-slate-700 disabled:opacity-40 text-slate-200 text-sm font-medium rounded transition-colors"
+          >
+            {report().traces.length + report().xe_sessions.length === 0 ? "Close" : "Leave them"}
+          </button>
+          <Show when={report().traces.length + report().xe_sessions.length > 0}>
+            <button
+              type="button"
+              onClick={() => void handleCleanUp()}
+              disabled={cleaning() || selectedCount() === 0}
+              class="px-5 py-2 bg-amber-600 enabled:hover:bg-amber-500 disabled:bg-slate-700 disabled:text-slate-500 text-white text-sm font-medium rounded transition-colors"
+            >
+              {cleaning() ? "Stopping..." : `Stop and remove ${selectedCount()}`}
+            </button>
+          </Show>
+        </div>
+      </div>
+    </div>
+  );
+}
@@ -4,6 +4,7 @@ import type { QueryEvent, QueryResultData } from "../lib/types.ts";
 
 interface Props {
   query: QueryEvent;
+  parent: QueryEvent | null;
   onClose: () => void;
 }
 
@@ -107,6 +108,10 @@ export default function QueryDetail(props: Props) {
   const [mounted, setMounted] = createSignal(false);
   const [runState, setRunState] = createSignal<RunState>({ status: "idle" });
   const [showConfirm, setShowConfirm] = createSignal(false);
+  const [now, setNow] = createSignal(Date.now());
+
+  const clock = window.setInterval(() => setNow(Date.now()), 500);
+  onCleanup(() => clearInterval(clock));
 
   let dragging = false;
   let startY = 0;
@@ -133,11 +138,30 @@ export default function QueryDetail(props: Props) {
   }
 
   function formatDuration(ms: number): string {
-    if (ms < 1000) return `${ms}ms`;
+    if (ms < 1) return `${Math.round(ms * 1000)}µs`;
+    if (ms < 10) return `${ms.toFixed(2)}ms`;
+    if (ms < 1000) return `${Math.round(ms)}ms`;
     if (ms < 60000) return `${(ms / 1000).toFixed(1)}s`;
     return `${(ms / 60000).toFixed(1)}m`;
   }
 
+  function eventTypeLabel(): string {
+    const name = props.query.event_name;
+    if (name === "attention") return "ATTN";
+    if (props.query.error && !name.includes("completed")) return "ERROR";
+    if (name.includes("rpc")) return "RPC";
+    if (name === "sp_statement_completed" || name === "sql_statement_completed") return "STMT";
+    return "BATCH";
+  }
+
+  function formatQueryDuration(): string {
+    if (props.query.event_status === "running") {
+      return formatDuration(Math.max(0, now() - Date.parse(props.query.captured_at)));
+    }
+    if (props.query.event_status === "unknown") return "?";
+    return formatDuration(props.query.elapsed_time_ms);
+  }
+
   function formatStartTimeParts(isoStr: string): { time: string; date: string } {
     if (!isoStr) return { time: "-", date: "-" };
 
@@ -256,13 +280,21 @@ export default function QueryDetail(props: Props) {
             {/* Event Type & Session */}
             <div class="flex items-center gap-3 px-4 border-r border-slate-700/50">
               <span class="px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-slate-700 text-slate-400">
-                {props.query.event_name === "rpc_completed"
-                  ? "RPC"
-                  : props.query.event_name === "sp_statement_completed" ||
-                    props.query.event_name === "sql_statement_completed"
-                    ? "STMT"
-                    : "BATCH"}
+                {eventTypeLabel()}
               </span>
+              <Show when={props.query.event_status !== "completed"}>
+                <span
+                  class={`px-1.5 py-0.5 rounded text-[10px] font-bold uppercase ${props.query.event_status === "running" || props.query.event_status === "cancelled"
+                    ? "bg-amber-500/15 text-amber-400"
+                    : props.query.event_status === "failed"
+                      ? "bg-red-500/15 text-red-400"
+                      : "bg-slate-700 text-slate-400"
+                    }`}
+                  title={props.query.event_status === "unknown" ? "The completion of this query was not captured" : undefined}
+                >
+                  {props.query.event_status}
+                </span>
+              </Show>
               <div class="flex flex-col justify-center">
                 <span class="text-[11px] font-semibold text-slate-100 tabular-nums">#{props.query.session_id}</span>
                 <span class="text-[9px] text-slate-500 uppercase tracking-tighter">Session</span>
@@ -285,8 +317,34 @@ export default function QueryDetail(props: Props) {
               </span>
             </div>
 
+            {/* Client */}
+            <Show when={props.query.nt_user_name || props.query.client_process_id || props.query.transaction_id}>
+              <div class="flex flex-col justify-center px-4 border-r border-slate-700/50 min-w-max">
+                <span class="text-[11px] font-medium text-slate-300 leading-tight">
+                  {props.query.nt_user_name || "-"}
+                  <Show when={props.query.client_process_id}>
+                    <span class="text-slate-500"> (PID {props.query.client_process_id})</span>
+                  </Show>
+                </span>
+                <span class="text-[9px] text-slate-500 tabular-nums">
+                  {props.query.transaction_id ? `Transaction ${props.query.transaction_id}` : "No transaction"}
+                </span>
+              </div>
+            </Show>
+
+            {/* Object */}
+            <Show when={props.query.object_name}>
+              <div class="flex flex-col justify-center px-4 border-r border-slate-700/50 min-w-max">
+                <span class="text-[11px] font-semibold text-slate-100">{props.query.object_name}</span>
+                <span class="text-[9px] text-slate-500 uppercase tracking-tighter">Object</span>
+              </div>
+            </Show>
+
             {/* Timestamp */}
-            <div class="flex flex-col justify-center px-4 border-r border-slate-700/50 min-w-max">
+            <div
+              class="flex flex-col justify-center px-4 border-r border-slate-700/50 min-w-max"
+              title={props.query.end_time ? `Ended ${props.query.end_time.replace("T", " ")}` : undefined}
+            >
               <span class="text-[11px] font-medium text-slate-300 tabular-nums leading-tight">
                 {formatStartTimeParts(props.query.start_time).time}
               </span>
@@ -298,11 +356,11 @@ export default function QueryDetail(props: Props) {
             {/* Stats */}
             <div class="flex items-stretch divide-x divide-slate-700/50 border-l border-r border-slate-700/50">
               <div class="flex flex-col items-center justify-center px-4 min-w-[70px]">
-                <span class="text-[11px] font-bold text-slate-100 tabular-nums">{formatDuration(props.query.elapsed_time)}</span>
+                <span class="text-[11px] font-bold text-slate-100 tabular-nums">{formatQueryDuration()}</span>
                 <span class="text-[9px] text-slate-500 uppercase tracking-wider">Duration</span>
               </div>
               <div class="flex flex-col items-center justify-center px-4 min-w-[60px]">
-                <span class="text-[11px] font-bold text-slate-100 tabular-nums">{formatDuration(props.query.cpu_time)}</span>
+                <span class="text-[11px] font-bold text-slate-100 tabular-nums">{formatDuration(props.query.cpu_time_ms)}</span>
                 <span class="text-[9px] text-slate-500 uppercase tracking-wider">CPU</span>
               </div>
               <div class="flex flex-col items-center justify-center px-4 min-w-[70px]">
@@ -310,7 +368,12 @@ export default function QueryDetail(props: Props) {
                 <span class="text-[9px] text-slate-500 uppercase tracking-wider">Reads</span>
               </div>
               <div class="flex flex-col items-center justify-center px-4 min-w-[70px]">
-                <span class="text-[11px] font-bold text-slate-100 tabular-nums">{props.query.physical_reads.toLocaleString()}</span>
+                <span
+                  class="text-[11px] font-bold text-slate-100 tabular-nums"
+                  title={props.query.physical_reads === null ? "Not available from SQL Trace" : undefined}
+                >
+                  {props.query.physical_reads?.toLocaleString() ?? "-"}
+                </span>
                 <span class="text-[9px] text-slate-500 uppercase tracking-wider">Physical</span>
               </div>
               <div class="flex flex-col items-center justify-center px-4 min-w-[60px]">
@@ -351,6 +414,17 @@ export default function QueryDetail(props: Props) {
 
       {/* SQL Text & Results */}
       <div ref={contentRef} class="flex-1 overflow-auto p-4 flex flex-col gap-6">
+        {props.query.error && (
+          <div>
+            <div class="text-[10px] text-slate-500 uppercase tracking-wider mb-1.5 font-medium">
+              Error {props.query.error.number} (severity {props.query.error.severity})
+            </div>
+            <div class="bg-red-950/30 rounded-lg p-4 border border-red-900/50 text-xs text-red-400 font-mono whitespace-pre-wrap break-words">
+              {props.query.error.message || "No message was captured for this error."}
+            </div>
+          </div>
+        )}
+
         <SqlBlock
           text={props.query.current_statement || props.query.sql_text}
           label="Statement"
@@ -364,6 +438,38 @@ export default function QueryDetail(props: Props) {
             />
           )}
 
+        {props.query.fingerprint && (
+          <SqlBlock
+            text={props.query.fingerprint}
+            label={`Fingerprint ${props.query.query_hash}`}
+          />
+        )}
+
+        {props.query.extra && Object.keys(props.query.extra).length > 0 && (
+          <div>
+            <div class="text-[10px] text-slate-500 uppercase tracking-wider mb-1.5 font-medium">
+              Imported Columns
+            </div>
+            <div class="bg-slate-800/40 rounded-lg p-3 border border-slate-700/50 grid grid-cols-[max-content_1fr] gap-x-4 gap-y-1 text-[11px]">
+              {Object.entries(props.query.extra).map(([name, value]) => (
+                <>
+                  <span class="text-slate-500">{name}</span>
+                  <span class="text-slate-300 font-mono break-all">
+                    {typeof value === "string" ? value : JSON.stringify(value)}
+                  </span>
+                </>
+              ))}
+            </div>
+          </div>
+        )}
+
+        {props.parent && (
+          <SqlBlock
+            text={props.parent.sql_text}
+            label={props.parent.event_name.includes("rpc") ? "Parent RPC" : "Parent Batch"}
+          />
+        )}
+
         {/* Query Results */}
         {runState().status === "error" && (
           <div>
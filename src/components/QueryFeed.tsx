@@ -1,17 +1,25 @@
-import { For, createEffect } from "solid-js";
+import { For, createEffect, createSignal, onCleanup } from "solid-js";
 import type { QueryEvent } from "../lib/types.ts";
 
 interface Props {
   queries: QueryEvent[];
+  statementCounts: Map<string, number>;
+  expandedParents: Set<string>;
   selectedId: string | null;
   autoScroll: "on" | "off" | "smart";
   connected: boolean;
   capturing: boolean;
   onSelect: (id: string | null) => void;
+  onToggleStatements: (id: string) => void;
 }
 
 export default function QueryFeed(props: Props) {
   let containerRef: HTMLDivElement | undefined;
+  const [now, setNow] = createSignal(Date.now());
+
+  // Drives the elapsed counter of running queries.
+  const clock = window.setInterval(() => setNow(Date.now()), 500);
+  onCleanup(() => clearInterval(clock));
 
   createEffect(() => {
     const len = props.queries.length;
@@ -55,11 +63,21 @@ export default function QueryFeed(props: Props) {
   }
 
   function formatDuration(ms: number): string {
-    if (ms < 1000) return `${ms}ms`;
+    if (ms < 1) return `${Math.round(ms * 1000)}µs`;
+    if (ms < 10) return `${ms.toFixed(2)}ms`;
+    if (ms < 1000) return `${Math.round(ms)}ms`;
     if (ms < 60000) return `${(ms / 1000).toFixed(1)}s`;
     return `${(ms / 60000).toFixed(1)}m`;
   }
 
+  function formatQueryDuration(query: QueryEvent): string {
+    if (query.event_status === "running") {
+      return formatDuration(Math.max(0, now() - Date.parse(query.captured_at)));
+    }
+    if (query.event_status === "unknown") return "?";
+    return formatDuration(query.elapsed_time_ms);
+  }
+
   function formatTime(isoStr: string): string {
     if (!isoStr) return "-";
 
@@ -77,6 +95,9 @@ export default function QueryFeed(props: Props) {
   }
 
   function formatEventType(eventName: string): string {
+    if (eventName === "attention") return "ATTN";
+    if (eventName.includes("error") || eventName === "exception") return "ERROR";
+    if (eventName.includes("statement")) return "STMT";
     if (eventName.includes("rpc")) return "RPC";
     if (eventName.includes("batch")) return "BATCH";
     return eventName;
@@ -86,6 +107,39 @@ export default function QueryFeed(props: Props) {
     return sql.replace(/\s+/g, " ").trim();
   }
 
+  function rowText(query: QueryEvent): string {
+    if (query.event_name === "attention") return "Attention: the client cancelled the request or it timed out";
+    if (query.error && !query.event_name.includes("completed")) {
+      return `Msg ${query.error.number}, Level ${query.error.severity}: ${cleanSql(query.error.message)}`;
+    }
+    return cleanSql(query.current_statement || query.sql_text);
+  }
+
+  function statusTitle(query: QueryEvent): string | undefined {
+    switch (query.event_status) {
+      case "running":
+        return "Still running";
+      case "unknown":
+        return "The completion of this query was not captured";
+      case "failed":
+        return query.error ? `Failed: Msg ${query.error.number}, ${query.error.message}` : "Failed";
+      case "cancelled":
+        return "Cancelled by the client (attention)";
+      default:
+        return undefined;
+    }
+  }
+
+  function statusClass(query: QueryEvent): string {
+    if (query.event_status === "failed" || (query.error && !query.event_name.includes("completed"))) {
+      return "border-l-2 border-l-red-500";
+    }
+    if (query.event_status === "cancelled" || query.event_name === "attention") {
+      return "border-l-2 border-l-amber-500";
+    }
+    return "border-l-2 border-l-transparent";
+  }
+
   return (
     <div
       ref={containerRef}
@@ -126,13 +180,28 @@ export default function QueryFeed(props: Props) {
         <For each={props.queries}>
           {(query) => (
             <div
-              class={`grid grid-cols-[50px_80px_70px_140px_1fr_80px_80px_80px] gap-px cursor-pointer border-b border-slate-800/50 text-xs transition-colors ${props.selectedId === query.id
+              class={`grid grid-cols-[50px_80px_70px_140px_1fr_80px_80px_80px] gap-px cursor-pointer border-b border-slate-800/50 text-xs transition-colors ${statusClass(query)} ${props.selectedId === query.id
                 ? "bg-blue-600/15 text-slate-100"
                 : "hover:bg-slate-800/50 text-slate-300"
                 }`}
               onClick={() => props.onSelect(props.selectedId === query.id ? null : query.id)}
             >
-              <div class="px-2 py-1.5 text-slate-500">
+              <div class="px-2 py-1.5 text-slate-500 flex items-center gap-1">
+                {(props.statementCounts.get(query.id) ?? 0) > 0 && (
+                  <button
+                    type="button"
+                    class="w-3 text-slate-400 hover:text-slate-200"
+                    onClick={(e) => {
+                      e.stopPropagation();
+                      props.onToggleStatements(query.id);
+                    }}
+                    title={`${props.statementCounts.get(query.id)} statement(s)`}
+                  >
+                    <i
+                      class={`fa-solid ${props.expandedParents.has(query.id) ? "fa-caret-down" : "fa-caret-right"} text-[10px]`}
+                    />
+                  </button>
+                )}
                 {formatEventType(query.event_name)}
               </div>
               <div class="px-2 py-1.5 tabular-nums text-slate-400">
@@ -142,14 +211,25 @@ export default function QueryFeed(props: Props) {
               <div class="px-2 py-1.5 truncate text-slate-400">
                 {query.database_name}
               </div>
-              <div class="px-2 py-1.5 truncate font-mono text-[11px]">
-                {cleanSql(query.current_statement || query.sql_text)}
+              <div
+                class={`px-2 py-1.5 truncate font-mono text-[11px] ${query.parent_id && props.expandedParents.has(query.parent_id) ? "pl-6 text-slate-400" : ""}`}
+              >
+                {query.parent_id && props.expandedParents.has(query.parent_id) && (
+                  <i class="fa-solid fa-turn-up fa-rotate-90 text-[9px] text-slate-600 mr-1.5" />
+                )}
+                {rowText(query)}
               </div>
-              <div class="px-2 py-1.5 text-right tabular-nums">
-                {formatDuration(query.elapsed_time)}
+              <div
+                class={`px-2 py-1.5 text-right tabular-nums ${query.event_status === "running" ? "text-amber-400" : ""}`}
+                title={statusTitle(query)}
+              >
+                {query.event_status === "running" && (
+                  <i class="fa-solid fa-spinner fa-spin text-[9px] mr-1.5" />
+                )}
+                {formatQueryDuration(query)}
               </div>
               <div class="px-2 py-1.5 text-right tabular-nums">
-                {formatDuration(query.cpu_time)}
+                {formatDuration(query.cpu_time_ms)}
               </div>
               <div class="px-2 py-1.5 text-right tabular-nums">
                 {query.logical_reads.toLocaleString()}
@@ -0,0 +1,298 @@
+import { invoke } from "@tauri-apps/api/core";
+import { For, Show, createSignal, onCleanup, onMount } from "solid-js";
+import type { CaptureFilter, SessionInfo } from "../lib/types.ts";
+import ExportSessionDialog from "./ExportSessionDialog.tsx";
+import ImportSessionDialog from "./ImportSessionDialog.tsx";
+
+interface Props {
+  capturing: boolean;
+  // The session the feed was loaded from, and the ids of the rows it shows.
+  openedSessionId: string | null;
+  visibleEventIds: string[];
+  onOpen: (id: string) => Promise<void>;
+  onClose: () => void;
+}
+
+const SOURCE_LABELS: Record<string, string> = {
+  sql_trace: "SQL Trace",
+  xe_ring_buffer: "XE ring buffer",
+  xe_event_file: "XE event file",
+  trace_file: "Trace file",
+  xel_file: "XEL file",
+  import: "Imported file",
+};
+
+function formatDateTime(isoStr: string | null): string {
+  if (!isoStr) return "-";
+  const date = new Date(isoStr);
+  return Number.isNaN(date.getTime()) ? isoStr : date.toLocaleString();
+}
+
+function formatSpan(session: SessionInfo): string {
+  if (!session.stopped_at) return "recording";
+  const ms = new Date(session.stopped_at).getTime() - new Date(session.started_at).getTime();
+  if (!Number.isFinite(ms) || ms < 0) return "-";
+  const minutes = Math.floor(ms / 60000);
+  if (minutes < 1) return `${Math.round(ms / 1000)}s`;
+  if (minutes < 60) return `${minutes}m`;
+  return `${Math.floor(minutes / 60)}h ${minutes % 60}m`;
+}
+
+function describeFilter(filter: Partial<CaptureFilter> | undefined): string {
+  if (!filter) return "";
+  const parts: string[] = [];
+  if (filter.database_name) parts.push(`db ${filter.database_name}`);
+  if (filter.login_name) parts.push(`login ${filter.login_name}`);
+  if (filter.host_name) parts.push(`host ${filter.host_name}`);
+  if (filter.program_name) parts.push(`app ${filter.program_name}`);
+  if (filter.min_duration_ms != null) parts.push(`≥ ${filter.min_duration_ms}ms`);
+  if (filter.min_logical_reads != null) parts.push(`≥ ${filter.min_logical_reads} reads`);
+  if (filter.text_like) parts.push(`text ${filter.text_like}`);
+  return parts.join(", ");
+}
+
+export default function SessionsDialog(props: Props) {
+  const [sessions, setSessions] = createSignal<SessionInfo[]>([]);
+  const [loading, setLoading] = createSignal(true);
+  const [error, setError] = createSignal<string | null>(null);
+  const [editingId, setEditingId] = createSignal<string | null>(null);
+  const [editName, setEditName] = createSignal("");
+  const [confirmDeleteId, setConfirmDeleteId] = createSignal<string | null>(null);
+  const [openingId, setOpeningId] = createSignal<string | null>(null);
+  const [exporting, setExporting] = createSignal<SessionInfo | null>(null);
+  const [importing, setImporting] = createSignal(false);
+
+  async function refresh() {
+    try {
+      setSessions(await invoke<SessionInfo[]>("list_sessions"));
+      setError(null);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setLoading(false);
+    }
+  }
+
+  onMount(() => {
+    void refresh();
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape" && editingId() === null && exporting() === null && !importing()) {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  async function handleOpen(id: string) {
+    setOpeningId(id);
+    try {
+      await props.onOpen(id);
+    } catch (e) {
+      setError(String(e));
+    } finally {
+      setOpeningId(null);
+    }
+  }
+
+  async function handleRename(id: string) {
+    const name = editName().trim();
+    setEditingId(null);
+    if (!name || name === sessions().find((s) => s.id === id)?.name) return;
+    try {
+      await invoke("rename_session", { id, name });
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
+  async function handleDelete(id: string) {
+    setConfirmDeleteId(null);
+    try {
+      await invoke("delete_session", { id });
+      await refresh();
+    } catch (e) {
+      setError(String(e));
+    }
+  }
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-4xl h-[75vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="flex items-center gap-3 px-5 py-3 border-b border-slate-800">
+          <div class="w-8 h-8 rounded-full bg-blue-500/10 flex items-center justify-center shrink-0">
+            <i class="fa-solid fa-clock-rotate-left text-blue-400 text-xs" />
+          </div>
+          <h2 class="text-sm font-semibold text-slate-100 flex-1">Saved Sessions</h2>
+          <button
+            type="button"
+            onClick={() => setImporting(true)}
+            class="flex items-center gap-1.5 px-3 py-1.5 text-xs font-medium rounded bg-slate-700 hover:bg-slate-600 text-slate-200 transition-colors"
+            title="Import a CSV or JSON Lines export"
+          >
+            <i class="fa-solid fa-file-import text-[10px]" />
+            Import
+          </button>
+          <button
+            type="button"
+            onClick={props.onClose}
+            class="text-slate-500 hover:text-slate-200 w-8 h-8 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+            title="Close"
+          >
+            <i class="fa-solid fa-xmark" />
+          </button>
+        </div>
+
+        <Show when={error()}>
+          <div class="px-5 py-2 text-xs text-red-400 border-b border-slate-800">{error()}</div>
+        </Show>
+        <Show when={props.capturing}>
+          <div class="px-5 py-2 text-xs text-amber-400 border-b border-slate-800">
+            Stop the capture to open a saved session.
+          </div>
+        </Show>
+
+        <div class="flex-1 overflow-auto">
+          <Show
+            when={sessions().length > 0}
+            fallback={
+              <div class="h-full flex items-center justify-center text-xs text-slate-500">
+                {loading() ? "Loading..." : "No saved sessions yet. Live captures are saved automatically."}
+              </div>
+            }
+          >
+            <For each={sessions()}>
+              {(session) => (
+                <div class="flex items-center gap-3 px-5 py-2.5 border-b border-slate-800/60 hover:bg-slate-800/40">
+                  <div class="flex-1 min-w-0">
+                    <Show
+                      when={editingId() === session.id}
+                      fallback={
+                        <div class="text-xs font-medium text-slate-200 truncate" title={session.name}>
+                          {session.name}
+                          <Show when={session.read_only}>
+                            <span class="ml-2 px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-slate-700 text-slate-400">
+                              Imported
+                            </span>
+                          </Show>
+                          <Show when={!session.stopped_at}>
+                            <span class="ml-2 px-1.5 py-0.5 rounded text-[10px] font-bold uppercase bg-red-500/15 text-red-400">
+                              Recording
+                            </span>
+                          </Show>
+                        </div>
+                      }
+                    >
+                      <input
+                        type="text"
+                        value={editName()}
+                        onInput={(e) => setEditName(e.currentTarget.value)}
+                        onKeyDown={(e) => {
+                          if (e.key === "Enter") void handleRename(session.id);
+                          if (e.key === "Escape") setEditingId(null);
+                        }}
+                        onBlur={() => void handleRename(session.id)}
+                        ref={(el) => setTimeout(() => el.select())}
+                        class="w-full bg-slate-800 border border-slate-700 rounded px-2 py-1 text-xs text-slate-100 focus:outline-none focus:border-blue-500"
+                      />
+                    </Show>
+                    <div class="text-[10px] text-slate-500 truncate">
+                      {session.server_name || "-"} · {SOURCE_LABELS[session.source?.kind] ?? session.source?.kind ?? "-"}
+                      {" "}· {formatDateTime(session.started_at)} · {formatSpan(session)}
+                      {" "}· {session.event_count.toLocaleString()} events
+                      <Show when={describeFilter(session.options?.filter)}>
+                        {(filter) => <span> · {filter()}</span>}
+                      </Show>
+                    </div>
+                  </div>
+
+                  <Show
+                    when={confirmDeleteId() === session.id}
+                    fallback={
+                      <div class="flex items-center gap-1 shrink-0">
+                        <button
+                          type="button"
+                          disabled={props.capturing || openingId() !== null}
+                          onClick={() => void handleOpen(session.id)}
+                          class="px-3 py-1 text-xs font-medium rounded bg-blue-600 enabled:hover:bg-blue-500 text-white disabled:opacity-40 disabled:cursor-not-allowed"
+                        >
+                          {openingId() === session.id ? "Opening..." : "Open"}
+                        </button>
+                        <button
+                          type="button"
+                          onClick={() => setExporting(session)}
+                          class="text-slate-500 hover:text-slate-200 w-7 h-7 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+                          title="Export"
+                        >
+                          <i class="fa-solid fa-file-export text-[10px]" />
+                        </button>
+                        <button
+                          type="button"
+                          onClick={() => {
+                            setEditName(session.name);
+                            setEditingId(session.id);
+                          }}
+                          class="text-slate-500 hover:text-slate-200 w-7 h-7 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+                          title="Rename"
+                        >
+                          <i class="fa-solid fa-pen text-[10px]" />
+                        </button>
+                        <button
+                          type="button"
+                          onClick={() => setConfirmDeleteId(session.id)}
+                          class="text-slate-500 hover:text-red-400 w-7 h-7 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+                          title="Delete"
+                        >
+                          <i class="fa-solid fa-trash-can text-[10px]" />
+                        </button>
+                      </div>
+                    }
+                  >
+                    <div class="flex items-center gap-1 shrink-0">
+                      <span class="text-[11px] text-slate-400 mr-1">Delete?</span>
+                      <button
+                        type="button"
+                        onClick={() => void handleDelete(session.id)}
+                        class="px-2.5 py-1 text-xs font-medium rounded bg-red-600 hover:bg-red-500 text-white"
+                      >
+                        Delete
+                      </button>
+                      <button
+                        type="button"
+                        onClick={() => setConfirmDeleteId(null)}
+                        class="px-2.5 py-1 text-xs font-medium rounded bg-slate-700 hover:bg-slate-600 text-slate-200"
+                      >
+                        Cancel
+                      </button>
+                    </div>
+                  </Show>
+                </div>
+              )}
+            </For>
+          </Show>
+        </div>
+      </div>
+
+      <Show when={importing()}>
+        <ImportSessionDialog
+          onImported={() => {
+            setImporting(false);
+            void refresh();
+          }}
+          onCancel={() => setImporting(false)}
+        />
+      </Show>
+
+      <Show when={exporting()} keyed>
+        {(session) => (
+          <ExportSessionDialog
+            session={session}
+            visibleEventIds={session.id === props.openedSessionId ? props.visibleEventIds : null}
+            onClose={() => setExporting(null)}
+          />
+        )}
+      </Show>
+    </div>
+  );
+}
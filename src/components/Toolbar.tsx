@@ -1,19 +1,41 @@
+import Dropdown from "./Dropdown.tsx";
+
 interface Props {
   connected: boolean;
   capturing: boolean;
   queryCount: number;
+  deadlockCount: number;
+  blockedCount: number;
+  distinctQueryCount: number;
+  captureSource: string;
+  statementLevel: boolean;
+  captureFilterCount: number;
+  captureFilesCustomized: boolean;
   filterText: string;
   advancedFilterCount: number;
   autoScroll: "on" | "off" | "smart";
   deduplicateRepeats: boolean;
   error: string | null;
+  reconnectAttempt: number | null;
+  behind: boolean;
   onStartCapture: () => void;
+  onCaptureSourceChange: (value: string) => void;
+  onToggleStatementLevel: () => void;
+  onOpenCaptureFilter: () => void;
+  onOpenCaptureFiles: () => void;
+  onOpenFile: () => void;
+  onOpenSessions: () => void;
   onStopCapture: () => void;
   onClear: () => void;
   onFilterChange: (value: string) => void;
   onOpenAdvancedFilter: () => void;
+  onOpenDeadlocks: () => void;
+  onOpenBlocking: () => void;
+  onOpenTopQueries: () => void;
   onToggleAutoScroll: () => void;
   onToggleDeduplicateRepeats: () => void;
+  liveEventLimit: number;
+  onLiveEventLimitChange: (limit: number) => void;
 }
 
 export default function Toolbar(props: Props) {
@@ -24,6 +46,67 @@ export default function Toolbar(props: Props) {
     <div class="flex flex-col bg-slate-800/60 border-b border-slate-700">
       <div class="flex items-center gap-2 px-3 py-2">
         <div class="flex items-center gap-1.5">
+          {!props.capturing && (
+            <div class="w-[170px]" title="Capture source">
+              <Dropdown
+                value={props.captureSource}
+                options={[
+                  { value: "sql_trace", label: "SQL Trace" },
+                  { value: "xe_ring_buffer", label: "XEvents (ring buffer)" },
+                  { value: "xe_event_file", label: "XEvents (event file)" },
+                ]}
+                onChange={props.onCaptureSourceChange}
+              />
+            </div>
+          )}
+
+          {!props.capturing && (
+            <button
+              class={`${btnBase} min-w-0 ${props.statementLevel
+                ? "bg-blue-600/20 text-blue-400 border-blue-500/40"
+                : "bg-slate-700 text-slate-400"
+                }`}
+              onClick={props.onToggleStatementLevel}
+              title="Also capture each statement inside batches and stored procedures (adds load on busy servers)"
+            >
+              <i class="fa-solid fa-list-ol text-[10px]" />
+              Statements
+            </button>
+          )}
+
+          {!props.capturing && (
+            <button
+              class={`${btnBase} min-w-0 ${props.captureFilterCount > 0
+                ? "bg-blue-600/20 text-blue-400 border-blue-500/40"
+                : "bg-slate-700 text-slate-400"
+                }`}
+              onClick={props.onOpenCaptureFilter}
+              title="Filter on the server, before events are collected"
+            >
+              <i class="fa-solid fa-server text-[10px]" />
+              Server filter
+              {props.captureFilterCount > 0 && (
+                <span class="flex items-center justify-center bg-blue-500 text-white text-[9px] font-bold rounded-full w-4 h-4">
+                  {props.captureFilterCount}
+                </span>
+              )}
+            </button>
+          )}
+
+          {!props.capturing && (
+            <button
+              class={`${btnBase} min-w-0 ${props.captureFilesCustomized
+                ? "bg-blue-600/20 text-blue-400 border-blue-500/40"
+                : "bg-slate-700 text-slate-400"
+                }`}
+              onClick={props.onOpenCaptureFiles}
+              title="Where the capture writes on the server, file size, rollover and stop time"
+            >
+              <i class="fa-solid fa-hard-drive text-[10px]" />
+              Files
+            </button>
+          )}
+
           {!props.capturing ? (
             <button
               class={`${btnBase} bg-emerald-600 enabled:hover:bg-emerald-500 text-white`}
@@ -43,6 +126,25 @@ export default function Toolbar(props: Props) {
             </button>
           )}
 
+          <button
+            class={`${btnBase} bg-slate-700 enabled:hover:bg-slate-600 text-slate-200`}
+            disabled={props.capturing}
+            onClick={props.onOpenFile}
+            title="Open a capture file"
+          >
+            <i class="fa-solid fa-folder-open text-[10px]" />
+            Open
+          </button>
+
+          <button
+            class={`${btnBase} bg-slate-700 enabled:hover:bg-slate-600 text-slate-200`}
+            onClick={props.onOpenSessions}
+            title="Browse saved capture sessions"
+          >
+            <i class="fa-solid fa-clock-rotate-left text-[10px]" />
+            Sessions
+          </button>
+
           <button
             class={`${btnBase} bg-slate-700 enabled:hover:bg-slate-600 text-slate-200`}
             disabled={props.queryCount === 0}
@@ -82,6 +184,68 @@ export default function Toolbar(props: Props) {
           </button>
         </div>
 
+        {props.deadlockCount > 0 && (
+          <button
+            class={`${btnBase} min-w-0 bg-red-600/20 text-red-400 border-red-500/40 hover:bg-red-600/30`}
+            onClick={props.onOpenDeadlocks}
+            title="Show captured deadlocks"
+          >
+            <i class="fa-solid fa-lock text-[10px]" />
+            Deadlocks
+            <span class="flex items-center justify-center bg-red-500 text-white text-[9px] font-bold rounded-full min-w-4 h-4 px-1">
+              {props.deadlockCount}
+            </span>
+          </button>
+        )}
+
+        {(props.connected || props.blockedCount > 0) && (
+          <button
+            class={`${btnBase} min-w-0 ${props.blockedCount > 0
+              ? "bg-amber-600/20 text-amber-400 border-amber-500/40 hover:bg-amber-600/30"
+              : "bg-slate-700 text-slate-400 hover:bg-slate-600"
+              }`}
+            onClick={props.onOpenBlocking}
+            title="Show blocking chains"
+          >
+            <i class="fa-solid fa-hourglass-half text-[10px]" />
+            Blocking
+            {props.blockedCount > 0 && (
+              <span class="flex items-center justify-center bg-amber-500 text-white text-[9px] font-bold rounded-full min-w-4 h-4 px-1">
+                {props.blockedCount}
+              </span>
+            )}
+          </button>
+        )}
+
+        {(props.connected || props.distinctQueryCount > 0) && (
+          <button
+            class={`${btnBase} min-w-0 bg-slate-700 text-slate-300 hover:bg-slate-600`}
+            onClick={props.onOpenTopQueries}
+            title="Show aggregated statistics per query fingerprint"
+          >
+            <i class="fa-solid fa-ranking-star text-[10px]" />
+            Top queries
+            {props.distinctQueryCount > 0 && (
+              <span class="flex items-center justify-center bg-slate-500 text-white text-[9px] font-bold rounded-full min-w-4 h-4 px-1">
+                {props.distinctQueryCount}
+              </span>
+            )}
+          </button>
+        )}
+
+        <div class="w-[110px]" title="Events kept in the feed; older ones stay in the session">
+          <Dropdown
+            value={String(props.liveEventLimit)}
+            options={[
+              { value: "1000", label: "Keep 1k" },
+              { value: "5000", label: "Keep 5k" },
+              { value: "20000", label: "Keep 20k" },
+              { value: "50000", label: "Keep 50k" },
+            ]}
+            onChange={(value) => props.onLiveEventLimitChange(Number(value))}
+          />
+        </div>
+
         <button
           class={`${btnBase} ${props.deduplicateRepeats
             ? "bg-blue-600/20 text-blue-400 border-blue-500/40"
@@ -113,7 +277,27 @@ export default function Toolbar(props: Props) {
         </button>
       </div>
 
-      {props.error && (
+      {props.reconnectAttempt != null && (
+        <div class="mx-3 mb-2 p-2.5 bg-amber-500/10 border border-amber-500/20 rounded text-xs text-amber-400 select-text flex items-start gap-2 animate-in fade-in slide-in-from-top-1 duration-200">
+          <i class="fa-solid fa-rotate fa-spin mt-0.5" />
+          <div class="flex-1 leading-relaxed">
+            Connection lost, reconnecting (attempt {props.reconnectAttempt}). The capture resumes where it left off.
+            <div class="text-amber-400/70">{props.error}</div>
+          </div>
+        </div>
+      )}
+
+      {props.behind && props.reconnectAttempt == null && (
+        <div class="mx-3 mb-2 p-2.5 bg-amber-500/10 border border-amber-500/20 rounded text-xs text-amber-400 select-text flex items-start gap-2 animate-in fade-in slide-in-from-top-1 duration-200">
+          <i class="fa-solid fa-gauge-high mt-0.5" />
+          <div class="flex-1 leading-relaxed">
+            Falling behind: the server produces events faster than the capture reads them, so they show up late.
+            A server filter reduces the load.
+          </div>
+        </div>
+      )}
+
+      {props.error && props.reconnectAttempt == null && (
         <div class="mx-3 mb-2 p-2.5 bg-red-500/10 border border-red-500/20 rounded text-xs text-red-400 select-text flex items-start gap-2 animate-in fade-in slide-in-from-top-1 duration-200">
           <i class="fa-solid fa-circle-exclamation mt-0.5" />
           <div class="flex-1 leading-relaxed">
@@ -0,0 +1,183 @@
+import { invoke } from "@tauri-apps/api/core";
+import { For, Show, createEffect, createSignal, onCleanup, onMount } from "solid-js";
+import type { QueryAggregate, QueryStatsOrder, QueryStatsSnapshot } from "../lib/types.ts";
+
+interface Props {
+  snapshot: QueryStatsSnapshot | null;
+  onFilterByHash: (queryHash: string) => void;
+  onClose: () => void;
+}
+
+const TOP_QUERIES_LIMIT = 200;
+
+interface Column {
+  label: string;
+  title: string;
+  order: QueryStatsOrder | null;
+  value: (q: QueryAggregate) => string;
+}
+
+function formatMicros(us: number): string {
+  const ms = us / 1000;
+  if (ms < 1) return `${Math.round(us)}µs`;
+  if (ms < 10) return `${ms.toFixed(2)}ms`;
+  if (ms < 1000) return `${Math.round(ms)}ms`;
+  if (ms < 60000) return `${(ms / 1000).toFixed(1)}s`;
+  return `${(ms / 60000).toFixed(1)}m`;
+}
+
+function formatCount(value: number): string {
+  return Math.round(value).toLocaleString();
+}
+
+const COLUMNS: Column[] = [
+  { label: "Execs", title: "Executions", order: "executions", value: (q) => formatCount(q.executions) },
+  { label: "Total", title: "Total duration", order: "total_duration", value: (q) => formatMicros(q.duration.total) },
+  { label: "Avg", title: "Average duration", order: "avg_duration", value: (q) => formatMicros(q.duration.avg) },
+  { label: "P50", title: "Median duration", order: null, value: (q) => formatMicros(q.duration.p50) },
+  { label: "P95", title: "95th percentile duration", order: "p95_duration", value: (q) => formatMicros(q.duration.p95) },
+  { label: "P99", title: "99th percentile duration", order: null, value: (q) => formatMicros(q.duration.p99) },
+  { label: "Max", title: "Maximum duration", order: "max_duration", value: (q) => formatMicros(q.duration.max) },
+  { label: "CPU", title: "Total CPU", order: "total_cpu", value: (q) => formatMicros(q.cpu_time.total) },
+  { label: "Avg CPU", title: "Average CPU", order: "avg_cpu", value: (q) => formatMicros(q.cpu_time.avg) },
+  { label: "Reads", title: "Total logical reads", order: "total_reads", value: (q) => formatCount(q.logical_reads.total) },
+  { label: "Writes", title: "Total writes", order: "total_writes", value: (q) => formatCount(q.writes.total) },
+  { label: "Rows", title: "Total rows", order: "total_rows", value: (q) => formatCount(q.row_count.total) },
+];
+
+export default function TopQueriesDialog(props: Props) {
+  const [order, setOrder] = createSignal<QueryStatsOrder>(
+    (localStorage.getItem("top-queries-order") as QueryStatsOrder | null) ?? "total_duration"
+  );
+  const [queries, setQueries] = createSignal<QueryAggregate[]>([]);
+  const [error, setError] = createSignal<string | null>(null);
+
+  onMount(() => {
+    const handleKeyDown = (e: KeyboardEvent) => {
+      if (e.key === "Escape") {
+        props.onClose();
+      }
+    };
+    window.addEventListener("keydown", handleKeyDown);
+    onCleanup(() => window.removeEventListener("keydown", handleKeyDown));
+  });
+
+  // Re-query whenever the order changes or the backend reports new totals.
+  createEffect(() => {
+    const current = order();
+    void props.snapshot;
+    localStorage.setItem("top-queries-order", current);
+    invoke<QueryAggregate[]>("get_query_stats", { order: current, limit: TOP_QUERIES_LIMIT })
+      .then((result) => {
+        setQueries(result);
+        setError(null);
+      })
+      .catch((e) => setError(String(e)));
+  });
+
+  return (
+    <div class="absolute inset-0 z-[60] flex items-center justify-center bg-slate-900/80 backdrop-blur-sm">
+      <div class="w-full max-w-6xl h-[80vh] bg-slate-900 border border-slate-800 rounded-xl shadow-2xl flex flex-col overflow-hidden">
+        <div class="flex items-center gap-3 px-5 py-3 border-b border-slate-800">
+          <div class="w-8 h-8 rounded-full bg-blue-500/10 flex items-center justify-center shrink-0">
+            <i class="fa-solid fa-ranking-star text-blue-400 text-xs" />
+          </div>
+          <h2 class="text-sm font-semibold text-slate-100 flex-1">
+            Top Queries
+            <Show when={props.snapshot}>
+              {(snapshot) => (
+                <span class="text-slate-500 font-normal">
+                  {" "}({snapshot().distinct_queries.toLocaleString()} distinct, {snapshot().executions.toLocaleString()} executions)
+                </span>
+              )}
+            </Show>
+          </h2>
+          <button
+            type="button"
+            onClick={props.onClose}
+            class="text-slate-500 hover:text-slate-200 w-8 h-8 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+            title="Close"
+          >
+            <i class="fa-solid fa-xmark" />
+          </button>
+        </div>
+
+        <Show when={error()}>
+          <div class="px-5 py-2 text-xs text-red-400 border-b border-slate-800">{error()}</div>
+        </Show>
+
+        <div class="flex-1 overflow-auto">
+          <Show
+            when={queries().length > 0}
+            fallback={
+              <div class="h-full flex items-center justify-center text-xs text-slate-500">
+                No completed queries captured yet.
+              </div>
+            }
+          >
+            <table class="w-full text-xs">
+              <thead class="sticky top-0 bg-slate-900 z-10">
+                <tr class="text-[10px] text-slate-500 uppercase tracking-wider border-b border-slate-800">
+                  <th class="text-left font-medium px-3 py-2">Query</th>
+                  <For each={COLUMNS}>
+                    {(column) => (
+                      <th
+                        class={`text-right font-medium px-2 py-2 whitespace-nowrap ${column.order ? "cursor-pointer hover:text-slate-300" : ""} ${column.order && order() === column.order ? "text-blue-400" : ""}`}
+                        title={column.title}
+                        onClick={() => column.order && setOrder(column.order)}
+                      >
+                        {column.label}
+                        <Show when={column.order && order() === column.order}>
+                          <i class="fa-solid fa-caret-down ml-1" />
+                        </Show>
+                      </th>
+                    )}
+                  </For>
+                  <th class="w-8" />
+                </tr>
+              </thead>
+              <tbody>
+                <For each={queries()}>
+                  {(query) => (
+                    <tr class="border-b border-slate-800/60 hover:bg-slate-800/40">
+                      <td class="px-3 py-1.5 max-w-[420px]">
+                        <div class="font-mono text-[11px] text-slate-200 truncate" title={query.fingerprint}>
+                          {query.fingerprint}
+                        </div>
+                        <div class="text-[10px] text-slate-500 truncate">
+                          {query.database_name || "-"} · {query.query_hash}
+                          <Show when={query.failures > 0}>
+                            <span class="text-red-400"> · {query.failures.toLocaleString()} failed</span>
+                          </Show>
+                        </div>
+                      </td>
+                      <For each={COLUMNS}>
+                        {(column) => (
+                          <td
+                            class={`text-right px-2 py-1.5 tabular-nums whitespace-nowrap ${column.order && order() === column.order ? "text-slate-100 font-semibold" : "text-slate-400"}`}
+                          >
+                            {column.value(query)}
+                          </td>
+                        )}
+                      </For>
+                      <td class="px-2">
+                        <button
+                          type="button"
+                          onClick={() => props.onFilterByHash(query.query_hash)}
+                          class="text-slate-500 hover:text-blue-400 w-6 h-6 flex items-center justify-center rounded hover:bg-slate-800 transition-colors"
+                          title="Show executions in the feed"
+                        >
+                          <i class="fa-solid fa-filter text-[10px]" />
+                        </button>
+                      </td>
+                    </tr>
+                  )}
+                </For>
+              </tbody>
+            </table>
+          </Show>
+        </div>
+      </div>
+    </div>
+  );
+}
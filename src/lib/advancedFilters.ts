@@ -52,13 +52,20 @@ const COMPARABLE_OPERATOR_OPTIONS: AdvancedFilterOperatorOption[] = [
 
 export const ADVANCED_FILTER_COLUMNS: AdvancedFilterColumn[] = [
   { key: "event_name", label: "Type", type: "string" },
+  { key: "event_status", label: "Status", type: "string" },
   { key: "start_time", label: "Start time", type: "datetime" },
+  { key: "end_time", label: "End time", type: "datetime" },
   { key: "session_id", label: "Session", type: "number" },
   { key: "database_name", label: "Database", type: "string" },
+  { key: "object_name", label: "Object", type: "string" },
   { key: "sql_text", label: "SQL text", type: "string" },
   { key: "current_statement", label: "Current statement", type: "string" },
-  { key: "elapsed_time", label: "Duration (ms)", type: "number" },
-  { key: "cpu_time", label: "CPU (ms)", type: "number" },
+  { key: "fingerprint", label: "Fingerprint", type: "string" },
+  { key: "query_hash", label: "Query hash", type: "string" },
+  { key: "elapsed_time_ms", label: "Duration (ms)", type: "number" },
+  { key: "elapsed_time", label: "Duration (µs)", type: "number" },
+  { key: "cpu_time_ms", label: "CPU (ms)", type: "number" },
+  { key: "cpu_time", label: "CPU (µs)", type: "number" },
   { key: "logical_reads", label: "Logical reads", type: "number" },
   { key: "physical_reads", label: "Physical reads", type: "number" },
   { key: "writes", label: "Writes", type: "number" },
@@ -66,6 +73,10 @@ export const ADVANCED_FILTER_COLUMNS: AdvancedFilterColumn[] = [
   { key: "login_name", label: "Login", type: "string" },
   { key: "host_name", label: "Host", type: "string" },
   { key: "program_name", label: "Program", type: "string" },
+  { key: "nt_user_name", label: "NT user", type: "string" },
+  { key: "client_process_id", label: "Client PID", type: "number" },
+  { key: "transaction_id", label: "Transaction ID", type: "number" },
+  { key: "error", label: "Error", type: "string" },
   { key: "captured_at", label: "Captured at", type: "datetime" },
 ];
 
@@ -113,6 +124,19 @@ export function createFilterCondition(
   };
 }
 
+// Duration and CPU used to be whole milliseconds under these keys.
+const MIGRATED_COLUMNS: Partial<Record<string, keyof QueryEvent>> = {
+  elapsed_time: "elapsed_time_ms",
+  cpu_time: "cpu_time_ms",
+};
+
+export function migrateStoredFilters(filters: AdvancedFilterCondition[]): AdvancedFilterCondition[] {
+  return filters.map((filter) => ({
+    ...filter,
+    column: MIGRATED_COLUMNS[filter.column] ?? filter.column,
+  }));
+}
+
 export function normalizeFilters(filters: AdvancedFilterCondition[]): AdvancedFilterCondition[] {
   return filters
     .map((filter) => ({ ...filter, value: filter.value.trim() }))
@@ -121,11 +145,11 @@ export function normalizeFilters(filters: AdvancedFilterCondition[]): AdvancedFi
 
 export function evaluateFilter(query: QueryEvent, filter: AdvancedFilterCondition): boolean {
   const column = getColumnDefinition(filter.column);
-  const rawValue = query[filter.column];
+  const rawValue = getFilterValue(query, filter.column);
 
   if (column.type === "number") {
     return compareNumbers(
-      typeof rawValue === "number" ? rawValue : Number(rawValue),
+      typeof rawValue === "number" ? rawValue : rawValue === null ? Number.NaN : Number(rawValue),
       Number(filter.value),
       filter.operator,
     );
@@ -142,6 +166,14 @@ export function evaluateFilter(query: QueryEvent, filter: AdvancedFilterConditio
   return compareStrings(String(rawValue ?? ""), filter.value, filter.operator);
 }
 
+// Errors match on "<number> <message>", so both can be filtered on.
+function getFilterValue(query: QueryEvent, column: keyof QueryEvent): unknown {
+  if (column === "error") {
+    return query.error ? `${query.error.number} ${query.error.message}` : "";
+  }
+  return query[column];
+}
+
 function compareStrings(
   leftInput: string,
   rightInput: string,
@@ -0,0 +1,83 @@
+import type { BlockedProcessReport, BlockingProcess, QueryEvent } from "./types.ts";
+
+export interface BlockingChainNode {
+  sessionId: number;
+  // What the latest report says about this session.
+  process: BlockingProcess | null;
+  // The request this session is running (or last ran) according to the feed.
+  query: QueryEvent | null;
+  blocked: BlockingChainNode[];
+}
+
+/**
+ * Rebuilds blocking chains from blocker/blocked pairs. Only the latest report
+ * per blocked session counts, and a pair is dropped once the feed shows the
+ * blocked session finished a request after the report arrived.
+ */
+export function buildBlockingChains(
+  reports: BlockedProcessReport[],
+  queries: QueryEvent[],
+): BlockingChainNode[] {
+  const latestBySession = new Map<number, QueryEvent>();
+  for (const q of queries) {
+    if (q.parent_id) continue;
+    const current = latestBySession.get(q.session_id);
+    if (!current || current.event_status !== "running" || q.event_status === "running") {
+      latestBySession.set(q.session_id, q);
+    }
+  }
+
+  const latestReport = new Map<number, BlockedProcessReport>();
+  for (const report of reports) {
+    if (report.parse_error) continue;
+    latestReport.set(report.blocked.session_id, report);
+  }
+
+  const blockedBy = new Map<number, number>();
+  const blockerInfo = new Map<number, BlockingProcess>();
+  for (const [sessionId, report] of latestReport) {
+    const query = latestBySession.get(sessionId);
+    const resolved =
+      query !== undefined &&
+      query.event_status !== "running" &&
+      query.captured_at > report.captured_at;
+    if (resolved) continue;
+    blockedBy.set(sessionId, report.blocker.session_id);
+    blockerInfo.set(report.blocker.session_id, report.blocker);
+  }
+
+  const children = new Map<number, number[]>();
+  for (const [blocked, blocker] of blockedBy) {
+    const list = children.get(blocker);
+    if (list) list.push(blocked);
+    else children.set(blocker, [blocked]);
+  }
+
+  const visited = new Set<number>();
+  function build(sessionId: number): BlockingChainNode {
+    visited.add(sessionId);
+    const report = latestReport.get(sessionId);
+    return {
+      sessionId,
+      process: blockedBy.has(sessionId) && report ? report.blocked : blockerInfo.get(sessionId) ?? null,
+      query: latestBySession.get(sessionId) ?? null,
+      blocked: (children.get(sessionId) ?? [])
+        .filter((id) => !visited.has(id))
+        .map(build),
+    };
+  }
+
+  // Head blockers are not blocked themselves; anything left over is a cycle.
+  const heads: BlockingChainNode[] = [];
+  for (const blocker of children.keys()) {
+    if (!blockedBy.has(blocker)) heads.push(build(blocker));
+  }
+  for (const blocker of children.keys()) {
+    if (!visited.has(blocker)) heads.push(build(blocker));
+  }
+  return heads;
+}
+
+export function countBlockedSessions(chains: BlockingChainNode[]): number {
+  return chains.reduce((sum, node) => sum + node.blocked.length + countBlockedSessions(node.blocked), 0);
+}
@@ -8,25 +8,96 @@ export interface ConnectionConfig {
   trust_cert: boolean;
 }
 
+export type LiveCaptureKind = "sql_trace" | "xe_ring_buffer" | "xe_event_file";
+
+export type CaptureSourceKind =
+  | { kind: LiveCaptureKind }
+  | { kind: "trace_file"; path: string; on_server?: boolean }
+  | { kind: "xel_file"; path: string; on_server?: boolean };
+
+export interface CaptureOptions {
+  statement_level: boolean;
+  filter: CaptureFilter;
+  files: CaptureFileOptions;
+  /** RFC 3339; the capture stops by itself at this time. */
+  stop_at: string | null;
+}
+
+/** Where SQL Trace and the XE event_file target write on the server. */
+export interface CaptureFileOptions {
+  directory: string | null;
+  max_file_mb: number | null;
+  rollover: boolean;
+  max_files: number | null;
+}
+
+export const DEFAULT_CAPTURE_FILE_OPTIONS: CaptureFileOptions = {
+  directory: null,
+  max_file_mb: null,
+  rollover: true,
+  max_files: 20,
+};
+
+export interface CaptureFilter {
+  database_name: string | null;
+  login_name: string | null;
+  host_name: string | null;
+  program_name: string | null;
+  min_duration_ms: number | null;
+  min_logical_reads: number | null;
+  text_like: string | null;
+}
+
+export const EMPTY_CAPTURE_FILTER: CaptureFilter = {
+  database_name: null,
+  login_name: null,
+  host_name: null,
+  program_name: null,
+  min_duration_ms: null,
+  min_logical_reads: null,
+  text_like: null,
+};
+
 export interface QueryEvent {
   id: string;
   session_id: number;
   start_time: string;
+  end_time: string;
   event_name: string;
   database_name: string;
+  // Microseconds.
   cpu_time: number;
   elapsed_time: number;
-  physical_reads: number;
+  cpu_time_ms: number;
+  elapsed_time_ms: number;
+  physical_reads: number | null;
   writes: number;
   logical_reads: number;
   row_count: number;
   sql_text: string;
   current_statement: string;
+  fingerprint: string;
+  query_hash: string;
   login_name: string;
   host_name: string;
   program_name: string;
+  object_name: string;
+  client_process_id: number;
+  nt_user_name: string;
+  transaction_id: number;
   captured_at: string;
-  event_status: "completed";
+  event_status: "running" | "completed" | "failed" | "cancelled" | "unknown";
+  event_sequence: number;
+  parent_id: string | null;
+  error: QueryError | null;
+  // Columns an imported file had that the profiler does not know about.
+  extra?: Record<string, unknown>;
+}
+
+export interface QueryError {
+  number: number;
+  severity: number;
+  message: string;
 }
 
 export interface QueryResultData {
@@ -38,4 +109,205 @@ export interface ProfilerStatus {
   connected: boolean;
   capturing: boolean;
   error: string | null;
+  // Set while a capture is getting a lost connection back; `error` says why it was lost.
+  reconnect_attempt: number | null;
+  // The last poll hit its row limit, so events arrive late.
+  behind: boolean;
+}
+
+export interface DeadlockFrame {
+  procedure: string;
+  line: number;
+  statement: string;
+}
+
+export interface DeadlockProcess {
+  id: string;
+  is_victim: boolean;
+  session_id: number;
+  database_name: string;
+  login_name: string;
+  host_name: string;
+  program_name: string;
+  isolation_level: string;
+  lock_mode: string;
+  wait_resource: string;
+  wait_time_ms: number;
+  transaction_name: string;
+  input_buffer: string;
+  frames: DeadlockFrame[];
+}
+
+export interface DeadlockLockHolder {
+  process_id: string;
+  mode: string;
+  request_type: string;
+}
+
+export interface DeadlockResource {
+  id: string;
+  kind: string;
+  object_name: string;
+  index_name: string;
+  mode: string;
+  owners: DeadlockLockHolder[];
+  waiters: DeadlockLockHolder[];
+}
+
+export interface DeadlockGraph {
+  id: string;
+  event_time: string;
+  captured_at: string;
+  victim_ids: string[];
+  processes: DeadlockProcess[];
+  resources: DeadlockResource[];
+  xml: string;
+}
+
+export interface BlockingProcess {
+  session_id: number;
+  status: string;
+  database_name: string;
+  login_name: string;
+  host_name: string;
+  program_name: string;
+  isolation_level: string;
+  transaction_count: number;
+  lock_mode: string;
+  wait_resource: string;
+  wait_time_ms: number;
+  input_buffer: string;
+}
+
+export interface BlockedProcessReport {
+  id: string;
+  event_time: string;
+  captured_at: string;
+  blocked: BlockingProcess;
+  blocker: BlockingProcess;
+  xml: string;
+  // Set when the XML could not be parsed; both processes are empty then.
+  parse_error?: string | null;
+}
+
+export interface MetricSummary {
+  total: number;
+  avg: number;
+  min: number;
+  max: number;
+  p50: number;
+  p95: number;
+  p99: number;
+}
+
+export interface QueryAggregate {
+  query_hash: string;
+  fingerprint: string;
+  event_name: string;
+  database_name: string;
+  sample_text: string;
+  executions: number;
+  failures: number;
+  first_seen: string;
+  last_seen: string;
+  // Microseconds.
+  duration: MetricSummary;
+  cpu_time: MetricSummary;
+  logical_reads: MetricSummary;
+  writes: MetricSummary;
+  row_count: MetricSummary;
+}
+
+export interface QueryStatsSnapshot {
+  distinct_queries: number;
+  executions: number;
+  queries: QueryAggregate[];
+}
+
+export type QueryStatsOrder =
+  | "executions"
+  | "total_duration"
+  | "avg_duration"
+  | "max_duration"
+  | "p95_duration"
+  | "total_cpu"
+  | "avg_cpu"
+  | "total_reads"
+  | "total_writes"
+  | "total_rows";
+
+export interface SessionInfo {
+  id: string;
+  name: string;
+  server_name: string;
+  source: CaptureSourceKind | { kind: "import"; path: string };
+  // Null for imported sessions.
+  options: CaptureOptions | null;
+  started_at: string;
+  // Null while the session is still being recorded.
+  stopped_at: string | null;
+  // Imported from a file rather than recorded.
+  read_only: boolean;
+  event_count: number;
+}
+
+// An opened session; only its latest query events are loaded.
+export interface SessionData {
+  session: SessionInfo;
+  window: EventWindow;
+  deadlocks: DeadlockGraph[];
+  blocked_reports: BlockedProcessReport[];
+}
+
+// What the profiler sends after each poll.
+export interface LiveBatch {
+  // New events and updates to ones in the feed, in capture order.
+  events: QueryEvent[];
+  // Events that have left the live feed; the first `dropped` of the session.
+  dropped: number;
+  // How many events the live feed keeps.
+  limit: number;
+  // The session being recorded; null for file replays.
+  session_id: string | null;
+}
+
+// A page of a recorded session's query events.
+export interface EventWindow {
+  // Events before the page.
+  offset: number;
+  events: QueryEvent[];
+  total: number;
+}
+
+export type ExportFormat = "csv" | "ndjson" | "parquet";
+
+export interface OrphanedTrace {
+  id: number;
+  path: string;
+  running: boolean;
+  start_time: string;
+  last_event_time: string | null;
+  event_count: number;
+}
+
+export interface OrphanedXeSession {
+  name: string;
+  database_scope: boolean;
+  running: boolean;
+}
+
+export interface LeftoverFile {
+  path: string;
+  size_bytes: number;
+  modified: string;
+  // Written by a trace or session that still exists on the server.
+  in_use: boolean;
+}
+
+export interface OrphanReport {
+  traces: OrphanedTrace[];
+  xe_sessions: OrphanedXeSession[];
+  files: LeftoverFile[];
+  files_error: string | null;
+  other_clients: number;
 }